@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::ai::http;
+use crate::ai::provider::{AiProvider, Completion, Request, Usage};
+
+pub const ANTHROPIC_BASE_URL: &str = "https://api.anthropic.com";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+// Anthropic Messages API
+pub struct Anthropic {
+    pub base_url: String,
+    pub api_key: String,
+    pub model: String,
+    pub settings: http::Settings,
+}
+
+#[derive(Serialize)]
+struct Message {
+    role: String,
+    content: String,
+}
+
+#[derive(Serialize)]
+struct MessagesRequest {
+    model: String,
+    system: String,
+    messages: Vec<Message>,
+    max_tokens: u32,
+    temperature: f32,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+}
+
+#[derive(Deserialize)]
+struct ContentBlock {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct TokenUsage {
+    input_tokens: u64,
+    output_tokens: u64,
+}
+
+#[derive(Deserialize)]
+struct MessagesResponse {
+    content: Vec<ContentBlock>,
+    #[serde(default)]
+    usage: Option<TokenUsage>,
+}
+
+impl AiProvider for Anthropic {
+    fn generate(&self, request: &Request) -> Result<Completion, String> {
+        let body = MessagesRequest {
+            model: self.model.clone(),
+            system: request.system.to_string(),
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: request.prompt.to_string(),
+            }],
+            max_tokens: request.max_tokens,
+            temperature: request.temperature,
+            stream: request.on_token.is_some(),
+        };
+
+        let headers = [
+            ("x-api-key", self.api_key.clone()),
+            ("anthropic-version", ANTHROPIC_VERSION.to_string()),
+            ("Content-Type", "application/json".to_string()),
+        ];
+
+        let response = http::post(
+            "Anthropic",
+            &format!("{}/v1/messages", self.base_url.trim_end_matches('/')),
+            &headers,
+            &body,
+            self.settings,
+        )?;
+
+        let (text, usage) = if let Some(on_token) = request.on_token
+            && http::is_event_stream(&response)
+        {
+            let mut text = String::new();
+            let mut input_tokens = None;
+            let mut output_tokens = None;
+
+            for line in http::lines(response) {
+                let line = line?;
+                let data = match http::sse_data(&line) {
+                    Some(d) => d,
+                    None => continue,
+                };
+
+                let event: Value = serde_json::from_str(data)
+                    .map_err(|e| format!("Failed to parse Anthropic stream: {}", e))?;
+                if let Some(e) = http::stream_error("Anthropic", &event) {
+                    return Err(e);
+                }
+                match event["type"].as_str() {
+                    // Input tokens arrive first, output tokens with the final delta
+                    Some("message_start") => {
+                        input_tokens = event["message"]["usage"]["input_tokens"].as_u64();
+                    }
+                    Some("message_delta") => {
+                        output_tokens = event["usage"]["output_tokens"].as_u64().or(output_tokens);
+                    }
+                    Some("content_block_delta") => {
+                        if let Some(token) = event["delta"]["text"].as_str() {
+                            on_token(token);
+                            text.push_str(token);
+                        }
+                    }
+                    Some("message_stop") => break,
+                    _ => {}
+                }
+            }
+
+            let usage = match (input_tokens, output_tokens) {
+                (Some(prompt_tokens), Some(completion_tokens)) => Some(Usage { prompt_tokens, completion_tokens }),
+                _ => None,
+            };
+            (text, usage)
+        } else {
+            let messages_response: MessagesResponse = response
+                .json()
+                .map_err(|e| format!("Failed to parse Anthropic response: {}", e))?;
+
+            let usage = messages_response.usage.map(|u| Usage {
+                prompt_tokens: u.input_tokens,
+                completion_tokens: u.output_tokens,
+            });
+            let text: String = messages_response
+                .content
+                .into_iter()
+                .filter(|block| block.kind == "text")
+                .map(|block| block.text)
+                .collect();
+            (text, usage)
+        };
+
+        if text.trim().is_empty() {
+            return Err("Empty response from Anthropic".to_string());
+        }
+
+        Ok(Completion { text: text.trim().to_string(), usage })
+    }
+}
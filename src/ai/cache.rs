@@ -0,0 +1,54 @@
+use std::fs;
+use serde::{Deserialize, Serialize};
+use crate::core::tracker;
+
+// Responses cached under .ark/ai_cache/<key>.json, so asking again about
+// an unchanged tree does not bill another request
+const CACHE_DIR: &str = ".ark/ai_cache";
+pub const DEFAULT_TTL_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    created_at: i64,
+    action: String,
+    response: String,
+}
+
+// Key over everything that shapes the answer: provider, model, endpoint,
+// sampling settings, template and the final prompt
+pub fn key(parts: &[&str]) -> String {
+    tracker::hash_content(parts.join("\u{0}").as_bytes())
+}
+
+fn entry_path(key: &str) -> String {
+    format!("{}/{}.json", CACHE_DIR, key)
+}
+
+pub fn get(key: &str, ttl_secs: u64) -> Option<String> {
+    let content = fs::read_to_string(entry_path(key)).ok()?;
+    let entry: Entry = serde_json::from_str(&content).ok()?;
+
+    let age = chrono::Utc::now().timestamp() - entry.created_at;
+    if age < 0 || age as u64 >= ttl_secs {
+        let _ = fs::remove_file(entry_path(key));
+        return None;
+    }
+
+    Some(entry.response)
+}
+
+pub fn put(key: &str, action: &str, response: &str) -> Result<(), String> {
+    fs::create_dir_all(CACHE_DIR)
+        .map_err(|e| format!("Failed to create AI cache directory: {}", e))?;
+
+    let entry = Entry {
+        created_at: chrono::Utc::now().timestamp(),
+        action: action.to_string(),
+        response: response.to_string(),
+    };
+    let json = serde_json::to_string_pretty(&entry)
+        .map_err(|e| format!("Failed to serialize AI cache entry: {}", e))?;
+
+    fs::write(entry_path(key), json)
+        .map_err(|e| format!("Failed to write AI cache entry: {}", e))
+}
@@ -1,31 +1,84 @@
 use std::fs;
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
+use crate::ai::{provider, credentials, http};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AiConfig {
-    pub api_key: String,
+    // groq, openai, ollama or anthropic
+    #[serde(default = "default_provider")]
+    pub provider: String,
     pub model: String,
+    // Overrides the provider's default endpoint, e.g. a self-hosted
+    // OpenAI-compatible server or a remote Ollama
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    // XOR-obfuscated key written by older versions. Read only for
+    // migration, never written back.
+    #[serde(default, rename = "api_key", skip_serializing)]
+    pub legacy_api_key: String,
+    // Request timeout in seconds, per connect and per read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    // Retries on rate limits, server errors and timeouts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+    // How long cached answers stay valid; 0 turns the cache off
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_ttl_secs: Option<u64>,
+    // Stream answers to the terminal as they arrive (default on)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<bool>,
+    // Embedding model for `ark ai ask`; keyword search only when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<String>,
+    // Per-action overrides, keyed by action name (commit, review, ...)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub actions: HashMap<String, ActionSettings>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ActionSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
 }
 
 impl Default for AiConfig {
     fn default() -> Self {
         AiConfig {
-            api_key: String::new(),
+            provider: default_provider(),
             model: "llama-3.3-70b-versatile".to_string(),
+            base_url: None,
+            legacy_api_key: String::new(),
+            timeout_secs: None,
+            max_retries: None,
+            cache_ttl_secs: None,
+            stream: None,
+            embedding_model: None,
+            actions: HashMap::new(),
         }
     }
 }
 
-// Simple XOR encryption for API key
-pub fn encrypt_key(key: &str) -> String {
-    let secret: u8 = 0x5A;
-    key.bytes()
-        .map(|b| format!("{:02x}", b ^ secret))
-        .collect::<Vec<_>>()
-        .join("")
+impl AiConfig {
+    pub fn http_settings(&self) -> http::Settings {
+        http::Settings {
+            timeout_secs: self.timeout_secs.unwrap_or(http::DEFAULT_TIMEOUT_SECS),
+            max_retries: self.max_retries.unwrap_or(http::DEFAULT_MAX_RETRIES),
+        }
+    }
+}
+
+fn default_provider() -> String {
+    "groq".to_string()
 }
 
-pub fn decrypt_key(encrypted: &str) -> String {
+// Reverse the single-byte XOR used by older versions
+fn decrypt_legacy_key(encrypted: &str) -> String {
     let secret: u8 = 0x5A;
     (0..encrypted.len())
         .step_by(2)
@@ -48,13 +101,33 @@ pub fn load_config() -> Result<AiConfig, String> {
     let content = fs::read_to_string(".ark/ai_config.json")
         .map_err(|_| "AI not configured. Run 'ark ai setup' first.".to_string())?;
 
-    serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse AI config: {}", e))
+    let mut config: AiConfig = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse AI config: {}", e))?;
+
+    if !config.legacy_api_key.is_empty() {
+        migrate_legacy_key(&mut config)?;
+    }
+
+    Ok(config)
+}
+
+// Move a key stored by older versions inside the project into the
+// encrypted per-user store, then rewrite the project config without it
+fn migrate_legacy_key(config: &mut AiConfig) -> Result<(), String> {
+    let api_key = decrypt_legacy_key(&config.legacy_api_key);
+    credentials::store_key(&config.provider, &api_key)?;
+
+    config.legacy_api_key.clear();
+    save_config(config)?;
+
+    eprintln!("Moved AI API key out of .ark/ai_config.json into {}",
+        credentials::user_config_dir()?.display());
+    Ok(())
 }
 
 pub fn is_configured() -> bool {
     if let Ok(config) = load_config() {
-        !config.api_key.is_empty()
+        !provider::requires_api_key(&config.provider) || credentials::has_key(&config.provider)
     } else {
         false
     }
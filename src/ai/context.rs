@@ -0,0 +1,146 @@
+use crate::core::diff;
+use crate::core::tracker::{FileStatus, Status};
+use crate::security::scanner;
+
+// Prompt size limits. Token counts are estimated at ~4 chars per token.
+pub const TOKENS_PER_CALL: usize = 3000;
+pub const MAX_CALLS: usize = 4;
+const CHARS_PER_TOKEN: usize = 4;
+
+pub fn estimate_tokens(text: &str) -> u64 {
+    text.len().div_ceil(CHARS_PER_TOKEN) as u64
+}
+
+// Changes split into prompt-sized parts
+pub struct DiffContext {
+    pub chunks: Vec<String>,
+    // Files (or parts of files) that did not fit, with the reason
+    pub omitted: Vec<String>,
+}
+
+struct FileEntry {
+    path: String,
+    header: String,
+    hunks: Vec<String>,
+}
+
+// Build line-level diffs for the changes and pack them into at most
+// `max_chunks` parts of `token_budget` tokens each. Every file's first
+// hunk is placed before any file's second hunk, so a large change in one
+// file cannot crowd out the others.
+pub fn build(changes: &[FileStatus], token_budget: usize, max_chunks: usize) -> DiffContext {
+    let budget = token_budget * CHARS_PER_TOKEN;
+    let files: Vec<FileEntry> = changes.iter().map(file_entry).collect();
+
+    // (file, hunk) in priority order; header-only files use hunk None
+    let mut items: Vec<(usize, Option<usize>)> = Vec::new();
+    let rounds = files.iter().map(|f| f.hunks.len()).max().unwrap_or(0).max(1);
+    for round in 0..rounds {
+        for (i, f) in files.iter().enumerate() {
+            if f.hunks.is_empty() && round == 0 {
+                items.push((i, None));
+            } else if round < f.hunks.len() {
+                items.push((i, Some(round)));
+            }
+        }
+    }
+
+    // chunk -> (file -> hunks placed in it)
+    let mut chunks: Vec<Vec<(usize, Vec<String>)>> = vec![Vec::new()];
+    let mut used = 0;
+    let mut dropped: Vec<usize> = vec![0; files.len()];
+
+    for (file_index, hunk_index) in items {
+        let file = &files[file_index];
+        let mut hunk = hunk_index.map(|h| file.hunks[h].clone()).unwrap_or_default();
+
+        let current = chunks.last().unwrap();
+        let mut has_header = current.iter().any(|(i, _)| *i == file_index);
+        let cost = hunk.len() + if has_header { 0 } else { file.header.len() + 16 };
+
+        if used + cost > budget && used > 0 {
+            if chunks.len() >= max_chunks {
+                dropped[file_index] += 1;
+                continue;
+            }
+            chunks.push(Vec::new());
+            used = 0;
+            // The file's header is repeated in the new chunk
+            has_header = false;
+        }
+
+        // A single hunk larger than a whole call is cut to fit
+        let room = budget.saturating_sub(file.header.len() + 16);
+        if hunk.len() > room {
+            hunk = truncate(&hunk, room);
+        }
+
+        let current = chunks.last_mut().unwrap();
+        match current.iter_mut().find(|(i, _)| *i == file_index) {
+            Some((_, hunks)) => hunks.push(hunk.clone()),
+            None => current.push((file_index, if hunk.is_empty() { Vec::new() } else { vec![hunk.clone()] })),
+        }
+        used += hunk.len() + if has_header { 0 } else { file.header.len() + 16 };
+    }
+
+    let rendered = chunks.into_iter().map(|mut chunk| {
+        chunk.sort_by_key(|(i, _)| *i);
+        chunk.iter().map(|(i, hunks)| {
+            if hunks.is_empty() {
+                files[*i].header.clone()
+            } else {
+                format!("{}\n```diff\n{}```", files[*i].header, hunks.concat())
+            }
+        }).collect::<Vec<_>>().join("\n\n")
+    }).collect();
+
+    let omitted = files.iter().zip(&dropped).filter(|(_, d)| **d > 0).map(|(f, d)| {
+        let total = f.hunks.len().max(1);
+        if *d >= total {
+            format!("{} (entirely)", f.path)
+        } else {
+            format!("{} ({} of {} hunks)", f.path, d, total)
+        }
+    }).collect();
+
+    DiffContext { chunks: rendered, omitted }
+}
+
+fn file_entry(change: &FileStatus) -> FileEntry {
+    let status = match change.status {
+        Status::New       => "new file",
+        Status::Modified  => "modified",
+        Status::Deleted   => "deleted",
+        Status::Unchanged => "unchanged",
+    };
+
+    // Sensitive files (.env, keys, credentials) are never read
+    if scanner::is_sensitive_file(&change.path) {
+        return FileEntry {
+            path: change.path.clone(),
+            header: format!("[{}] {} (content withheld: sensitive file)", status, change.path),
+            hunks: Vec::new(),
+        };
+    }
+
+    let file_diff = diff::file_diff(change);
+    let header = match &file_diff.note {
+        Some(note) => format!("[{}] {} ({})", status, change.path, note),
+        None => format!("[{}] {}", status, change.path),
+    };
+
+    FileEntry { path: change.path.clone(), header, hunks: file_diff.hunks }
+}
+
+fn truncate(hunk: &str, max_len: usize) -> String {
+    let mut out = String::new();
+    for line in hunk.lines() {
+        if out.len() + line.len() + 1 > max_len.saturating_sub(24) {
+            out.push_str("... (hunk truncated)\n");
+            break;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
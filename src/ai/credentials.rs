@@ -0,0 +1,208 @@
+use std::fs;
+use std::io::Write;
+use std::env;
+use std::path::PathBuf;
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+
+// API keys live in a per-user directory, never inside the project, and are
+// encrypted with XChaCha20-Poly1305. The encryption key is either derived
+// from ARK_AI_PASSPHRASE (Argon2id) or read from a machine-local key file.
+
+pub const API_KEY_ENV: &str = "ARK_AI_API_KEY";
+const PASSPHRASE_ENV: &str = "ARK_AI_PASSPHRASE";
+const CONFIG_DIR_ENV: &str = "ARK_CONFIG_DIR";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct StoredKey {
+    // "keyfile" or "argon2id"
+    kdf: String,
+    #[serde(default)]
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+pub fn user_config_dir() -> Result<PathBuf, String> {
+    if let Ok(dir) = env::var(CONFIG_DIR_ENV) {
+        return Ok(PathBuf::from(dir));
+    }
+
+    dirs::config_dir()
+        .map(|d| d.join("ark"))
+        .ok_or_else(|| "Could not determine the user config directory. Set ARK_CONFIG_DIR.".to_string())
+}
+
+fn credentials_path() -> Result<PathBuf, String> {
+    Ok(user_config_dir()?.join("credentials.json"))
+}
+
+fn key_file_path() -> Result<PathBuf, String> {
+    Ok(user_config_dir()?.join("key"))
+}
+
+// The ARK_AI_API_KEY env var always wins over the stored key
+pub fn load_key(provider: &str) -> Result<Option<String>, String> {
+    if let Ok(key) = env::var(API_KEY_ENV)
+        && !key.trim().is_empty()
+    {
+        return Ok(Some(key.trim().to_string()));
+    }
+
+    let stored = match load_all()?.remove(provider) {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+
+    let key = encryption_key(&stored.kdf, &decode_hex(&stored.salt)?, false)?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+    let nonce = decode_hex(&stored.nonce)?;
+    let ciphertext = decode_hex(&stored.ciphertext)?;
+
+    let plaintext = cipher
+        .decrypt(XNonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: provider.as_bytes() })
+        .map_err(|_| "Failed to decrypt API key. Wrong passphrase or corrupted credentials.".to_string())?;
+
+    String::from_utf8(plaintext)
+        .map(Some)
+        .map_err(|_| "Stored API key is not valid UTF-8.".to_string())
+}
+
+pub fn has_key(provider: &str) -> bool {
+    env::var(API_KEY_ENV).map(|k| !k.trim().is_empty()).unwrap_or(false)
+        || load_all().map(|all| all.contains_key(provider)).unwrap_or(false)
+}
+
+pub fn store_key(provider: &str, api_key: &str) -> Result<(), String> {
+    let (kdf, salt) = if env::var(PASSPHRASE_ENV).is_ok() {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        ("argon2id".to_string(), salt.to_vec())
+    } else {
+        ("keyfile".to_string(), Vec::new())
+    };
+
+    let key = encryption_key(&kdf, &salt, true)?;
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: api_key.as_bytes(), aad: provider.as_bytes() })
+        .map_err(|_| "Failed to encrypt API key.".to_string())?;
+
+    let mut all = load_all()?;
+    all.insert(provider.to_string(), StoredKey {
+        kdf,
+        salt: encode_hex(&salt),
+        nonce: encode_hex(&nonce),
+        ciphertext: encode_hex(&ciphertext),
+    });
+
+    let json = serde_json::to_string_pretty(&all)
+        .map_err(|e| format!("Failed to serialize credentials: {}", e))?;
+
+    write_private(&credentials_path()?, json.as_bytes())
+}
+
+fn load_all() -> Result<HashMap<String, StoredKey>, String> {
+    let path = credentials_path()?;
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read credentials: {}", e))?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse credentials: {}", e))
+}
+
+fn encryption_key(kdf: &str, salt: &[u8], create: bool) -> Result<Vec<u8>, String> {
+    match kdf {
+        "argon2id" => {
+            let passphrase = env::var(PASSPHRASE_ENV)
+                .map_err(|_| format!("API key is passphrase protected. Set {}.", PASSPHRASE_ENV))?;
+
+            let mut key = [0u8; 32];
+            argon2::Argon2::default()
+                .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+                .map_err(|e| format!("Failed to derive key: {}", e))?;
+            Ok(key.to_vec())
+        }
+        "keyfile" => {
+            let path = key_file_path()?;
+
+            if let Ok(key) = fs::read(&path) {
+                if key.len() != 32 {
+                    return Err(format!("Key file {} is corrupted.", path.display()));
+                }
+                return Ok(key);
+            }
+
+            if !create {
+                return Err(format!("Key file {} not found. Run 'ark ai setup' again.", path.display()));
+            }
+
+            let key = XChaCha20Poly1305::generate_key(&mut OsRng);
+            write_private(&path, &key)?;
+            Ok(key.to_vec())
+        }
+        other => Err(format!("Unknown key derivation '{}'.", other)),
+    }
+}
+
+// Create the file readable by the current user only. The mode is set
+// when the file is created, so the content is never readable by others.
+fn write_private(path: &PathBuf, content: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder.create(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    // A file that already existed keeps its old mode until it is reset
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to set permissions on {}: {}", path.display(), e))?;
+    }
+
+    file.write_all(content)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>, String> {
+    if !hex.len().is_multiple_of(2) {
+        return Err("Corrupted credentials: invalid hex.".to_string());
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16)
+            .map_err(|_| "Corrupted credentials: invalid hex.".to_string()))
+        .collect()
+}
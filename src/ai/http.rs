@@ -0,0 +1,165 @@
+use std::io::{BufRead, BufReader};
+use std::thread;
+use std::time::Duration;
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, Response};
+use serde::Serialize;
+use serde_json::Value;
+
+// Shared transport for all providers: timeouts, retries with exponential
+// backoff on 429/5xx and timeouts, and readable API errors.
+
+pub const DEFAULT_TIMEOUT_SECS: u64 = 60;
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+const BACKOFF_BASE_MS: u64 = 1000;
+const MAX_WAIT_SECS: u64 = 60;
+const MAX_ERROR_LEN: usize = 300;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Settings {
+    // Applies to connecting and to each read, so a long streamed answer
+    // is fine as long as tokens keep arriving
+    pub timeout_secs: u64,
+    pub max_retries: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+            max_retries: DEFAULT_MAX_RETRIES,
+        }
+    }
+}
+
+pub fn post(
+    name: &str,
+    url: &str,
+    headers: &[(&str, String)],
+    body: &impl Serialize,
+    settings: Settings,
+) -> Result<Response, String> {
+    let client = Client::builder()
+        .timeout(Duration::from_secs(settings.timeout_secs))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let mut attempt = 0;
+
+    loop {
+        let mut builder = client.post(url).json(body);
+        for (header, value) in headers {
+            builder = builder.header(*header, value);
+        }
+
+        let wait = match builder.send() {
+            Ok(response) if response.status().is_success() => return Ok(response),
+            Ok(response) => {
+                let status = response.status();
+                if !is_retryable(status) || attempt >= settings.max_retries {
+                    return Err(api_error(name, response));
+                }
+                let wait = retry_after(&response).unwrap_or_else(|| backoff(attempt));
+                eprintln!("{} returned {}, retrying in {}s ({}/{})",
+                    name, status, wait.as_secs_f32(), attempt + 1, settings.max_retries);
+                wait
+            }
+            Err(e) if e.is_timeout() => {
+                if attempt >= settings.max_retries {
+                    return Err(format!("{} did not respond within {}s", name, settings.timeout_secs));
+                }
+                let wait = backoff(attempt);
+                eprintln!("{} timed out, retrying in {}s ({}/{})",
+                    name, wait.as_secs_f32(), attempt + 1, settings.max_retries);
+                wait
+            }
+            Err(e) => return Err(format!("Failed to connect to {}: {}", name, e)),
+        };
+
+        thread::sleep(wait);
+        attempt += 1;
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn backoff(attempt: u32) -> Duration {
+    let ms = BACKOFF_BASE_MS.saturating_mul(1 << attempt.min(16));
+    Duration::from_millis(ms.min(MAX_WAIT_SECS * 1000))
+}
+
+// Retry-After is either a number of seconds or an HTTP date
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get("retry-after")?.to_str().ok()?.trim();
+
+    let secs = match value.parse::<u64>() {
+        Ok(secs) => secs,
+        Err(_) => {
+            let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+            (at.timestamp() - chrono::Utc::now().timestamp()).max(0) as u64
+        }
+    };
+
+    Some(Duration::from_secs(secs.min(MAX_WAIT_SECS)))
+}
+
+fn api_error(name: &str, response: Response) -> String {
+    let status = response.status();
+    let body = response.text().unwrap_or_default();
+    let detail = error_detail(&body);
+
+    if detail.is_empty() {
+        format!("{} API error: {}", name, status)
+    } else {
+        format!("{} API error: {}: {}", name, status, detail)
+    }
+}
+
+// Pull the message out of the usual error shapes:
+// {"error": {"message": ...}} (OpenAI, Groq, Anthropic), {"error": "..."} (Ollama)
+fn error_detail(body: &str) -> String {
+    let detail = match serde_json::from_str::<Value>(body) {
+        Ok(json) => json["error"]["message"].as_str()
+            .or_else(|| json["error"].as_str())
+            .or_else(|| json["message"].as_str())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| body.to_string()),
+        Err(_) => body.to_string(),
+    };
+
+    let detail = detail.split_whitespace().collect::<Vec<_>>().join(" ");
+    if detail.chars().count() > MAX_ERROR_LEN {
+        format!("{}...", detail.chars().take(MAX_ERROR_LEN).collect::<String>())
+    } else {
+        detail
+    }
+}
+
+// Read a streamed body line by line (SSE or newline-delimited JSON)
+pub fn lines(response: Response) -> impl Iterator<Item = Result<String, String>> {
+    BufReader::new(response)
+        .lines()
+        .map(|l| l.map_err(|e| format!("Failed to read streamed response: {}", e)))
+}
+
+// Some OpenAI-compatible servers ignore `stream` and answer in one piece
+pub fn is_event_stream(response: &Response) -> bool {
+    response.headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.starts_with("text/event-stream"))
+        .unwrap_or(false)
+}
+
+// Payload of a server-sent event `data:` line
+pub fn sse_data(line: &str) -> Option<&str> {
+    line.strip_prefix("data:").map(|d| d.trim())
+}
+
+// Streams report errors in-band once the 200 has been sent
+pub fn stream_error(name: &str, event: &Value) -> Option<String> {
+    let message = event["error"]["message"].as_str().or_else(|| event["error"].as_str())?;
+    Some(format!("{} API error: {}", name, message))
+}
@@ -1,2 +1,16 @@
 pub mod groq;
 pub mod config;
+pub mod provider;
+pub mod openai;
+pub mod ollama;
+pub mod anthropic;
+pub mod credentials;
+pub mod context;
+pub mod review;
+pub mod prompts;
+pub mod http;
+pub mod cache;
+pub mod usage;
+pub mod retrieval;
+pub mod resolve;
+pub mod split;
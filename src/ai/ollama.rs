@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::ai::http;
+use crate::ai::provider::{AiProvider, Completion, Request, Usage};
+
+pub const OLLAMA_BASE_URL: &str = "http://localhost:11434";
+
+// Local Ollama server, no API key required
+pub struct Ollama {
+    pub base_url: String,
+    pub model: String,
+    pub settings: http::Settings,
+}
+
+#[derive(Serialize)]
+struct Message {
+    role: String,
+    content: String,
+}
+
+#[derive(Serialize)]
+struct Options {
+    temperature: f32,
+    num_predict: u32,
+}
+
+#[derive(Serialize)]
+struct ChatRequest {
+    model: String,
+    messages: Vec<Message>,
+    stream: bool,
+    options: Options,
+}
+
+#[derive(Deserialize)]
+struct ResponseMessage {
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    message: ResponseMessage,
+    #[serde(default)]
+    prompt_eval_count: Option<u64>,
+    #[serde(default)]
+    eval_count: Option<u64>,
+}
+
+fn parse_usage(prompt: Option<u64>, completion: Option<u64>) -> Option<Usage> {
+    Some(Usage {
+        prompt_tokens: prompt?,
+        completion_tokens: completion?,
+    })
+}
+
+impl AiProvider for Ollama {
+    fn generate(&self, request: &Request) -> Result<Completion, String> {
+        let body = ChatRequest {
+            model: self.model.clone(),
+            messages: vec![
+                Message {
+                    role: "system".to_string(),
+                    content: request.system.to_string(),
+                },
+                Message {
+                    role: "user".to_string(),
+                    content: request.prompt.to_string(),
+                },
+            ],
+            stream: request.on_token.is_some(),
+            options: Options {
+                temperature: request.temperature,
+                num_predict: request.max_tokens,
+            },
+        };
+
+        let response = http::post(
+            "Ollama",
+            &format!("{}/api/chat", self.base_url.trim_end_matches('/')),
+            &[],
+            &body,
+            self.settings,
+        )?;
+
+        let (content, usage) = if let Some(on_token) = request.on_token {
+            // Newline-delimited JSON, one message fragment per line
+            let mut text = String::new();
+            let mut usage = None;
+
+            for line in http::lines(response) {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let event: Value = serde_json::from_str(&line)
+                    .map_err(|e| format!("Failed to parse Ollama stream: {}", e))?;
+                if let Some(e) = http::stream_error("Ollama", &event) {
+                    return Err(e);
+                }
+                if let Some(token) = event["message"]["content"].as_str() {
+                    on_token(token);
+                    text.push_str(token);
+                }
+                if event["done"].as_bool() == Some(true) {
+                    usage = parse_usage(event["prompt_eval_count"].as_u64(), event["eval_count"].as_u64());
+                    break;
+                }
+            }
+
+            (text.trim().to_string(), usage)
+        } else {
+            let chat_response: ChatResponse = response
+                .json()
+                .map_err(|e| format!("Failed to parse Ollama response: {}", e))?;
+            let usage = parse_usage(chat_response.prompt_eval_count, chat_response.eval_count);
+            (chat_response.message.content.trim().to_string(), usage)
+        };
+
+        if content.is_empty() {
+            return Err("Empty response from Ollama".to_string());
+        }
+
+        Ok(Completion { text: content, usage })
+    }
+
+    fn embed(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let response = http::post(
+            "Ollama",
+            &format!("{}/api/embed", self.base_url.trim_end_matches('/')),
+            &[],
+            &serde_json::json!({ "model": model, "input": texts }),
+            self.settings,
+        )?;
+
+        let body: Value = response
+            .json()
+            .map_err(|e| format!("Failed to parse Ollama embeddings: {}", e))?;
+
+        body["embeddings"].as_array()
+            .ok_or_else(|| "Failed to parse Ollama embeddings".to_string())?
+            .iter()
+            .map(|v| v.as_array()
+                .map(|v| v.iter().filter_map(|x| x.as_f64()).map(|x| x as f32).collect())
+                .ok_or_else(|| "Failed to parse Ollama embeddings".to_string()))
+            .collect()
+    }
+}
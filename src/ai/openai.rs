@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::ai::http;
+use crate::ai::provider::{AiProvider, Completion, Request, Usage};
+
+pub const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+// Any server implementing the OpenAI chat completions API
+pub struct OpenAiCompatible {
+    pub name: String,
+    pub base_url: String,
+    pub api_key: String,
+    pub model: String,
+    pub settings: http::Settings,
+}
+
+#[derive(Serialize)]
+struct Message {
+    role: String,
+    content: String,
+}
+
+#[derive(Serialize)]
+struct ChatRequest {
+    model: String,
+    messages: Vec<Message>,
+    max_tokens: u32,
+    temperature: f32,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    // Ask for token usage in the final streamed chunk
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: ResponseMessage,
+}
+
+#[derive(Deserialize)]
+struct ResponseMessage {
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct TokenUsage {
+    prompt_tokens: u64,
+    completion_tokens: u64,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<TokenUsage>,
+}
+
+fn parse_usage(value: &Value) -> Option<Usage> {
+    Some(Usage {
+        prompt_tokens: value["prompt_tokens"].as_u64()?,
+        completion_tokens: value["completion_tokens"].as_u64()?,
+    })
+}
+
+impl AiProvider for OpenAiCompatible {
+    fn generate(&self, request: &Request) -> Result<Completion, String> {
+        let body = ChatRequest {
+            model: self.model.clone(),
+            messages: vec![
+                Message {
+                    role: "system".to_string(),
+                    content: request.system.to_string(),
+                },
+                Message {
+                    role: "user".to_string(),
+                    content: request.prompt.to_string(),
+                },
+            ],
+            max_tokens: request.max_tokens,
+            temperature: request.temperature,
+            stream: request.on_token.is_some(),
+            stream_options: request.on_token.map(|_| serde_json::json!({ "include_usage": true })),
+        };
+
+        let mut headers = vec![("Content-Type", "application/json".to_string())];
+        if !self.api_key.is_empty() {
+            headers.push(("Authorization", format!("Bearer {}", self.api_key)));
+        }
+
+        let response = http::post(
+            &self.name,
+            &format!("{}/chat/completions", self.base_url.trim_end_matches('/')),
+            &headers,
+            &body,
+            self.settings,
+        )?;
+
+        if let Some(on_token) = request.on_token
+            && http::is_event_stream(&response)
+        {
+            let mut text = String::new();
+            let mut usage = None;
+
+            for line in http::lines(response) {
+                let line = line?;
+                let data = match http::sse_data(&line) {
+                    Some(d) => d,
+                    None => continue,
+                };
+                if data == "[DONE]" {
+                    break;
+                }
+
+                let event: Value = serde_json::from_str(data)
+                    .map_err(|e| format!("Failed to parse {} stream: {}", self.name, e))?;
+                if let Some(e) = http::stream_error(&self.name, &event) {
+                    return Err(e);
+                }
+                if let Some(token) = event["choices"][0]["delta"]["content"].as_str() {
+                    on_token(token);
+                    text.push_str(token);
+                }
+                // Groq reports streamed usage under x_groq
+                usage = parse_usage(&event["usage"])
+                    .or_else(|| parse_usage(&event["x_groq"]["usage"]))
+                    .or(usage);
+            }
+
+            if text.trim().is_empty() {
+                return Err(format!("Empty response from {}", self.name));
+            }
+            return Ok(Completion { text: text.trim().to_string(), usage });
+        }
+
+        let chat_response: ChatResponse = response
+            .json()
+            .map_err(|e| format!("Failed to parse {} response: {}", self.name, e))?;
+
+        let usage = chat_response.usage.map(|u| Usage {
+            prompt_tokens: u.prompt_tokens,
+            completion_tokens: u.completion_tokens,
+        });
+
+        chat_response
+            .choices
+            .into_iter()
+            .next()
+            .map(|c| Completion { text: c.message.content.trim().to_string(), usage })
+            .ok_or_else(|| format!("Empty response from {}", self.name))
+    }
+
+    fn embed(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let mut headers = vec![("Content-Type", "application/json".to_string())];
+        if !self.api_key.is_empty() {
+            headers.push(("Authorization", format!("Bearer {}", self.api_key)));
+        }
+
+        let response = http::post(
+            &self.name,
+            &format!("{}/embeddings", self.base_url.trim_end_matches('/')),
+            &headers,
+            &serde_json::json!({ "model": model, "input": texts }),
+            self.settings,
+        )?;
+
+        let body: Value = response
+            .json()
+            .map_err(|e| format!("Failed to parse {} embeddings: {}", self.name, e))?;
+
+        body["data"].as_array()
+            .ok_or_else(|| format!("Failed to parse {} embeddings", self.name))?
+            .iter()
+            .map(|item| item["embedding"].as_array()
+                .map(|v| v.iter().filter_map(|x| x.as_f64()).map(|x| x as f32).collect())
+                .ok_or_else(|| format!("Failed to parse {} embeddings", self.name)))
+            .collect()
+    }
+}
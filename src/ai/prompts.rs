@@ -0,0 +1,251 @@
+use std::fs;
+use std::path::Path;
+use crate::ai::{resolve, review, split};
+
+// Prompt templates for every `ark ai` action. A project can override any
+// of them with .ark/prompts/<action>.md; `ark ai prompts --init` writes the
+// defaults there as a starting point.
+//
+// Placeholders: {{diff}}, {{branch}}, {{history}}, {{files}}, {{question}},
+// {{conflict}}.
+// HTML comments are stripped before sending, so templates can carry notes.
+
+pub const PROMPTS_DIR: &str = ".ark/prompts";
+
+// Bumped whenever a default template changes, so customized copies made
+// from an older default can be flagged by `ark ai prompts`
+pub const TEMPLATE_VERSION: u32 = 1;
+
+pub const ACTIONS: &[&str] = &["commit", "review", "fix", "fix-apply", "explain", "diff", "suggest", "ask", "resolve", "changelog", "split"];
+
+pub const PLACEHOLDERS: &[&str] = &["diff", "branch", "history", "files", "question", "conflict"];
+
+const COMMIT: &str = "Generate a concise git commit message (one line, max 72 chars) following conventional commits format (feat:, fix:, chore:, docs:, refactor:, test:) based on these file changes:
+
+{{diff}}
+
+Respond with ONLY the commit message. No explanation, no quotes.
+";
+
+const REVIEW: &str = "As a senior code reviewer, review these changes for bugs, security issues, performance problems and code quality. Only report issues worth fixing:
+
+{{diff}}
+";
+
+const FIX: &str = "Analyze these code changes and suggest specific fixes or improvements:
+
+{{diff}}
+
+Be specific and practical. Format as numbered list.
+";
+
+const FIX_APPLY: &str = "Find bugs or problems in these code changes and fix them. Lines starting with '+' are the current content of the files:
+
+{{diff}}
+
+Only fix real problems.
+";
+
+const EXPLAIN: &str = "Explain what this project has been working on based on these recent commits from branch '{{branch}}':
+{{history}}
+
+Be concise and clear. Explain in simple terms what was built or changed.
+";
+
+const DIFF: &str = "Explain these code changes in simple, clear language. What was changed and why might these changes have been made?
+
+{{diff}}
+
+Be concise. Use simple language a junior developer would understand.
+";
+
+const SUGGEST: &str = "Based on this project context, suggest the next 3 best actions a developer should take:
+
+Current branch: {{branch}}
+Uncommitted changes: {{files}}
+Recent commits:
+{{history}}
+
+You MUST suggest ONLY ark commands (ark save, ark ai commit, ark ai auto, ark sync, ark branch, ark diff, etc.). NEVER suggest git commands. Format as numbered list with the exact ark command to run.
+";
+
+const ASK: &str = "Answer the question about this project's history using only the saved commits below. Cite the id of every commit you rely on in square brackets, e.g. [3f9a1c2b7d4e5f60]. If the commits do not answer the question, say so.
+
+Question: {{question}}
+
+Relevant commits:
+{{history}}
+";
+
+const RESOLVE: &str = "Resolve this merge conflict in {{files}}. Branch '{{branch}}' is merging in another branch; 'ours' is the current branch, 'theirs' the branch being merged and 'base' their common ancestor.
+
+Recent commits on both branches:
+{{history}}
+
+{{conflict}}
+
+Keep the intent of both sides where they are compatible. Keep the code style and indentation of the file.
+";
+
+const CHANGELOG: &str = "Write a changelog entry for this saved change. Its message was too vague to use: \"{{history}}\"
+
+Changed files: {{files}}
+
+{{diff}}
+
+Describe the change for someone reading the release notes, not for a developer reading the code.
+";
+
+const SPLIT: &str = "These uncommitted changes on branch '{{branch}}' mix several unrelated edits. Group the changed files into focused saves, one logical change each, with a conventional commit message (feat:, fix:, chore:, docs:, refactor:, test:) per group.
+
+Changed files: {{files}}
+
+{{diff}}
+";
+
+// Output formats Ark parses. Always appended, so a custom template
+// cannot break the response parser.
+const PATCH_INSTRUCTIONS: &str = "Respond with ONLY unified diffs against the current files: a '--- a/<path>' and '+++ b/<path>' header per file, then '@@' hunks with accurate line numbers and 3 lines of unchanged context. If nothing needs fixing, respond with NO CHANGES.";
+
+const CHANGELOG_INSTRUCTIONS: &str = "Respond with ONLY one line of the form '<type>: <description>', where type is one of feat, fix, perf, refactor, docs, deprecate, remove, security, chore. Write the description in the imperative mood, at most 72 characters, without a trailing period.";
+
+pub fn default_template(action: &str) -> Option<&'static str> {
+    match action {
+        "commit" => Some(COMMIT),
+        "review" => Some(REVIEW),
+        "fix" => Some(FIX),
+        "fix-apply" => Some(FIX_APPLY),
+        "explain" => Some(EXPLAIN),
+        "diff" => Some(DIFF),
+        "suggest" => Some(SUGGEST),
+        "ask" => Some(ASK),
+        "resolve" => Some(RESOLVE),
+        "changelog" => Some(CHANGELOG),
+        "split" => Some(SPLIT),
+        _ => None,
+    }
+}
+
+fn output_contract(action: &str) -> Option<&'static str> {
+    match action {
+        "review" => Some(review::REVIEW_INSTRUCTIONS),
+        "fix-apply" => Some(PATCH_INSTRUCTIONS),
+        "resolve" => Some(resolve::RESOLVE_INSTRUCTIONS),
+        "changelog" => Some(CHANGELOG_INSTRUCTIONS),
+        "split" => Some(split::SPLIT_INSTRUCTIONS),
+        _ => None,
+    }
+}
+
+pub fn template_path(action: &str) -> String {
+    format!("{}/{}.md", PROMPTS_DIR, action)
+}
+
+pub fn is_custom(action: &str) -> bool {
+    Path::new(&template_path(action)).exists()
+}
+
+// Version recorded in a custom template's header, if any
+pub fn custom_version(action: &str) -> Option<u32> {
+    let content = fs::read_to_string(template_path(action)).ok()?;
+    let header = content.lines().next()?;
+    header.trim()
+        .strip_prefix("<!-- ark-prompt v")?
+        .strip_suffix("-->")?
+        .trim()
+        .parse()
+        .ok()
+}
+
+pub fn load(action: &str) -> String {
+    fs::read_to_string(template_path(action))
+        .ok()
+        .or_else(|| default_template(action).map(|t| t.to_string()))
+        .unwrap_or_default()
+}
+
+// Fill in the template for an action. Unknown placeholders are left as-is.
+pub fn render(action: &str, vars: &[(&str, String)]) -> String {
+    let template = strip_comments(&load(action));
+
+    // One pass over the template, so placeholders inside a value (a diff
+    // that contains "{{files}}", say) are never expanded
+    let mut text = String::new();
+    let mut rest = template.as_str();
+    while let Some(start) = rest.find("{{") {
+        text.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let value = after.find("}}")
+            .and_then(|end| vars.iter().find(|(name, _)| *name == &after[..end]).map(|(_, v)| (end, v)));
+        match value {
+            Some((end, value)) => {
+                text.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                text.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    text.push_str(rest);
+
+    let mut text = text.trim().to_string();
+    if let Some(contract) = output_contract(action) {
+        text.push_str("\n\n");
+        text.push_str(contract);
+    }
+    text
+}
+
+// Write the default templates, keeping any that already exist.
+// Returns (written, kept).
+pub fn init() -> Result<(Vec<String>, Vec<String>), String> {
+    fs::create_dir_all(PROMPTS_DIR)
+        .map_err(|e| format!("Failed to create prompts directory: {}", e))?;
+
+    let mut written = Vec::new();
+    let mut kept = Vec::new();
+
+    for action in ACTIONS {
+        let path = template_path(action);
+        if Path::new(&path).exists() {
+            kept.push(path);
+            continue;
+        }
+
+        let content = format!(
+            "<!-- ark-prompt v{} -->\n<!-- Placeholders: {} -->\n{}",
+            TEMPLATE_VERSION,
+            PLACEHOLDERS.iter().map(|p| format!("{{{{{}}}}}", p)).collect::<Vec<_>>().join(", "),
+            default_template(action).unwrap_or_default()
+        );
+        fs::write(&path, content)
+            .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        written.push(path);
+    }
+
+    Ok((written, kept))
+}
+
+fn strip_comments(text: &str) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("<!--") {
+        result.push_str(&rest[..start]);
+        match rest[start..].find("-->") {
+            Some(end) => {
+                rest = &rest[start + end + 3..];
+                // Drop the newline after a comment on its own line
+                rest = rest.strip_prefix('\n').unwrap_or(rest);
+            }
+            None => {
+                rest = "";
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
@@ -0,0 +1,210 @@
+use crate::ai::{config, credentials, groq, openai, ollama, anthropic, cache, usage, prompts, context};
+
+pub const DEFAULT_SYSTEM_PROMPT: &str = "You are a helpful assistant for developers. Be concise and practical.";
+pub const DEFAULT_MAX_TOKENS: u32 = 500;
+pub const DEFAULT_TEMPERATURE: f32 = 0.3;
+
+pub const PROVIDERS: &[&str] = &["groq", "openai", "ollama", "anthropic"];
+
+pub struct Request<'a> {
+    pub system: &'a str,
+    pub prompt: &'a str,
+    pub max_tokens: u32,
+    pub temperature: f32,
+    // When set, the provider streams and calls this with each piece of text
+    pub on_token: Option<&'a dyn Fn(&str)>,
+}
+
+impl<'a> Request<'a> {
+    pub fn new(prompt: &'a str) -> Self {
+        Request {
+            system: DEFAULT_SYSTEM_PROMPT,
+            prompt,
+            max_tokens: DEFAULT_MAX_TOKENS,
+            temperature: DEFAULT_TEMPERATURE,
+            on_token: None,
+        }
+    }
+}
+
+// Token counts as reported by the provider
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+pub struct Completion {
+    pub text: String,
+    pub usage: Option<Usage>,
+}
+
+pub trait AiProvider {
+    fn generate(&self, request: &Request) -> Result<Completion, String>;
+
+    fn embed(&self, _model: &str, _texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        Err("This AI provider does not offer embeddings. Remove embedding_model from .ark/ai_config.json.".to_string())
+    }
+}
+
+pub fn from_config(ai_config: &config::AiConfig) -> Result<Box<dyn AiProvider>, String> {
+    let api_key = credentials::load_key(&ai_config.provider)?.unwrap_or_default();
+    if api_key.is_empty() && requires_api_key(&ai_config.provider) {
+        return Err(format!("No API key for '{}'. Run 'ark ai setup' or set {}.",
+            ai_config.provider, credentials::API_KEY_ENV));
+    }
+    let model = ai_config.model.clone();
+    let base_url = ai_config.base_url.clone();
+    let settings = ai_config.http_settings();
+
+    match ai_config.provider.as_str() {
+        "groq" => Ok(Box::new(groq::provider(api_key, model, base_url, settings))),
+        "openai" => Ok(Box::new(openai::OpenAiCompatible {
+            name: "OpenAI".to_string(),
+            base_url: base_url.unwrap_or_else(|| openai::OPENAI_BASE_URL.to_string()),
+            api_key,
+            model,
+            settings,
+        })),
+        "ollama" => Ok(Box::new(ollama::Ollama {
+            base_url: base_url.unwrap_or_else(|| ollama::OLLAMA_BASE_URL.to_string()),
+            model,
+            settings,
+        })),
+        "anthropic" => Ok(Box::new(anthropic::Anthropic {
+            base_url: base_url.unwrap_or_else(|| anthropic::ANTHROPIC_BASE_URL.to_string()),
+            api_key,
+            model,
+            settings,
+        })),
+        other => Err(format!("Unknown AI provider '{}'. Use: {}", other, PROVIDERS.join(", "))),
+    }
+}
+
+pub fn default_model(provider: &str) -> &'static str {
+    match provider {
+        "openai" => "gpt-4o-mini",
+        "ollama" => "llama3.1",
+        "anthropic" => "claude-3-5-sonnet-latest",
+        _ => "llama-3.3-70b-versatile",
+    }
+}
+
+// Local providers run without an API key
+pub fn requires_api_key(provider: &str) -> bool {
+    provider != "ollama"
+}
+
+// Structured answers need more room than a commit message
+pub fn default_max_tokens(action: &str) -> u32 {
+    match action {
+        "review" => 1500,
+        "fix-apply" => 2000,
+        _ => DEFAULT_MAX_TOKENS,
+    }
+}
+
+// Generate for an `ark ai` action, applying its overrides from AiConfig.
+// With `on_token`, the answer is streamed unless streaming is turned off.
+// Answers are cached per prompt and every call is recorded in the usage ledger.
+pub fn generate(
+    action: &str,
+    prompt: &str,
+    on_token: Option<&dyn Fn(&str)>,
+    use_cache: bool,
+) -> Result<String, String> {
+    let mut ai_config = config::load_config()?;
+    let overrides = ai_config.actions.get(action).cloned().unwrap_or_default();
+
+    if let Some(model) = overrides.model {
+        ai_config.model = model;
+    }
+
+    let request = Request {
+        max_tokens: overrides.max_tokens.unwrap_or_else(|| default_max_tokens(action)),
+        temperature: overrides.temperature.unwrap_or(DEFAULT_TEMPERATURE),
+        on_token: if ai_config.stream.unwrap_or(true) { on_token } else { None },
+        ..Request::new(prompt)
+    };
+
+    let ttl = ai_config.cache_ttl_secs.unwrap_or(cache::DEFAULT_TTL_SECS);
+    let cache_key = cache::key(&[
+        &ai_config.provider,
+        &ai_config.model,
+        ai_config.base_url.as_deref().unwrap_or_default(),
+        &request.max_tokens.to_string(),
+        &request.temperature.to_string(),
+        &prompts::load(action),
+        request.system,
+        prompt,
+    ]);
+
+    if use_cache
+        && ttl > 0
+        && let Some(text) = cache::get(&cache_key, ttl)
+    {
+        record_usage(&ai_config, action, Usage::default(), true, false);
+        return Ok(text);
+    }
+
+    let provider = from_config(&ai_config)?;
+    let completion = provider.generate(&request)?;
+
+    let (tokens, estimated) = match completion.usage {
+        Some(u) => (u, false),
+        None => (Usage {
+            prompt_tokens: context::estimate_tokens(request.system) + context::estimate_tokens(prompt),
+            completion_tokens: context::estimate_tokens(&completion.text),
+        }, true),
+    };
+    record_usage(&ai_config, action, tokens, false, estimated);
+
+    if ttl > 0
+        && let Err(e) = cache::put(&cache_key, action, &completion.text)
+    {
+        eprintln!("Warning: {}", e);
+    }
+
+    Ok(completion.text)
+}
+
+fn record_usage(ai_config: &config::AiConfig, action: &str, tokens: Usage, cached: bool, estimated: bool) {
+    let entry = usage::Entry {
+        timestamp: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        action: action.to_string(),
+        provider: ai_config.provider.clone(),
+        model: ai_config.model.clone(),
+        prompt_tokens: tokens.prompt_tokens,
+        completion_tokens: tokens.completion_tokens,
+        cached,
+        estimated,
+    };
+
+    if let Err(e) = usage::record(entry) {
+        eprintln!("Warning: {}", e);
+    }
+}
+
+pub fn embedding_model() -> Option<String> {
+    config::load_config().ok().and_then(|c| c.embedding_model)
+}
+
+pub fn embed(model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+    let ai_config = config::load_config()?;
+    let provider = from_config(&ai_config)?;
+    let vectors = provider.embed(model, texts)?;
+
+    if vectors.len() != texts.len() {
+        return Err(format!("Expected {} embeddings, got {}", texts.len(), vectors.len()));
+    }
+
+    let tokens = Usage {
+        prompt_tokens: texts.iter().map(|t| context::estimate_tokens(t)).sum(),
+        completion_tokens: 0,
+    };
+    let mut embed_config = ai_config;
+    embed_config.model = model.to_string();
+    record_usage(&embed_config, "embed", tokens, false, true);
+
+    Ok(vectors)
+}
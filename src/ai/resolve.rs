@@ -0,0 +1,34 @@
+use serde::Deserialize;
+use crate::ai::review;
+
+pub const RESOLVE_INSTRUCTIONS: &str = "Respond with ONLY a JSON object, no prose and no code fences, of the form:\n{\"resolution\": \"the lines that replace the whole conflict, without any conflict markers\", \"explanation\": \"one or two sentences on how the two sides were combined\"}";
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Proposal {
+    pub resolution: String,
+    #[serde(default)]
+    pub explanation: String,
+}
+
+// Parse and validate the model's proposed resolution for one conflict
+pub fn parse(response: &str) -> Result<Proposal, String> {
+    let json = review::extract_json(response)
+        .ok_or_else(|| "AI response did not contain a JSON resolution.".to_string())?;
+
+    let mut proposal: Proposal = serde_json::from_str(json)
+        .map_err(|e| format!("Failed to parse AI resolution: {}", e))?;
+
+    if proposal.resolution.lines().any(|l| {
+        ["<<<<<<<", "|||||||", "=======", ">>>>>>>"].iter().any(|m| l.starts_with(m))
+    }) {
+        return Err("AI resolution still contains conflict markers.".to_string());
+    }
+
+    // Conflict sections always end with a newline
+    if !proposal.resolution.is_empty() && !proposal.resolution.ends_with('\n') {
+        proposal.resolution.push('\n');
+    }
+    proposal.explanation = proposal.explanation.trim().to_string();
+
+    Ok(proposal)
+}
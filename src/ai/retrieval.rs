@@ -0,0 +1,340 @@
+use std::fs;
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use crate::ai::provider;
+use crate::core::{branch, commit, diff};
+use crate::security::scanner;
+
+// Retrieval over saved history for `ark ai ask`. Every commit's message,
+// paths and changed lines are indexed in .ark/ai_index.json; commits never
+// change once saved, so the index only grows. With `embedding_model` set in
+// the AI config, commits are also embedded and ranked by similarity.
+const INDEX_FILE: &str = ".ark/ai_index.json";
+const MESSAGE_WEIGHT: u32 = 3;
+const PATH_WEIGHT: u32 = 2;
+const MAX_HITS: usize = 5;
+const EXCERPT_LINES: usize = 12;
+const EMBED_TEXT_LEN: usize = 2000;
+const EMBED_BATCH: usize = 16;
+
+const STOPWORDS: &[&str] = &[
+    "the", "and", "for", "was", "were", "did", "does", "what", "when", "why", "how", "who",
+    "which", "where", "with", "from", "that", "this", "have", "has", "had", "are", "is",
+    "our", "we", "you", "it", "to", "of", "in", "on", "at", "by", "an", "or", "be", "do",
+    "change", "changed", "make", "made",
+];
+
+#[derive(Serialize, Deserialize, Default)]
+struct Index {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    embedding_model: Option<String>,
+    entries: HashMap<String, Entry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    terms: HashMap<String, u32>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    embedding: Option<Vec<f32>>,
+}
+
+pub struct Hit {
+    pub commit: commit::Commit,
+    pub branches: Vec<String>,
+    pub excerpts: Vec<String>,
+}
+
+// Find the commits most relevant to the question, best first
+pub fn retrieve(question: &str, use_embeddings: bool) -> Result<Vec<Hit>, String> {
+    let mut index = update_index()?;
+    let query = tokenize(question);
+
+    let mut scores = keyword_scores(&index, &query);
+
+    let model = if use_embeddings { provider::embedding_model() } else { None };
+    if let Some(model) = model {
+        embed_missing(&mut index, &model)?;
+        save_index(&index)?;
+
+        let question_vec = provider::embed(&model, &[question.to_string()])?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        for (id, entry) in &index.entries {
+            if let Some(vector) = &entry.embedding {
+                let similarity = cosine(&question_vec, vector).max(0.0);
+                *scores.entry(id.clone()).or_default() += similarity;
+            }
+        }
+    }
+
+    let mut ranked: Vec<(String, f64)> = scores.into_iter().filter(|(_, s)| *s > 0.0).collect();
+    ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then(a.0.cmp(&b.0)));
+    ranked.truncate(MAX_HITS);
+
+    let branch_map = branches_by_commit();
+    let query: HashSet<String> = query.into_iter().collect();
+
+    Ok(ranked.into_iter()
+        .filter_map(|(id, _)| commit::load_commit(&id).ok())
+        .map(|c| Hit {
+            branches: branch_map.get(&c.id).cloned().unwrap_or_default(),
+            excerpts: excerpts(&c, &query),
+            commit: c,
+        })
+        .collect())
+}
+
+fn load_index() -> Index {
+    fs::read_to_string(INDEX_FILE)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_index(index: &Index) -> Result<(), String> {
+    let json = serde_json::to_string(index)
+        .map_err(|e| format!("Failed to serialize history index: {}", e))?;
+
+    fs::write(INDEX_FILE, json)
+        .map_err(|e| format!("Failed to write history index: {}", e))
+}
+
+// Index commits saved since the last run and drop ones that are gone
+fn update_index() -> Result<Index, String> {
+    let mut index = load_index();
+    let ids = commit::all_commit_ids();
+    let mut changed = false;
+
+    for id in &ids {
+        if index.entries.contains_key(id) {
+            continue;
+        }
+        if let Ok(c) = commit::load_commit(id) {
+            index.entries.insert(id.clone(), document(&c));
+            changed = true;
+        }
+    }
+
+    let before = index.entries.len();
+    index.entries.retain(|id, _| ids.contains(id));
+    changed |= index.entries.len() != before;
+
+    if changed {
+        save_index(&index)?;
+    }
+    Ok(index)
+}
+
+fn document(c: &commit::Commit) -> Entry {
+    let mut terms: HashMap<String, u32> = HashMap::new();
+    let mut add = |text: &str, weight: u32| {
+        for term in tokenize(text) {
+            *terms.entry(term).or_default() += weight;
+        }
+    };
+
+    add(&c.message, MESSAGE_WEIGHT);
+
+    let mut text = format!("{}\n", c.message);
+
+    for change in diff::commit_changes(c) {
+        add(&change.path, PATH_WEIGHT);
+        text.push_str(&format!("{}\n", change.path));
+
+        if scanner::is_sensitive_file(&change.path) {
+            continue;
+        }
+        for line in changed_lines(&change.diff.hunks) {
+            // Keep secret values out of the index and the embedding input
+            let (line, _) = scanner::scrub_text(line);
+            add(&line, 1);
+            if text.len() < EMBED_TEXT_LEN {
+                text.push_str(&line);
+                text.push('\n');
+            }
+        }
+    }
+
+    text.truncate(text.char_indices().nth(EMBED_TEXT_LEN).map(|(i, _)| i).unwrap_or(text.len()));
+
+    Entry { terms, text, embedding: None }
+}
+
+fn changed_lines(hunks: &[String]) -> impl Iterator<Item = &str> {
+    hunks.iter()
+        .flat_map(|h| h.lines())
+        .filter(|l| (l.starts_with('+') || l.starts_with('-')) && !l.starts_with("+++") && !l.starts_with("---"))
+}
+
+fn embed_missing(index: &mut Index, model: &str) -> Result<(), String> {
+    // Vectors from another model are not comparable
+    if index.embedding_model.as_deref() != Some(model) {
+        for entry in index.entries.values_mut() {
+            entry.embedding = None;
+        }
+        index.embedding_model = Some(model.to_string());
+    }
+
+    let missing: Vec<String> = index.entries.iter()
+        .filter(|(_, e)| e.embedding.is_none())
+        .map(|(id, _)| id.clone())
+        .collect();
+
+    for batch in missing.chunks(EMBED_BATCH) {
+        let texts: Vec<String> = batch.iter().map(|id| index.entries[id].text.clone()).collect();
+        let vectors = provider::embed(model, &texts)?;
+
+        for (id, vector) in batch.iter().zip(vectors) {
+            if let Some(entry) = index.entries.get_mut(id) {
+                entry.embedding = Some(vector);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// BM25 over the weighted term counts, normalized so the best match scores 1
+fn keyword_scores(index: &Index, query: &[String]) -> HashMap<String, f64> {
+    let mut scores = HashMap::new();
+    let total = index.entries.len() as f64;
+    if total == 0.0 || query.is_empty() {
+        return scores;
+    }
+
+    let lengths: HashMap<&String, f64> = index.entries.iter()
+        .map(|(id, e)| (id, e.terms.values().sum::<u32>() as f64))
+        .collect();
+    let average = (lengths.values().sum::<f64>() / total).max(1.0);
+
+    let (k1, b) = (1.2, 0.75);
+
+    for term in query {
+        let containing = index.entries.values().filter(|e| e.terms.contains_key(term)).count() as f64;
+        if containing == 0.0 {
+            continue;
+        }
+        let idf = ((total - containing + 0.5) / (containing + 0.5) + 1.0).ln();
+
+        for (id, entry) in &index.entries {
+            if let Some(&tf) = entry.terms.get(term) {
+                let tf = tf as f64;
+                let norm = k1 * (1.0 - b + b * lengths[id] / average);
+                *scores.entry(id.clone()).or_default() += idf * tf * (k1 + 1.0) / (tf + norm);
+            }
+        }
+    }
+
+    let best = scores.values().cloned().fold(0.0, f64::max);
+    if best > 0.0 {
+        for score in scores.values_mut() {
+            *score /= best;
+        }
+    }
+    scores
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| *x as f64 * *y as f64).sum();
+    let norm_a: f64 = a.iter().map(|x| (*x as f64).powi(2)).sum::<f64>().sqrt();
+    let norm_b: f64 = b.iter().map(|x| (*x as f64).powi(2)).sum::<f64>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+fn branches_by_commit() -> HashMap<String, Vec<String>> {
+    let mut map: HashMap<String, Vec<String>> = HashMap::new();
+    for name in branch::list_branches() {
+        if let Ok(b) = branch::load_branch(&name) {
+            for id in b.commit_ids {
+                map.entry(id).or_default().push(name.clone());
+            }
+        }
+    }
+    map
+}
+
+// The changed lines of a commit that mention the question's terms, with
+// the file they belong to. Falls back to the start of the diff.
+fn excerpts(c: &commit::Commit, query: &HashSet<String>) -> Vec<String> {
+    let mut matching = Vec::new();
+    let mut fallback = Vec::new();
+
+    for change in diff::commit_changes(c) {
+        if scanner::is_sensitive_file(&change.path) {
+            continue;
+        }
+        for line in changed_lines(&change.diff.hunks) {
+            let entry = format!("{}: {}", change.path, line);
+            if tokenize(line).iter().any(|t| query.contains(t)) {
+                matching.push(entry);
+            } else if fallback.len() < EXCERPT_LINES {
+                fallback.push(entry);
+            }
+        }
+    }
+
+    let mut lines = if matching.is_empty() { fallback } else { matching };
+    lines.truncate(EXCERPT_LINES);
+    lines
+}
+
+// Lowercase words, with snake_case and camelCase identifiers also split
+// into their parts and a trailing plural "s" dropped
+fn tokenize(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+
+    for word in text.split(|c: char| !c.is_alphanumeric() && c != '_') {
+        if word.is_empty() {
+            continue;
+        }
+
+        let mut parts = vec![word.to_lowercase()];
+        let mut current = String::new();
+        let mut split = Vec::new();
+        let mut prev_lower = false;
+        for ch in word.chars() {
+            // Split on underscores and at lower-to-upper camelCase boundaries
+            if (ch == '_' || (ch.is_uppercase() && prev_lower)) && !current.is_empty() {
+                split.push(current.to_lowercase());
+                current = String::new();
+            }
+            if ch != '_' {
+                current.push(ch);
+            }
+            prev_lower = ch.is_lowercase();
+        }
+        if !current.is_empty() {
+            split.push(current.to_lowercase());
+        }
+        if split.len() > 1 {
+            parts.extend(split);
+        }
+
+        for part in parts {
+            let term = normalize(&part);
+            if term.len() >= 2 && !STOPWORDS.contains(&term.as_str()) {
+                terms.push(term);
+            }
+        }
+    }
+
+    terms
+}
+
+fn normalize(word: &str) -> String {
+    if word.len() > 3 && word.ends_with('s') && !word.ends_with("ss") {
+        word[..word.len() - 1].to_string()
+    } else {
+        word.to_string()
+    }
+}
@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+pub const SEVERITIES: &[&str] = &["low", "medium", "high", "critical"];
+pub const CATEGORIES: &[&str] = &[
+    "bug", "security", "performance", "style", "maintainability", "testing", "docs",
+];
+
+pub const REVIEW_INSTRUCTIONS: &str = "Respond with ONLY a JSON object, no prose and no code fences, of the form:\n{\"findings\": [{\"file\": \"path as shown above\", \"line_start\": 1, \"line_end\": 1, \"severity\": \"low|medium|high|critical\", \"category\": \"bug|security|performance|style|maintainability|testing|docs\", \"suggestion\": \"what to change and why\", \"patch\": \"optional unified diff\"}]}\nUse line numbers of the new version of each file. Return {\"findings\": []} if there is nothing worth reporting.";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Finding {
+    pub file: String,
+    pub line_start: usize,
+    #[serde(default)]
+    pub line_end: usize,
+    pub severity: String,
+    #[serde(default)]
+    pub category: String,
+    pub suggestion: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ReviewResponse {
+    findings: Vec<Value>,
+}
+
+pub struct Review {
+    pub findings: Vec<Finding>,
+    // Findings dropped because they failed validation
+    pub rejected: usize,
+}
+
+// Rank of a severity name, None if unknown
+pub fn severity_rank(severity: &str) -> Option<usize> {
+    SEVERITIES.iter().position(|s| *s == severity.to_lowercase())
+}
+
+// Parse and validate the model's answer. Findings must point at one of
+// the reviewed files and carry a known severity; anything else is dropped.
+pub fn parse(response: &str, files: &[String]) -> Result<Review, String> {
+    let json = extract_json(response)
+        .ok_or_else(|| "AI response did not contain a JSON review.".to_string())?;
+
+    let findings: Vec<Value> = if json.trim_start().starts_with('[') {
+        serde_json::from_str(json)
+    } else {
+        serde_json::from_str::<ReviewResponse>(json).map(|r| r.findings)
+    }.map_err(|e| format!("Failed to parse AI review: {}", e))?;
+
+    let mut review = Review { findings: Vec::new(), rejected: 0 };
+
+    for value in findings {
+        let mut finding: Finding = match serde_json::from_value(value) {
+            Ok(f) => f,
+            Err(_) => {
+                review.rejected += 1;
+                continue;
+            }
+        };
+
+        let file = normalize_path(&finding.file);
+        let known_file = files.iter().find(|f| normalize_path(f) == file);
+
+        if known_file.is_none() || finding.line_start == 0 || severity_rank(&finding.severity).is_none()
+            || finding.suggestion.trim().is_empty()
+        {
+            review.rejected += 1;
+            continue;
+        }
+
+        finding.file = file;
+        finding.severity = finding.severity.to_lowercase();
+        finding.category = finding.category.to_lowercase();
+        if !CATEGORIES.contains(&finding.category.as_str()) {
+            finding.category = "other".to_string();
+        }
+        if finding.line_end < finding.line_start {
+            finding.line_end = finding.line_start;
+        }
+        if finding.patch.as_deref().map(|p| p.trim().is_empty()).unwrap_or(false) {
+            finding.patch = None;
+        }
+
+        review.findings.push(finding);
+    }
+
+    // Most severe first, then by location
+    review.findings.sort_by(|a, b| {
+        a.file.cmp(&b.file)
+            .then(severity_rank(&b.severity).cmp(&severity_rank(&a.severity)))
+            .then(a.line_start.cmp(&b.line_start))
+    });
+
+    Ok(review)
+}
+
+pub fn to_json(findings: &[Finding]) -> String {
+    let doc = json!({
+        "tool": "ark",
+        "version": env!("CARGO_PKG_VERSION"),
+        "findings": findings,
+    });
+
+    serde_json::to_string_pretty(&doc).unwrap_or_default()
+}
+
+pub fn to_sarif(findings: &[Finding]) -> String {
+    let mut rule_ids: Vec<String> = findings.iter().map(|f| f.category.clone()).collect();
+    rule_ids.sort();
+    rule_ids.dedup();
+
+    let rules: Vec<Value> = rule_ids.iter().map(|id| json!({
+        "id": format!("review/{}", id),
+        "name": id,
+        "shortDescription": { "text": format!("AI review: {}", id) },
+    })).collect();
+
+    let results: Vec<Value> = findings.iter().map(|f| {
+        let mut result = json!({
+            "ruleId": format!("review/{}", f.category),
+            "ruleIndex": rule_ids.iter().position(|id| *id == f.category).unwrap_or(0),
+            "level": sarif_level(&f.severity),
+            "message": { "text": f.suggestion },
+            "locations": [{
+                "physicalLocation": {
+                    "artifactLocation": { "uri": f.file },
+                    "region": { "startLine": f.line_start, "endLine": f.line_end },
+                },
+            }],
+            "properties": { "severity": f.severity },
+        });
+
+        if let Some(patch) = &f.patch {
+            result["properties"]["patch"] = json!(patch);
+        }
+
+        result
+    }).collect();
+
+    let doc = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "ark-ai-review",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/sumitt-wayne/Ark",
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    });
+
+    serde_json::to_string_pretty(&doc).unwrap_or_default()
+}
+
+fn sarif_level(severity: &str) -> &'static str {
+    match severity {
+        "critical" | "high" => "error",
+        "medium" => "warning",
+        _ => "note",
+    }
+}
+
+// Models often wrap JSON in prose or code fences
+pub fn extract_json(response: &str) -> Option<&str> {
+    let start = response.find(['{', '['])?;
+    let end = response.rfind(['}', ']'])?;
+    if end < start {
+        return None;
+    }
+    Some(&response[start..=end])
+}
+
+fn normalize_path(path: &str) -> String {
+    path.trim().trim_start_matches("./").to_string()
+}
@@ -0,0 +1,121 @@
+use serde::Deserialize;
+use crate::ai::review;
+
+pub const SPLIT_INSTRUCTIONS: &str = "Respond with ONLY a JSON object, no prose and no code fences, of the form:\n{\"groups\": [{\"message\": \"conventional commit message for this group\", \"files\": [\"path as shown above\"]}]}\nPut every changed file in exactly one group. Order the groups so each save makes sense on its own, e.g. refactors before the features that use them.";
+
+// Message for changed files the model left out of every group
+pub const LEFTOVER_MESSAGE: &str = "chore: save remaining changes";
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Group {
+    pub message: String,
+    pub files: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct SplitResponse {
+    groups: Vec<Group>,
+}
+
+fn normalize(path: &str) -> String {
+    path.trim().trim_start_matches("./").to_string()
+}
+
+// Match a path from the model or the user against the changed files
+fn find<'a>(path: &str, files: &'a [String]) -> Option<&'a String> {
+    let wanted = normalize(path);
+    files.iter().find(|f| normalize(f) == wanted)
+}
+
+// Parse and validate the model's plan. Unknown files are dropped, a file
+// claimed by several groups stays in the first, and files no group
+// claimed are collected into a last group.
+pub fn parse(response: &str, files: &[String]) -> Result<Vec<Group>, String> {
+    let json = review::extract_json(response)
+        .ok_or_else(|| "AI response did not contain a JSON plan.".to_string())?;
+
+    let parsed: SplitResponse = serde_json::from_str(json)
+        .map_err(|e| format!("Failed to parse AI plan: {}", e))?;
+
+    let mut claimed: Vec<String> = Vec::new();
+    let mut groups = Vec::new();
+
+    for group in parsed.groups {
+        let mut paths = Vec::new();
+        for path in &group.files {
+            if let Some(f) = find(path, files)
+                && !claimed.contains(f)
+            {
+                claimed.push(f.clone());
+                paths.push(f.clone());
+            }
+        }
+
+        let message = group.message.lines().next().unwrap_or("").trim().to_string();
+        if paths.is_empty() || message.is_empty() {
+            continue;
+        }
+        groups.push(Group { message, files: paths });
+    }
+
+    let leftover: Vec<String> = files.iter().filter(|f| !claimed.contains(f)).cloned().collect();
+    if !leftover.is_empty() {
+        groups.push(Group { message: LEFTOVER_MESSAGE.to_string(), files: leftover });
+    }
+
+    Ok(groups)
+}
+
+// The plan as text the user can edit
+pub fn to_text(groups: &[Group]) -> String {
+    let mut text = String::from(
+        "# Each save starts with a 'message:' line followed by its files, one per line.\n\
+         # Change messages, move files between saves, or reorder the saves.\n\
+         # Files left out of every save stay unsaved. Lines starting with '#' are ignored.\n",
+    );
+
+    for group in groups {
+        text.push_str(&format!("\nmessage: {}\n", group.message));
+        for file in &group.files {
+            text.push_str(&format!("  {}\n", file));
+        }
+    }
+
+    text
+}
+
+// Read back an edited plan
+pub fn from_text(text: &str, files: &[String]) -> Result<Vec<Group>, String> {
+    let mut groups: Vec<Group> = Vec::new();
+    let mut claimed: Vec<String> = Vec::new();
+
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(message) = line.strip_prefix("message:") {
+            let message = message.trim();
+            if message.is_empty() {
+                return Err(format!("Line {}: empty message.", n + 1));
+            }
+            groups.push(Group { message: message.to_string(), files: Vec::new() });
+            continue;
+        }
+
+        let group = groups.last_mut()
+            .ok_or_else(|| format!("Line {}: '{}' comes before any 'message:' line.", n + 1, line))?;
+        let file = find(line, files)
+            .ok_or_else(|| format!("Line {}: '{}' is not a changed file.", n + 1, line))?;
+        if claimed.contains(file) {
+            return Err(format!("Line {}: '{}' is already in an earlier save.", n + 1, line));
+        }
+
+        claimed.push(file.clone());
+        group.files.push(file.clone());
+    }
+
+    groups.retain(|g| !g.files.is_empty());
+    Ok(groups)
+}
@@ -0,0 +1,66 @@
+use std::fs;
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+
+// Local ledger of AI calls and the tokens they used
+const LEDGER_FILE: &str = ".ark/ai_usage.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Entry {
+    pub timestamp: String,
+    pub action: String,
+    pub provider: String,
+    pub model: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    // Answered from .ark/ai_cache without a request
+    #[serde(default)]
+    pub cached: bool,
+    // The provider did not report usage; counted from text length
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub estimated: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Total {
+    pub calls: u64,
+    pub cached: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+pub fn load() -> Vec<Entry> {
+    fs::read_to_string(LEDGER_FILE)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+pub fn record(entry: Entry) -> Result<(), String> {
+    let mut entries = load();
+    entries.push(entry);
+
+    let json = serde_json::to_string_pretty(&entries)
+        .map_err(|e| format!("Failed to serialize AI usage: {}", e))?;
+
+    fs::write(LEDGER_FILE, json)
+        .map_err(|e| format!("Failed to write AI usage: {}", e))
+}
+
+// Totals keyed by (day, action), oldest day first
+pub fn totals(entries: &[Entry]) -> BTreeMap<(String, String), Total> {
+    let mut totals: BTreeMap<(String, String), Total> = BTreeMap::new();
+
+    for entry in entries {
+        let day = entry.timestamp.split(' ').next().unwrap_or_default().to_string();
+        let total = totals.entry((day, entry.action.clone())).or_default();
+        total.calls += 1;
+        if entry.cached {
+            total.cached += 1;
+        }
+        total.prompt_tokens += entry.prompt_tokens;
+        total.completion_tokens += entry.completion_tokens;
+    }
+
+    totals
+}
@@ -1,66 +1,136 @@
 use colored::Colorize;
-use std::io::{self, Write};
 use std::fs;
-use crate::core::{repo, tracker, commit, branch};
-use crate::ai::{groq, config};
+use std::cell::Cell;
+use std::io::{self, Write};
+use std::path::Path;
+use crate::core::{repo, tracker, commit, branch, patch, backup, merge, diff, changelog, message};
+use crate::ai::{provider, config, credentials, context, review, prompts, usage, retrieval, resolve, split};
+use crate::cli::{lint, save, scan, tag};
+use crate::security::scanner;
+
+// Flags shared by all `ark ai` actions
+#[derive(Debug, Default)]
+pub struct AiOptions {
+    // Positional arguments after the action, e.g. the question for `ask`
+    pub args: Vec<String>,
+    pub allow_secrets: bool,
+    // Print the exact prompt that would be sent, without calling the provider
+    pub show_prompt: bool,
+    // Output format for review: text, json or sarif
+    pub format: String,
+    pub min_severity: Option<String>,
+    pub fail_on: Option<String>,
+    // `ark ai setup` overrides
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub base_url: Option<String>,
+    // `ark ai fix` patch handling
+    pub apply: bool,
+    pub revert: bool,
+    pub yes: bool,
+    // `ark ai prompts --init`
+    pub init: bool,
+    // Skip .ark/ai_cache and always call the provider
+    pub no_cache: bool,
+}
 
-pub fn run(action: &str) {
+pub fn run(action: &str, options: &AiOptions) {
     if !repo::is_initialized() {
         eprintln!("{}", "Error: Not an Ark repository. Run 'ark start' first.".red().bold());
         return;
     }
 
     match action {
-        "setup"   => ai_setup(),
-        "commit"  => ai_commit(),
-        "review"  => ai_review(),
-        "fix"     => ai_fix(),
-        "auto"    => ai_auto(),
-        "explain" => ai_explain(),
-        "diff"    => ai_diff(),
-        "suggest" => ai_suggest(),
+        "setup"   => ai_setup(options),
+        "commit"  => ai_commit(options),
+        "review"  => ai_review(options),
+        "fix"     => ai_fix(options),
+        "auto"    => ai_auto(options),
+        "explain" => ai_explain(options),
+        "diff"    => ai_diff(options),
+        "suggest" => ai_suggest(options),
+        "ask"     => ai_ask(options),
+        "resolve" => ai_resolve(options),
+        "changelog" => ai_changelog(options),
+        "split"   => ai_split(options),
+        "prompts" => ai_prompts(options),
+        "usage"   => ai_usage(),
         _ => {
             eprintln!("{} Unknown action '{}'.", "Error:".red().bold(), action);
             println!();
             println!("{}", "Available commands:".bold());
-            println!("  {} {}", "ark ai setup".cyan(),   "→ Configure AI API key");
-            println!("  {} {}", "ark ai commit".cyan(),  "→ Generate smart commit message");
-            println!("  {} {}", "ark ai review".cyan(),  "→ Review your changes");
-            println!("  {} {}", "ark ai fix".cyan(),     "→ Get fix suggestions");
-            println!("  {} {}", "ark ai auto".cyan(),    "→ Auto save + push");
-            println!("  {} {}", "ark ai explain".cyan(), "→ Explain project history");
-            println!("  {} {}", "ark ai diff".cyan(),    "→ Explain current changes");
-            println!("  {} {}", "ark ai suggest".cyan(), "→ Get next step suggestions");
+            println!("  {} → Configure AI provider and API key", "ark ai setup".cyan());
+            println!("  {} → Generate smart commit message", "ark ai commit".cyan());
+            println!("  {} → Review your changes", "ark ai review".cyan());
+            println!("  {} → Get fix suggestions (--apply to patch, --revert to undo)", "ark ai fix".cyan());
+            println!("  {} → Auto save + push", "ark ai auto".cyan());
+            println!("  {} → Explain project history", "ark ai explain".cyan());
+            println!("  {} → Explain current changes", "ark ai diff".cyan());
+            println!("  {} → Get next step suggestions", "ark ai suggest".cyan());
+            println!("  {} → Ask a question about the project history", "ark ai ask \"...\"".cyan());
+            println!("  {} → Resolve merge conflicts hunk by hunk", "ark ai resolve [file]".cyan());
+            println!("  {} → Split your changes into several focused saves", "ark ai split".cyan());
+            println!("  {} → Write release notes to CHANGELOG.md", "ark ai changelog [from] [to]".cyan());
+            println!("  {} → Show prompt templates (--init to customize)", "ark ai prompts".cyan());
+            println!("  {} → Show token usage by day and command", "ark ai usage".cyan());
         }
     }
 }
 
-fn ai_setup() {
+fn ai_setup(options: &AiOptions) {
+    let provider_name = options.provider.clone().unwrap_or_else(|| "groq".to_string());
+
+    if !provider::PROVIDERS.contains(&provider_name.as_str()) {
+        eprintln!("{} Unknown provider '{}'. Use: {}",
+            "Error:".red().bold(), provider_name, provider::PROVIDERS.join(", "));
+        return;
+    }
+
     println!("{}", "Ark AI Setup".bold().underline());
-    println!("{}", "Enter your Groq API key (from console.groq.com):".dimmed());
-    print!("  API Key: ");
-    io::stdout().flush().unwrap();
+    println!("  {} {}", "provider:".dimmed(), provider_name.cyan());
 
     let mut api_key = String::new();
-    io::stdin().read_line(&mut api_key).unwrap();
-    let api_key = api_key.trim().to_string();
 
-    if api_key.is_empty() {
-        eprintln!("{}", "Error: API key cannot be empty.".red());
-        return;
+    if provider::requires_api_key(&provider_name) {
+        let hint = match provider_name.as_str() {
+            "groq" => "Enter your Groq API key (from console.groq.com):",
+            "anthropic" => "Enter your Anthropic API key (from console.anthropic.com):",
+            _ => "Enter your API key:",
+        };
+        println!("{}", hint.dimmed());
+        print!("  API Key: ");
+        io::stdout().flush().unwrap();
+
+        io::stdin().read_line(&mut api_key).unwrap();
+        api_key = api_key.trim().to_string();
+
+        if api_key.is_empty() {
+            eprintln!("{}", "Error: API key cannot be empty.".red());
+            return;
+        }
     }
 
-    let encrypted = config::encrypt_key(&api_key);
+    if !api_key.is_empty()
+        && let Err(e) = credentials::store_key(&provider_name, &api_key)
+    {
+        eprintln!("{} {}", "Error:".red().bold(), e);
+        return;
+    }
 
-    let ai_config = config::AiConfig {
-        api_key: encrypted,
-        model: "llama-3.3-70b-versatile".to_string(),
-    };
+    // Keep timeouts, streaming and per-action overrides when switching providers
+    let mut ai_config = config::load_config().unwrap_or_default();
+    ai_config.provider = provider_name.clone();
+    ai_config.model = options.model.clone()
+        .unwrap_or_else(|| provider::default_model(&provider_name).to_string());
+    ai_config.base_url = options.base_url.clone();
 
     match config::save_config(&ai_config) {
         Ok(_) => {
             println!("{}", "✓ AI configured successfully!".green().bold());
             println!("  {} {}", "model:".dimmed(), ai_config.model.cyan());
+            if let Some(url) = &ai_config.base_url {
+                println!("  {} {}", "endpoint:".dimmed(), url.cyan());
+            }
             println!();
             println!("{}", "Available commands:".dimmed());
             println!("  ark ai commit  → smart commit message");
@@ -74,10 +144,10 @@ fn ai_setup() {
     }
 }
 
-fn ai_commit() {
-    if !config::is_configured() {
-        eprintln!("{}", "Error: AI not configured. Run 'ark ai setup' first.".red());
-        return;
+fn ai_commit(options: &AiOptions) {
+    let offline = !options.show_prompt && !config::is_configured();
+    if offline {
+        eprintln!("{}", "AI not configured; generating the message from the diff instead. Run 'ark ai setup' to use AI.".yellow());
     }
 
     let changes = tracker::scan_changes();
@@ -87,58 +157,98 @@ fn ai_commit() {
         return;
     }
 
-    // Read actual file content for better commit messages
-    let mut file_contents = Vec::new();
-    for f in &changes {
-        let status = match f.status {
-            tracker::Status::New      => "added",
-            tracker::Status::Modified => "modified",
-            tracker::Status::Deleted  => "deleted",
-            tracker::Status::Unchanged => "unchanged",
-        };
+    // Refuse to save secrets unless explicitly overridden
+    let allowed_secrets = match scan::secret_gate(&save::changed_paths(&changes), options.allow_secrets) {
+        Some(findings) => findings,
+        None => return,
+    };
 
-        if f.status == tracker::Status::Deleted {
-            file_contents.push(format!("[{}] {}", status, f.path));
-        } else if let Ok(content) = fs::read_to_string(&f.path) {
-            let preview = content.chars().take(300).collect::<String>();
-            file_contents.push(format!("[{}] {}\n{}", status, f.path, preview));
-        } else {
-            file_contents.push(format!("[{}] {}", status, f.path));
-        }
-    }
+    println!("{}", "⚡ Generating commit message...".dimmed());
 
-    let prompt = format!(
-        "Generate a concise git commit message (one line, max 72 chars) following conventional commits format (feat:, fix:, chore:, docs:, refactor:, test:) based on these file changes:\n\n{}\n\nRespond with ONLY the commit message. No explanation, no quotes.",
-        file_contents.join("\n\n")
-    );
+    let message = match commit_message_or_local(&changes, offline, options) {
+        Some(m) => m,
+        None => return,
+    };
 
-    println!("{}", "⚡ Generating commit message...".dimmed());
+    println!();
+    println!("  {} {}", "→".green().bold(), message.cyan().bold());
+    println!();
 
-    match groq::generate(&prompt) {
-        Ok(message) => {
-            println!();
-            println!("  {} {}", "→".green().bold(), message.cyan().bold());
-            println!();
+    if !lint::generated_gate(&message, &branch::get_current_branch()) {
+        return;
+    }
 
-            let snapshot = tracker::build_snapshot();
-            match commit::save_commit(&message, snapshot) {
-                Ok(id) => {
-                    println!("{}", "✓ Changes saved!".green().bold());
-                    println!("  {} {}", "id:".dimmed(), id.dimmed());
-                }
-                Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
-            }
+    let snapshot = tracker::build_snapshot();
+    let commit_options = commit::CommitOptions {
+        allowed_secrets,
+        ..Default::default()
+    };
+    match commit::save_commit_with(&message, snapshot, commit_options) {
+        Ok(id) => {
+            println!("{}", "✓ Changes saved!".green().bold());
+            println!("  {} {}", "id:".dimmed(), id.dimmed());
         }
         Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
     }
 }
 
-fn ai_review() {
-    if !config::is_configured() {
+// The AI's commit message, or one generated locally from the diff when
+// AI is not configured or the request fails. None for a dry run.
+fn commit_message_or_local(changes: &[tracker::FileStatus], offline: bool, options: &AiOptions) -> Option<String> {
+    if offline {
+        return Some(message::generate(changes));
+    }
+
+    match commit_message(changes, options)? {
+        Ok(m) => Some(m),
+        Err(e) => {
+            eprintln!("{} {}", "⚠".yellow(), format!("{}; generating the message from the diff instead", e).dimmed());
+            Some(message::generate(changes))
+        }
+    }
+}
+
+// Generate a commit message. One that breaks the project's message
+// rules is sent back once with the problems listed.
+fn commit_message(changes: &[tracker::FileStatus], options: &AiOptions) -> Option<Result<String, String>> {
+    let message = match send_changes(changes, "commit", None, options)? {
+        Ok(m) => m.trim().to_string(),
+        Err(e) => return Some(Err(e)),
+    };
+
+    let problems = lint::violations(&message, &branch::get_current_branch());
+    if problems.is_empty() {
+        return Some(Ok(message));
+    }
+
+    status(options, "  Message breaks the project's message rules, asking again...");
+    let prompt = format!(
+        "This commit message breaks the project's commit message rules:\n\n{}\n\nProblems:\n- {}\n\nRespond with ONLY a corrected commit message that fixes every problem. No explanation, no quotes.",
+        message,
+        problems.join("\n- ")
+    );
+    send("commit", &prompt, None, options).map(|r| r.map(|m| m.trim().to_string()))
+}
+
+fn ai_review(options: &AiOptions) {
+    if !options.show_prompt && !config::is_configured() {
         eprintln!("{}", "Error: AI not configured. Run 'ark ai setup' first.".red());
         return;
     }
 
+    if !["text", "json", "sarif"].contains(&options.format.as_str()) {
+        eprintln!("{} Unknown format '{}'. Use: text, json, sarif", "Error:".red().bold(), options.format);
+        return;
+    }
+
+    for severity in [&options.min_severity, &options.fail_on].into_iter().flatten() {
+        if review::severity_rank(severity).is_none() {
+            eprintln!("{} Unknown severity '{}'. Use: {}",
+                "Error:".red().bold(), severity, review::SEVERITIES.join(", "));
+            return;
+        }
+    }
+
     let changes = tracker::scan_changes();
 
     if changes.is_empty() {
@@ -146,43 +256,133 @@ fn ai_review() {
         return;
     }
 
-    let mut file_contents = Vec::new();
-    for f in &changes {
-        let status = match f.status {
-            tracker::Status::New      => "new file",
-            tracker::Status::Modified => "modified",
-            tracker::Status::Deleted  => "deleted",
-            tracker::Status::Unchanged => "unchanged",
-        };
+    status(options, "⚡ Reviewing changes...");
 
-        if let Ok(content) = fs::read_to_string(&f.path) {
-            let preview = content.chars().take(400).collect::<String>();
-            file_contents.push(format!("[{}] {}\n```\n{}\n```", status, f.path, preview));
-        } else {
-            file_contents.push(format!("[{}] {}", status, f.path));
+    match request_review(&changes, options) {
+        Some(Ok(result)) => {
+            let min_rank = options.min_severity.as_deref().and_then(review::severity_rank).unwrap_or(0);
+            let shown: Vec<review::Finding> = result.findings.iter()
+                .filter(|f| review::severity_rank(&f.severity).unwrap_or(0) >= min_rank)
+                .cloned()
+                .collect();
+
+            match options.format.as_str() {
+                "json" => println!("{}", review::to_json(&shown)),
+                "sarif" => println!("{}", review::to_sarif(&shown)),
+                _ => render_review(&shown, result.rejected),
+            }
+
+            if let Some(fail_on) = &options.fail_on
+                && review_fails(&result.findings, fail_on)
+            {
+                eprintln!("{} review found issues at or above '{}' severity.", "✗ Failed:".red().bold(), fail_on);
+                std::process::exit(1);
+            }
         }
+        Some(Err(e)) => eprintln!("{} {}", "Error:".red().bold(), e),
+        None => {}
     }
+}
 
-    let prompt = format!(
-        "As a senior code reviewer, review these changes and give 3 specific, actionable suggestions. Be concise:\n\n{}\n\nFormat as numbered list. Focus on code quality, bugs, and improvements.",
-        file_contents.join("\n\n")
-    );
+// Run a structured review and return whether saving may continue.
+// Used by `ark save --fail-on`; fails closed when the review cannot run.
+pub fn review_gate(changes: &[tracker::FileStatus], fail_on: &str) -> bool {
+    if review::severity_rank(fail_on).is_none() {
+        eprintln!("{} Unknown severity '{}'. Use: {}",
+            "Error:".red().bold(), fail_on, review::SEVERITIES.join(", "));
+        return false;
+    }
+
+    if !config::is_configured() {
+        eprintln!("{}", "Error: AI not configured. Run 'ark ai setup' first.".red());
+        return false;
+    }
 
-    println!("{}", "⚡ Reviewing changes...".dimmed());
+    println!("{}", "⚡ Reviewing changes before saving...".dimmed());
 
-    match groq::generate(&prompt) {
-        Ok(review) => {
-            println!();
-            println!("{}", "Code Review:".green().bold().underline());
-            println!();
-            println!("{}", review);
+    let options = AiOptions { format: "text".to_string(), ..Default::default() };
+    match request_review(changes, &options) {
+        Some(Ok(result)) => {
+            render_review(&result.findings, result.rejected);
+            if review_fails(&result.findings, fail_on) {
+                eprintln!("{} review found issues at or above '{}' severity. Nothing saved.",
+                    "✗ Blocked:".red().bold(), fail_on);
+                return false;
+            }
+            true
         }
-        Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+        Some(Err(e)) => {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            false
+        }
+        None => false,
     }
 }
 
-fn ai_fix() {
-    if !config::is_configured() {
+fn request_review(changes: &[tracker::FileStatus], options: &AiOptions) -> Option<Result<review::Review, String>> {
+    let files: Vec<String> = changes.iter().map(|f| f.path.clone()).collect();
+
+    send_changes(changes, "review", None, options).map(|r| r.and_then(|text| review::parse(&text, &files)))
+}
+
+fn review_fails(findings: &[review::Finding], fail_on: &str) -> bool {
+    let threshold = review::severity_rank(fail_on).unwrap_or(0);
+    findings.iter().any(|f| review::severity_rank(&f.severity).unwrap_or(0) >= threshold)
+}
+
+fn render_review(findings: &[review::Finding], rejected: usize) {
+    println!();
+    println!("{}", "Code Review:".green().bold().underline());
+    println!();
+
+    if findings.is_empty() {
+        println!("{}", "✓ No issues found.".green());
+    }
+
+    let mut current_file = "";
+    for f in findings {
+        if f.file != current_file {
+            if !current_file.is_empty() {
+                println!();
+            }
+            println!("  {}", f.file.yellow().bold());
+            current_file = &f.file;
+        }
+
+        let severity = match f.severity.as_str() {
+            "critical" => f.severity.to_uppercase().red().bold(),
+            "high"     => f.severity.to_uppercase().red(),
+            "medium"   => f.severity.to_uppercase().yellow(),
+            _          => f.severity.to_uppercase().dimmed(),
+        };
+        let lines = if f.line_end > f.line_start {
+            format!("L{}-{}", f.line_start, f.line_end)
+        } else {
+            format!("L{}", f.line_start)
+        };
+
+        println!("    {} {} {} {}", "●".cyan(), severity, lines.cyan(), f.category.dimmed());
+        println!("      {}", f.suggestion);
+        if let Some(patch) = &f.patch {
+            for line in patch.lines() {
+                println!("      {}", line.dimmed());
+            }
+        }
+    }
+
+    if rejected > 0 {
+        println!();
+        println!("{}", format!("({} malformed finding(s) ignored)", rejected).dimmed());
+    }
+}
+
+fn ai_fix(options: &AiOptions) {
+    if options.revert {
+        revert_fix();
+        return;
+    }
+
+    if !options.show_prompt && !config::is_configured() {
         eprintln!("{}", "Error: AI not configured. Run 'ark ai setup' first.".red());
         return;
     }
@@ -194,44 +394,209 @@ fn ai_fix() {
         return;
     }
 
-    let mut file_contents = Vec::new();
-    for f in &changes {
-        if let Ok(content) = fs::read_to_string(&f.path) {
-            let status = match f.status {
-                tracker::Status::New      => "new file",
-                tracker::Status::Modified => "modified",
-                tracker::Status::Deleted  => "deleted",
-                tracker::Status::Unchanged => "unchanged",
-            };
-            let preview = content.chars().take(500).collect::<String>();
-            file_contents.push(format!("[{}] {}\n```\n{}\n```", status, f.path, preview));
+    if options.apply {
+        println!("{}", "⚡ Generating patches...".dimmed());
+
+        match send_changes(&changes, "fix-apply", None, options) {
+            Some(Ok(response)) => apply_fix(&response, options),
+            Some(Err(e)) => eprintln!("{} {}", "Error:".red().bold(), e),
+            None => {}
         }
+        return;
     }
 
-    let prompt = format!(
-        "Analyze these code changes and suggest specific fixes or improvements:\n\n{}\n\nBe specific and practical. Format as numbered list.",
-        file_contents.join("\n\n")
-    );
-
     println!("{}", "⚡ Analyzing for fixes...".dimmed());
 
-    match groq::generate(&prompt) {
-        Ok(fix) => {
-            println!();
-            println!("{}", "Fix Suggestions:".green().bold().underline());
+    match send_changes(&changes, "fix", Some("Fix Suggestions:"), options) {
+        Some(Ok(_)) => {
             println!();
-            println!("{}", fix);
+            println!("{}", "Run 'ark ai fix --apply' to get these as patches.".dimmed());
         }
-        Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+        Some(Err(e)) => eprintln!("{} {}", "Error:".red().bold(), e),
+        None => {}
     }
 }
 
-fn ai_auto() {
-    if !config::is_configured() {
-        eprintln!("{}", "Error: AI not configured. Run 'ark ai setup' first.".red());
+// Validate the model's patches against the working tree, ask per hunk,
+// back up the originals and write the result
+fn apply_fix(response: &str, options: &AiOptions) {
+    let patches = patch::parse(response);
+
+    if patches.is_empty() {
+        println!("{}", "No fixes proposed.".yellow());
+        return;
+    }
+
+    let mut updates: Vec<(String, String)> = Vec::new();
+    let mut applied = 0;
+    let mut skipped = 0;
+    let mut quit = false;
+
+    for file in &patches {
+        if quit {
+            break;
+        }
+
+        println!();
+        println!("{}", file.path.yellow().bold());
+
+        if !patch::is_safe_path(&file.path) {
+            println!("  {} path is outside the project, skipped", "✗".red());
+            skipped += file.hunks.len();
+            continue;
+        }
+
+        let exists = Path::new(&file.path).exists();
+        if exists == file.is_new {
+            let reason = if exists { "file already exists" } else { "file not found" };
+            println!("  {} {}, skipped", "✗".red(), reason);
+            skipped += file.hunks.len();
+            continue;
+        }
+
+        let current = if exists {
+            match fs::read_to_string(&file.path) {
+                Ok(c) => c,
+                Err(_) => {
+                    println!("  {} not a text file, skipped", "✗".red());
+                    skipped += file.hunks.len();
+                    continue;
+                }
+            }
+        } else {
+            String::new()
+        };
+        let lines: Vec<&str> = current.lines().collect();
+
+        let mut chosen = Vec::new();
+        for (i, hunk) in file.hunks.iter().enumerate() {
+            let label = format!("hunk {}/{}", i + 1, file.hunks.len());
+
+            // Writing a placeholder back would destroy the real value
+            if hunk.lines.iter().any(|l| l.contains("[REDACTED:")) {
+                println!("  {} {} touches redacted content, skipped", "✗".red(), label);
+                skipped += 1;
+                continue;
+            }
+
+            let start = match patch::locate(&lines, hunk) {
+                Some(s) => s,
+                None => {
+                    println!("  {} {} does not match the current file, skipped", "✗".red(), label);
+                    skipped += 1;
+                    continue;
+                }
+            };
+
+            println!("  {}", format!("@@ {} at line {} @@", label, start + 1).cyan());
+            for line in &hunk.lines {
+                let shown = format!("  {}", line);
+                match line.chars().next() {
+                    Some('+') => println!("{}", shown.green()),
+                    Some('-') => println!("{}", shown.red()),
+                    _ => println!("{}", shown.dimmed()),
+                }
+            }
+
+            if !options.yes {
+                print!("{}", "  Apply this hunk? (y/n/q): ".yellow().bold());
+                io::stdout().flush().unwrap();
+
+                let mut input = String::new();
+                io::stdin().read_line(&mut input).unwrap();
+
+                match input.trim().to_lowercase().as_str() {
+                    "y" => {}
+                    "q" => {
+                        skipped += file.hunks.len() - i;
+                        quit = true;
+                        break;
+                    }
+                    _ => {
+                        skipped += 1;
+                        continue;
+                    }
+                }
+            }
+
+            chosen.push((hunk.clone(), start));
+        }
+
+        if chosen.is_empty() {
+            continue;
+        }
+
+        match patch::apply(&current, &chosen) {
+            Ok(content) => {
+                applied += chosen.len();
+                updates.push((file.path.clone(), content));
+            }
+            Err(e) => {
+                println!("  {} {} skipped", "✗".red(), e);
+                skipped += chosen.len();
+            }
+        }
+    }
+
+    println!();
+
+    if updates.is_empty() {
+        println!("{}", "No changes applied.".yellow());
         return;
     }
 
+    let paths: Vec<String> = updates.iter().map(|(p, _)| p.clone()).collect();
+    let saved = match backup::create("ai fix", &paths) {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            return;
+        }
+    };
+
+    for (path, content) in &updates {
+        if let Some(parent) = Path::new(path).parent()
+            && !parent.as_os_str().is_empty()
+        {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Err(e) = fs::write(path, content) {
+            eprintln!("{} Failed to write {}: {}", "Error:".red().bold(), path, e);
+            eprintln!("{}", "Run 'ark ai fix --revert' to restore the original files.".yellow());
+            return;
+        }
+    }
+
+    println!("{} {} hunk(s) to {} file(s)",
+        "✓ Applied".green().bold(), applied.to_string().cyan(), updates.len().to_string().cyan());
+    if skipped > 0 {
+        println!("  {} {} hunk(s)", "skipped:".dimmed(), skipped.to_string().yellow());
+    }
+    println!("  {} #{}", "backup:".dimmed(), saved.id.to_string().cyan());
+    println!("{}", "Undo with: ark ai fix --revert".dimmed());
+}
+
+fn revert_fix() {
+    match backup::restore_latest() {
+        Ok(Some(b)) => {
+            println!("{} {} from {}", "✓ Reverted".green().bold(), b.message.cyan(), b.timestamp.dimmed());
+            let mut paths: Vec<&String> = b.files.keys().collect();
+            paths.sort();
+            for path in paths {
+                println!("  {} {}", "restored:".dimmed(), path.yellow());
+            }
+        }
+        Ok(None) => println!("{}", "No applied fixes to revert.".yellow()),
+        Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+    }
+}
+
+fn ai_auto(options: &AiOptions) {
+    let offline = !options.show_prompt && !config::is_configured();
+    if offline {
+        eprintln!("{}", "AI not configured; generating the message from the diff instead. Run 'ark ai setup' to use AI.".yellow());
+    }
+
     let changes = tracker::scan_changes();
 
     if changes.is_empty() {
@@ -242,6 +607,12 @@ fn ai_auto() {
     println!("{}", "⚡ AI Auto Mode".bold());
     println!();
 
+    // Refuse to save secrets unless explicitly overridden
+    let allowed_secrets = match scan::secret_gate(&save::changed_paths(&changes), options.allow_secrets) {
+        Some(findings) => findings,
+        None => return,
+    };
+
     // Show changes summary
     let new_count = changes.iter().filter(|f| f.status == tracker::Status::New).count();
     let mod_count = changes.iter().filter(|f| f.status == tracker::Status::Modified).count();
@@ -254,62 +625,179 @@ fn ai_auto() {
     );
     println!();
 
-    // Read file content for better message
-    let mut file_contents = Vec::new();
-    for f in &changes {
-        let status = match f.status {
-            tracker::Status::New      => "added",
-            tracker::Status::Modified => "modified",
-            tracker::Status::Deleted  => "deleted",
-            tracker::Status::Unchanged => "unchanged",
-        };
-        if let Ok(content) = fs::read_to_string(&f.path) {
-            let preview = content.chars().take(300).collect::<String>();
-            file_contents.push(format!("[{}] {}\n{}", status, f.path, preview));
-        } else {
-            file_contents.push(format!("[{}] {}", status, f.path));
+    println!("{}", "  Generating message...".dimmed());
+
+    let message = match commit_message_or_local(&changes, offline, options) {
+        Some(m) => m,
+        None => return,
+    };
+
+    println!("  {} {}", "→".green().bold(), message.cyan().bold());
+    println!();
+
+    if !lint::message_gate(&message, &branch::get_current_branch()) {
+        return;
+    }
+
+    print!("{}", "  Save and push? (y/n): ".yellow().bold());
+    io::stdout().flush().unwrap();
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap();
+
+    if input.trim().to_lowercase() != "y" {
+        println!("{}", "  Cancelled.".yellow());
+        return;
+    }
+
+    println!();
+    let snapshot = tracker::build_snapshot();
+    let commit_options = commit::CommitOptions { allowed_secrets, ..Default::default() };
+    match commit::save_commit_with(&message, snapshot, commit_options) {
+        Ok(id) => {
+            println!("  {} {}", "✓ Saved:".green(), id.dimmed());
+            crate::cli::sync::run(options.allow_secrets, None);
         }
+        Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
     }
+}
 
-    let prompt = format!(
-        "Generate a concise git commit message (one line, max 72 chars) following conventional commits format for these changes:\n\n{}\n\nRespond with ONLY the commit message. No explanation, no quotes.",
-        file_contents.join("\n\n")
-    );
+fn ai_split(options: &AiOptions) {
+    if !options.show_prompt && !config::is_configured() {
+        eprintln!("{}", "Error: AI not configured. Run 'ark ai setup' first.".red());
+        return;
+    }
 
-    println!("{}", "  Generating message...".dimmed());
+    let changes = tracker::scan_changes();
 
-    match groq::generate(&prompt) {
-        Ok(message) => {
-            println!("  {} {}", "→".green().bold(), message.cyan().bold());
-            println!();
+    if changes.is_empty() {
+        println!("{}", "No changes to split.".yellow());
+        return;
+    }
 
-            print!("{}", "  Save and push? (y/n): ".yellow().bold());
-            io::stdout().flush().unwrap();
+    let files: Vec<String> = changes.iter().map(|f| f.path.clone()).collect();
 
-            let mut input = String::new();
-            io::stdin().read_line(&mut input).unwrap();
+    println!("{}", "⚡ Grouping changes...".dimmed());
 
-            if input.trim().to_lowercase() != "y" {
-                println!("{}", "  Cancelled.".yellow());
+    let mut groups = match send_changes(&changes, "split", None, options) {
+        Some(Ok(response)) => match split::parse(&response, &files) {
+            Ok(g) => g,
+            Err(e) => {
+                eprintln!("{} {}", "Error:".red().bold(), e);
                 return;
             }
+        },
+        Some(Err(e)) => {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            return;
+        }
+        None => return,
+    };
 
+    // Show the plan until it is accepted, edited or cancelled
+    loop {
+        println!();
+        println!("{}", "Proposed saves:".bold().underline());
+        for (i, group) in groups.iter().enumerate() {
+            println!();
+            println!("  {} {}", format!("{}.", i + 1).dimmed(), group.message.cyan().bold());
+            for file in &group.files {
+                println!("     {}", file.yellow());
+            }
+            for problem in lint::violations(&group.message, &branch::get_current_branch()) {
+                println!("     {} {}", "✗".red(), problem.red());
+            }
+        }
+        let planned: usize = groups.iter().map(|g| g.files.len()).sum();
+        if planned < files.len() {
             println!();
-            let snapshot = tracker::build_snapshot();
-            match commit::save_commit(&message, snapshot) {
-                Ok(id) => {
-                    println!("  {} {}", "✓ Saved:".green(), id.dimmed());
-                    crate::cli::sync::run();
+            println!("  {} {} file(s) stay unsaved", "note:".dimmed(), (files.len() - planned).to_string().yellow());
+        }
+        println!();
+
+        if groups.is_empty() {
+            println!("{}", "Nothing to save.".yellow());
+            return;
+        }
+
+        // Messages must follow the project's rules before anything is saved
+        let rejected = groups.iter().any(|g| !lint::violations(&g.message, &branch::get_current_branch()).is_empty());
+
+        if options.yes && rejected {
+            eprintln!("{} Some messages break the project's message rules. Run without --yes to edit the plan.", "Error:".red().bold());
+            return;
+        }
+
+        if options.yes {
+            break;
+        }
+
+        print!("{}", "  Create these saves? (y)es / (e)dit plan / (n)o: ".yellow().bold());
+        io::stdout().flush().unwrap();
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input).unwrap();
+
+        match input.trim().to_lowercase().as_str() {
+            "y" if rejected => eprintln!("  {} Fix the messages marked ✗ first.", "✗".red()),
+            "y" => break,
+            "e" => {
+                match edit_text(&split::to_text(&groups)).and_then(|text| split::from_text(&text, &files)) {
+                    Ok(g) => groups = g,
+                    Err(e) => eprintln!("  {} {}", "✗".red(), e),
                 }
-                Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+            }
+            _ => {
+                println!("{}", "  Cancelled.".yellow());
+                return;
             }
         }
-        Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
     }
+
+    // Refuse to save secrets unless explicitly overridden
+    let planned: Vec<tracker::FileStatus> = changes.iter()
+        .filter(|c| groups.iter().any(|g| g.files.contains(&c.path)))
+        .cloned()
+        .collect();
+    let allowed_secrets = match scan::secret_gate(&save::changed_paths(&planned), options.allow_secrets) {
+        Some(findings) => findings,
+        None => return,
+    };
+
+    // Each save adds only its own files on top of the one before
+    let current = tracker::build_snapshot();
+    for group in &groups {
+        let mut snapshot = tracker::load_snapshot();
+        for file in &group.files {
+            match current.get(file) {
+                Some(hash) => snapshot.insert(file.clone(), hash.clone()),
+                None => snapshot.remove(file),
+            };
+        }
+
+        let commit_options = commit::CommitOptions {
+            allowed_secrets: allowed_secrets.iter()
+                .filter(|f| group.files.iter().any(|p| f.starts_with(&format!("{}:", p))))
+                .cloned()
+                .collect(),
+            ..Default::default()
+        };
+
+        match commit::save_commit_with(&group.message, snapshot, commit_options) {
+            Ok(id) => println!("  {} {} {}", "✓ Saved:".green(), id.dimmed(), group.message.cyan()),
+            Err(e) => {
+                eprintln!("{} {}", "Error:".red().bold(), e);
+                return;
+            }
+        }
+    }
+
+    println!();
+    println!("{} {} save(s)", "✓ Created".green().bold(), groups.len().to_string().cyan());
 }
 
-fn ai_explain() {
-    if !config::is_configured() {
+fn ai_explain(options: &AiOptions) {
+    if !options.show_prompt && !config::is_configured() {
         eprintln!("{}", "Error: AI not configured. Run 'ark ai setup' first.".red());
         return;
     }
@@ -321,34 +809,19 @@ fn ai_explain() {
         return;
     }
 
-    let current = branch::get_current_branch();
-    let recent: Vec<String> = history.iter().rev().take(5).filter_map(|id| {
-        commit::load_commit(id).ok().map(|c| {
-            format!("- {} ({})", c.message, c.timestamp)
-        })
-    }).collect();
-
-    let prompt = format!(
-        "Explain what this project has been working on based on these recent commits from branch '{}':\n{}\n\nBe concise and clear. Explain in simple terms what was built or changed.",
-        current,
-        recent.join("\n")
-    );
+    let prompt = prompts::render("explain", &template_vars(&tracker::scan_changes()));
 
     println!("{}", "⚡ Analyzing project history...".dimmed());
 
-    match groq::generate(&prompt) {
-        Ok(explanation) => {
-            println!();
-            println!("{}", "Project Summary:".green().bold().underline());
-            println!();
-            println!("{}", explanation);
-        }
-        Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+    match send("explain", &prompt, Some("Project Summary:"), options) {
+        Some(Ok(_)) => {}
+        Some(Err(e)) => eprintln!("{} {}", "Error:".red().bold(), e),
+        None => {}
     }
 }
 
-fn ai_diff() {
-    if !config::is_configured() {
+fn ai_diff(options: &AiOptions) {
+    if !options.show_prompt && !config::is_configured() {
         eprintln!("{}", "Error: AI not configured. Run 'ark ai setup' first.".red());
         return;
     }
@@ -360,53 +833,563 @@ fn ai_diff() {
         return;
     }
 
-    let mut file_contents = Vec::new();
-    for f in &changes {
-        let status = match f.status {
-            tracker::Status::New      => "new file",
-            tracker::Status::Modified => "modified",
-            tracker::Status::Deleted  => "deleted",
-            tracker::Status::Unchanged => "unchanged",
-        };
+    println!("{}", "⚡ Explaining changes...".dimmed());
 
-        if let Ok(content) = fs::read_to_string(&f.path) {
-            let preview = content.chars().take(400).collect::<String>();
-            file_contents.push(format!("[{}] {}\n```\n{}\n```", status, f.path, preview));
-        } else {
-            file_contents.push(format!("[{}] {}", status, f.path));
+    match send_changes(&changes, "diff", Some("Change Explanation:"), options) {
+        Some(Ok(_)) => {}
+        Some(Err(e)) => eprintln!("{} {}", "Error:".red().bold(), e),
+        None => {}
+    }
+}
+
+fn ai_suggest(options: &AiOptions) {
+    if !options.show_prompt && !config::is_configured() {
+        eprintln!("{}", "Error: AI not configured. Run 'ark ai setup' first.".red());
+        return;
+    }
+
+    let prompt = prompts::render("suggest", &template_vars(&tracker::scan_changes()));
+
+    println!("{}", "⚡ Analyzing project state...".dimmed());
+
+    match send("suggest", &prompt, Some("Suggested Next Steps:"), options) {
+        Some(Ok(_)) => {}
+        Some(Err(e)) => eprintln!("{} {}", "Error:".red().bold(), e),
+        None => {}
+    }
+}
+
+fn ai_ask(options: &AiOptions) {
+    if !options.show_prompt && !config::is_configured() {
+        eprintln!("{}", "Error: AI not configured. Run 'ark ai setup' first.".red());
+        return;
+    }
+
+    let question = options.args.join(" ");
+    if question.trim().is_empty() {
+        eprintln!("{} Ask a question, e.g. ark ai ask \"when did we change the auth timeout and why?\"",
+            "Error:".red().bold());
+        return;
+    }
+
+    if commit::all_commit_ids().is_empty() {
+        println!("{}", "No saves to search yet.".yellow());
+        return;
+    }
+
+    println!("{}", "⚡ Searching history...".dimmed());
+
+    // Embedding the question is a provider call, so a dry run sticks to keywords
+    let hits = match retrieval::retrieve(&question, !options.show_prompt) {
+        Ok(h) => h,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            return;
         }
+    };
+
+    if hits.is_empty() {
+        println!("{}", "No saves match the question. Try other words.".yellow());
+        return;
     }
 
-    let prompt = format!(
-        "Explain these code changes in simple, clear language. What was changed and why might these changes have been made?\n\n{}\n\nBe concise. Use simple language a junior developer would understand.",
-        file_contents.join("\n\n")
-    );
+    let context: Vec<String> = hits.iter().map(|h| {
+        let mut block = format!("[{}] {} ({}, branch: {})\n",
+            h.commit.id, h.commit.message, h.commit.timestamp, h.branches.join(", "));
+        for line in &h.excerpts {
+            block.push_str(&format!("    {}\n", line));
+        }
+        block
+    }).collect();
 
-    println!("{}", "⚡ Explaining changes...".dimmed());
+    let mut vars = template_vars(&tracker::scan_changes());
+    vars.retain(|(name, _)| *name != "history");
+    vars.push(("history", context.join("\n")));
+    vars.push(("question", question));
+    let prompt = prompts::render("ask", &vars);
 
-    match groq::generate(&prompt) {
-        Ok(explanation) => {
+    match send("ask", &prompt, Some("Answer:"), options) {
+        Some(Ok(answer)) => {
             println!();
-            println!("{}", "Change Explanation:".green().bold().underline());
-            println!();
-            println!("{}", explanation);
+            println!("{}", "Sources:".bold());
+            for h in &hits {
+                let short = &h.commit.id[..h.commit.id.len().min(8)];
+                let mark = if answer.contains(short) { "✓".green() } else { "·".dimmed() };
+                println!("  {} {} {} {}", mark, h.commit.id.cyan(), h.commit.timestamp.dimmed(), h.commit.message);
+            }
         }
-        Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+        Some(Err(e)) => eprintln!("{} {}", "Error:".red().bold(), e),
+        None => {}
     }
 }
 
-fn ai_suggest() {
-    if !config::is_configured() {
+fn ai_resolve(options: &AiOptions) {
+    if !options.show_prompt && !config::is_configured() {
         eprintln!("{}", "Error: AI not configured. Run 'ark ai setup' first.".red());
         return;
     }
 
-    let changes = tracker::scan_changes();
-    let history = commit::load_history();
-    let current_branch = branch::get_current_branch();
+    let state = match merge::load_state() {
+        Some(s) => s,
+        None => {
+            eprintln!("{} No merge in progress. Conflicts come from 'ark merge <branch>'.", "Error:".red().bold());
+            return;
+        }
+    };
+
+    let files: Vec<&String> = match options.args.first() {
+        Some(file) => {
+            let wanted = file.trim_start_matches("./");
+            let found: Vec<&String> = state.conflicts.iter()
+                .filter(|p| p.trim_start_matches("./") == wanted)
+                .collect();
+            if found.is_empty() {
+                eprintln!("{} '{}' has no merge conflicts.", "Error:".red().bold(), file);
+                return;
+            }
+            found
+        }
+        None => state.conflicts.iter().collect(),
+    };
+
+    // Recent messages from both sides tell the model what each was after
+    let mut history = Vec::new();
+    for name in [&state.into, &state.branch] {
+        let ids = branch::load_branch(name).map(|b| b.commit_ids).unwrap_or_default();
+        for id in ids.iter().rev().take(5) {
+            if let Ok(c) = commit::load_commit(id) {
+                history.push(format!("- [{}] {} ({})", name, c.message, c.timestamp));
+            }
+        }
+    }
+    let history = if history.is_empty() { "none".to_string() } else { history.join("\n") };
+
+    for path in files {
+        // Sensitive files (.env, keys, credentials) are never sent
+        if scanner::is_sensitive_file(path) {
+            println!("{} {} {}", "⚠".yellow(), path.yellow(), "is a sensitive file, resolve manually".dimmed());
+            continue;
+        }
+
+        let content = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(_) => {
+                println!("{} {} {}", "⚠".yellow(), path.yellow(), "is not a readable text file, skipped".dimmed());
+                continue;
+            }
+        };
+
+        if !resolve_file(path, &content, &history, &state, options) {
+            return;
+        }
+    }
+
+    if options.show_prompt {
+        return;
+    }
+
+    println!();
+    let unresolved = state.conflicts.iter()
+        .filter(|p| fs::read_to_string(p).map(|c| merge::has_markers(&c)).unwrap_or(false))
+        .count();
+    if unresolved == 0 {
+        println!("{}", "✓ All conflicts resolved. Run 'ark merge --continue' to finish the merge.".green().bold());
+    } else {
+        println!("{} {} file(s) still have conflicts. Resolve them, then run 'ark merge --continue'.",
+            "⚠".yellow(), unresolved.to_string().yellow());
+    }
+}
+
+// Propose a resolution for every conflict in one file and let the user
+// accept, edit or reject each. Returns false when the run should stop.
+fn resolve_file(path: &str, content: &str, history: &str, state: &merge::MergeState, options: &AiOptions) -> bool {
+    let segments = merge::parse_conflicts(content);
+    let total = segments.iter().filter(|s| matches!(s, merge::Segment::Conflict { .. })).count();
+
+    println!();
+    println!("{}", path.yellow().bold());
+
+    if total == 0 {
+        println!("  {} no conflict markers left", "✓".green());
+        return true;
+    }
+
+    let mut output = String::new();
+    let mut left = 0;
+    let mut changed = false;
+    let mut n = 0;
+
+    for (i, segment) in segments.iter().enumerate() {
+        let (ours, base, theirs, raw) = match segment {
+            merge::Segment::Text(text) => {
+                output.push_str(text);
+                continue;
+            }
+            merge::Segment::Conflict { ours, base, theirs, raw } => (ours, base, theirs, raw),
+        };
+        n += 1;
+        let label = format!("conflict {}/{}", n, total);
+
+        let before = match i.checked_sub(1).and_then(|p| segments.get(p)) {
+            Some(merge::Segment::Text(t)) => t.lines().rev().take(5).collect::<Vec<_>>().into_iter().rev().collect::<Vec<_>>().join("\n"),
+            _ => String::new(),
+        };
+        let after = match segments.get(i + 1) {
+            Some(merge::Segment::Text(t)) => t.lines().take(5).collect::<Vec<_>>().join("\n"),
+            _ => String::new(),
+        };
+        let conflict = format!(
+            "Lines before the conflict:\n{}\n\nOurs ({}):\n{}\nBase:\n{}\nTheirs ({}):\n{}\nLines after the conflict:\n{}",
+            before, state.into, ours, base, state.branch, theirs, after
+        );
+
+        let prompt = prompts::render("resolve", &[
+            ("files", path.to_string()),
+            ("branch", state.into.clone()),
+            ("history", history.to_string()),
+            ("conflict", conflict),
+        ]);
+
+        status(options, &format!("⚡ Resolving {}...", label));
+        let proposal = match send("resolve", &prompt, None, options) {
+            Some(Ok(response)) => resolve::parse(&response),
+            Some(Err(e)) => {
+                eprintln!("{} {}", "Error:".red().bold(), e);
+                return false;
+            }
+            None => {
+                output.push_str(raw);
+                left += 1;
+                continue;
+            }
+        };
+
+        let proposal = match proposal {
+            // Writing a placeholder back would destroy the real value
+            Ok(p) if p.resolution.contains("[REDACTED:") => {
+                println!("  {} {} touches redacted content, left as is", "✗".red(), label);
+                output.push_str(raw);
+                left += 1;
+                continue;
+            }
+            Ok(p) => p,
+            Err(e) => {
+                println!("  {} {} {}, left as is", "✗".red(), label, e);
+                output.push_str(raw);
+                left += 1;
+                continue;
+            }
+        };
+
+        println!("  {}", format!("@@ {} @@", label).cyan());
+        if !proposal.explanation.is_empty() {
+            println!("  {}", proposal.explanation.dimmed());
+        }
+        for line in proposal.resolution.lines() {
+            println!("{}", format!("  +{}", line).green());
+        }
+
+        let resolution = if options.yes {
+            Some(proposal.resolution)
+        } else {
+            print!("{}", "  Accept this resolution? (a)ccept / (e)dit / (r)eject: ".yellow().bold());
+            io::stdout().flush().unwrap();
+
+            let mut input = String::new();
+            io::stdin().read_line(&mut input).unwrap();
+
+            match input.trim().to_lowercase().as_str() {
+                "a" | "y" => Some(proposal.resolution),
+                "e" => match edit_text(&proposal.resolution) {
+                    Ok(text) => Some(text),
+                    Err(e) => {
+                        println!("  {} {}, left as is", "✗".red(), e);
+                        None
+                    }
+                },
+                _ => None,
+            }
+        };
+
+        match resolution {
+            Some(text) => {
+                output.push_str(&text);
+                changed = true;
+            }
+            None => {
+                output.push_str(raw);
+                left += 1;
+            }
+        }
+    }
+
+    if changed && let Err(e) = fs::write(path, &output) {
+        eprintln!("{} Failed to write {}: {}", "Error:".red().bold(), path, e);
+        return false;
+    }
+
+    if left == 0 && !options.show_prompt {
+        println!("  {} resolved", "✓".green());
+    }
+
+    true
+}
+
+// Let the user adjust a proposed resolution in $VISUAL / $EDITOR
+fn edit_text(text: &str) -> Result<String, String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let path = std::env::temp_dir().join(format!("ark-resolve-{}.txt", std::process::id()));
+
+    fs::write(&path, text).map_err(|e| format!("Failed to write temporary file: {}", e))?;
+
+    let result = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(&path)
+        .status();
+
+    let edited = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+
+    match result {
+        Ok(s) if s.success() => {}
+        Ok(_) => return Err("editor exited with an error".to_string()),
+        Err(e) => return Err(format!("Failed to start editor '{}': {}", editor, e)),
+    }
 
-    // Build context
-    let changes_summary: Vec<String> = changes.iter().map(|f| {
+    let mut edited = edited.map_err(|e| format!("Failed to read edited text: {}", e))?;
+    if !edited.is_empty() && !edited.ends_with('\n') {
+        edited.push('\n');
+    }
+    Ok(edited)
+}
+
+fn ai_changelog(options: &AiOptions) {
+    let tags = tag::load_tags();
+
+    let (to_title, to_date, to_id) = match resolve_release(options.args.get(1).map(|s| s.as_str()).unwrap_or("HEAD"), &tags) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            return;
+        }
+    };
+
+    // Default to everything since the latest tag before the end point
+    let from_id = match options.args.first() {
+        Some(name) => match resolve_release(name, &tags) {
+            Ok((_, _, id)) => Some(id),
+            Err(e) => {
+                eprintln!("{} {}", "Error:".red().bold(), e);
+                return;
+            }
+        },
+        None => {
+            let reachable = merge::ancestors(&to_id);
+            tags.iter()
+                .filter(|t| t.commit_id != to_id && reachable.contains(&t.commit_id))
+                .max_by(|a, b| a.created_at.cmp(&b.created_at))
+                .map(|t| t.commit_id.clone())
+        }
+    };
+
+    let excluded = from_id.as_deref().map(merge::ancestors).unwrap_or_default();
+    // Oldest first, so saves made in the same second keep their order
+    let mut commits: Vec<commit::Commit> = merge::ancestors(&to_id).iter().rev()
+        .filter(|id| !excluded.contains(id))
+        .filter_map(|id| commit::load_commit(id).ok())
+        .filter(|c| c.merge_parent.is_none())
+        .collect();
+    commits.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+    if commits.is_empty() {
+        println!("{}", "No saves in that range.".yellow());
+        return;
+    }
+
+    let use_ai = options.show_prompt || config::is_configured();
+    if !use_ai {
+        println!("{}", "AI not configured; grouping by message prefix only.".dimmed());
+    }
+
+    let mut entries = Vec::new();
+    for c in &commits {
+        if use_ai && changelog::is_vague(&c.message) {
+            status(options, &format!("⚡ Summarizing {} \"{}\"...", &c.id[..c.id.len().min(8)], c.message));
+            match summarize_commit(c, options) {
+                Some(Ok(Some(entry))) => {
+                    entries.push(entry);
+                    continue;
+                }
+                // Summarized as a maintenance change, which stays out
+                Some(Ok(None)) => continue,
+                Some(Err(e)) => eprintln!("{} {}", "⚠".yellow(), format!("{}; using the message as is", e).dimmed()),
+                None => {}
+            }
+        }
+
+        if let Some(entry) = changelog::entry(&c.message) {
+            entries.push(entry);
+        }
+    }
+
+    if options.show_prompt {
+        return;
+    }
+
+    let release = changelog::render_release(&to_title, to_date.as_deref(), &entries);
+
+    println!();
+    println!("{}", release);
+
+    let existing = fs::read_to_string(changelog::FILE).ok();
+    let mut updated = changelog::update(existing.as_deref(), &to_title, &release);
+
+    // Releasing the newest tag ships everything that was unreleased
+    let newest = tags.iter().max_by(|a, b| a.created_at.cmp(&b.created_at));
+    if newest.is_some_and(|t| t.name == to_title)
+        && updated.lines().any(|l| l.starts_with("## [Unreleased]"))
+    {
+        updated = changelog::update(Some(&updated), "Unreleased", "## [Unreleased]\n");
+    }
+
+    match fs::write(changelog::FILE, updated) {
+        Ok(_) => println!("{} {} ({} saves)", "✓ Updated".green().bold(), changelog::FILE.cyan(), commits.len()),
+        Err(e) => eprintln!("{} Failed to write {}: {}", "Error:".red().bold(), changelog::FILE, e),
+    }
+}
+
+// A tag, branch, commit id or HEAD, as (release title, date, commit id).
+// Anything but a tag is an unreleased state.
+fn resolve_release(name: &str, tags: &[tag::Tag]) -> Result<(String, Option<String>, String), String> {
+    if let Some(t) = tags.iter().find(|t| t.name == name) {
+        let date = t.created_at.get(..10).map(|d| d.to_string());
+        return Ok((t.name.clone(), date, t.commit_id.clone()));
+    }
+
+    let id = if name == "HEAD" {
+        commit::load_history().last().cloned()
+    } else if branch::branch_exists(name) {
+        branch::load_branch(name).ok().and_then(|b| b.commit_ids.last().cloned())
+    } else if commit::load_commit(name).is_ok() {
+        Some(name.to_string())
+    } else {
+        return Err(format!("'{}' is not a tag, branch or save id.", name));
+    };
+
+    id.map(|id| ("Unreleased".to_string(), None, id))
+        .ok_or_else(|| "No saves found. Save changes first.".to_string())
+}
+
+// Ask the model to describe a commit whose message is too vague to use.
+// Ok(None) means the change is maintenance that stays out of the changelog.
+fn summarize_commit(c: &commit::Commit, options: &AiOptions) -> Option<Result<Option<changelog::Entry>, String>> {
+    let changes = diff::commit_changes(c);
+    let budget = context::TOKENS_PER_CALL * 4;
+
+    let mut text = String::new();
+    for change in &changes {
+        // Sensitive files (.env, keys, credentials) are never sent
+        if scanner::is_sensitive_file(&change.path) {
+            text.push_str(&format!("File: {} (content withheld: sensitive file)\n\n", change.path));
+            continue;
+        }
+        text.push_str(&format!("File: {}\n", change.path));
+        if let Some(note) = &change.diff.note {
+            text.push_str(&format!("({})\n", note));
+        }
+        for hunk in &change.diff.hunks {
+            text.push_str(hunk);
+        }
+        text.push('\n');
+        if text.len() > budget {
+            text.truncate(text.char_indices().nth(budget).map(|(i, _)| i).unwrap_or(text.len()));
+            text.push_str("\n(diff truncated)\n");
+            break;
+        }
+    }
+
+    let files: Vec<&str> = changes.iter().map(|ch| ch.path.as_str()).collect();
+    let prompt = prompts::render("changelog", &[
+        ("history", c.message.clone()),
+        ("files", if files.is_empty() { "none".to_string() } else { files.join(", ") }),
+        ("diff", text),
+    ]);
+
+    let response = match send("changelog", &prompt, None, options)? {
+        Ok(r) => r,
+        Err(e) => return Some(Err(e)),
+    };
+
+    let line = response.lines()
+        .map(|l| l.trim().trim_matches(|ch| ch == '`' || ch == '"' || ch == '\''))
+        .find(|l| !l.is_empty())
+        .unwrap_or("");
+
+    match crate::core::conventional::parse(line) {
+        Some(_) => Some(Ok(changelog::entry(line))),
+        None => Some(Err("AI summary was not of the form '<type>: <description>'".to_string())),
+    }
+}
+
+// Send the action's prompt template filled with the line-level diff of
+// the changes. When the diff does not fit in one call, each part is
+// summarized separately and the template gets the summaries instead.
+fn send_changes(
+    changes: &[tracker::FileStatus],
+    action: &str,
+    heading: Option<&str>,
+    options: &AiOptions,
+) -> Option<Result<String, String>> {
+    let ctx = context::build(changes, context::TOKENS_PER_CALL, context::MAX_CALLS);
+    let mut vars = template_vars(changes);
+
+    let omitted_note = if ctx.omitted.is_empty() {
+        String::new()
+    } else {
+        status(options, &format!("⚠ Too large for the prompt, omitted: {}", ctx.omitted.join(", ")));
+        format!("\n\nNote: these changes were omitted to fit the size limit: {}", ctx.omitted.join(", "))
+    };
+
+    if ctx.chunks.len() <= 1 {
+        let diff = ctx.chunks.first().cloned().unwrap_or_default();
+        vars.push(("diff", format!("{}{}", diff, omitted_note)));
+        return send(action, &prompts::render(action, &vars), heading, options);
+    }
+
+    status(options, &format!("  Large change, summarizing in {} parts...", ctx.chunks.len()));
+
+    let mut summaries = Vec::new();
+    for (i, chunk) in ctx.chunks.iter().enumerate() {
+        let prompt = format!(
+            "Summarize part {} of {} of a code change as a short bullet list of what changed. Mention file names. Be factual.\n\n{}",
+            i + 1,
+            ctx.chunks.len(),
+            chunk
+        );
+
+        match send(action, &prompt, None, options) {
+            Some(Ok(summary)) => summaries.push(format!("Part {}:\n{}", i + 1, summary)),
+            Some(Err(e)) => return Some(Err(e)),
+            // Dry run: show where the summary would go
+            None => summaries.push(format!("Part {}:\n<summary of part {}>", i + 1, i + 1)),
+        }
+    }
+
+    vars.push(("diff", format!(
+        "(The change was too large to show in full; these are summaries of its parts.)\n\n{}{}",
+        summaries.join("\n\n"),
+        omitted_note
+    )));
+    send(action, &prompts::render(action, &vars), heading, options)
+}
+
+// Values for the {{branch}}, {{files}} and {{history}} placeholders
+fn template_vars(changes: &[tracker::FileStatus]) -> Vec<(&'static str, String)> {
+    let files: Vec<String> = changes.iter().map(|f| {
         let status = match f.status {
             tracker::Status::New      => "new",
             tracker::Status::Modified => "modified",
@@ -416,26 +1399,175 @@ fn ai_suggest() {
         format!("{}: {}", status, f.path)
     }).collect();
 
-    let recent_commits: Vec<String> = history.iter().rev().take(3).filter_map(|id| {
-        commit::load_commit(id).ok().map(|c| c.message)
+    let history: Vec<String> = commit::load_history().iter().rev().take(5).filter_map(|id| {
+        commit::load_commit(id).ok().map(|c| format!("- {} ({})", c.message, c.timestamp))
     }).collect();
 
-    let prompt = format!(
-        "Based on this project context, suggest the next 3 best actions a developer should take:\n\nCurrent branch: {}\nUncommitted changes: {}\nRecent commits:\n{}\n\nYou MUST suggest ONLY ark commands (ark save, ark ai commit, ark ai auto, ark sync, ark branch, ark diff, etc.). NEVER suggest git commands. Format as numbered list with the exact ark command to run.",
-        current_branch,
-        if changes.is_empty() { "none".to_string() } else { changes_summary.join(", ") },
-        if recent_commits.is_empty() { "none".to_string() } else { recent_commits.join("\n") }
-    );
+    vec![
+        ("branch", branch::get_current_branch()),
+        ("files", if files.is_empty() { "none".to_string() } else { files.join(", ") }),
+        ("history", if history.is_empty() { "none".to_string() } else { history.join("\n") }),
+    ]
+}
 
-    println!("{}", "⚡ Analyzing project state...".dimmed());
+fn ai_usage() {
+    let entries = usage::load();
 
-    match groq::generate(&prompt) {
-        Ok(suggestion) => {
-            println!();
-            println!("{}", "Suggested Next Steps:".green().bold().underline());
-            println!();
-            println!("{}", suggestion);
+    if entries.is_empty() {
+        println!("{}", "No AI usage recorded yet.".yellow());
+        return;
+    }
+
+    println!("{}", "AI usage:".bold().underline());
+    println!();
+    println!("  {}", format!("{:<12} {:<10} {:>6} {:>7} {:>10} {:>11}",
+        "date", "command", "calls", "cached", "prompt", "completion").dimmed());
+
+    let mut sum = usage::Total::default();
+    for ((day, action), total) in usage::totals(&entries) {
+        println!("  {:<12} {:<10} {:>6} {:>7} {:>10} {:>11}",
+            day, action.cyan(), total.calls, total.cached, total.prompt_tokens, total.completion_tokens);
+        sum.calls += total.calls;
+        sum.cached += total.cached;
+        sum.prompt_tokens += total.prompt_tokens;
+        sum.completion_tokens += total.completion_tokens;
+    }
+
+    println!();
+    println!("  {}", format!("{:<12} {:<10} {:>6} {:>7} {:>10} {:>11}",
+        "total", "", sum.calls, sum.cached, sum.prompt_tokens, sum.completion_tokens).bold());
+
+    if entries.iter().any(|e| e.estimated) {
+        println!();
+        println!("{}", "Some counts are estimated: the provider did not report usage.".dimmed());
+    }
+}
+
+fn ai_prompts(options: &AiOptions) {
+    if options.init {
+        match prompts::init() {
+            Ok((written, kept)) => {
+                for path in &written {
+                    println!("  {} {}", "created:".dimmed(), path.green());
+                }
+                for path in &kept {
+                    println!("  {} {} (already customized)", "kept:".dimmed(), path.yellow());
+                }
+                println!("{}", "✓ Prompt templates ready. Edit them to match your team's style.".green().bold());
+            }
+            Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
         }
-        Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+        return;
+    }
+
+    let overrides = config::load_config().map(|c| c.actions).unwrap_or_default();
+
+    println!("{}", "Prompt templates:".bold().underline());
+    println!();
+
+    for action in prompts::ACTIONS {
+        let source = if prompts::is_custom(action) {
+            prompts::template_path(action).cyan().to_string()
+        } else {
+            "built-in default".dimmed().to_string()
+        };
+        println!("  {:<10} {}", action.bold(), source);
+
+        if prompts::is_custom(action)
+            && prompts::custom_version(action).unwrap_or(0) < prompts::TEMPLATE_VERSION
+        {
+            println!("  {:<10} {}", "", "based on an older default; compare with 'ark ai prompts --init' output".yellow());
+        }
+
+        if let Some(settings) = overrides.get(*action) {
+            let mut parts = Vec::new();
+            if let Some(model) = &settings.model {
+                parts.push(format!("model={}", model));
+            }
+            if let Some(t) = settings.temperature {
+                parts.push(format!("temperature={}", t));
+            }
+            if let Some(m) = settings.max_tokens {
+                parts.push(format!("max_tokens={}", m));
+            }
+            if !parts.is_empty() {
+                println!("  {:<10} {}", "", parts.join(" ").dimmed());
+            }
+        }
+    }
+
+    println!();
+    println!("{}", format!("Placeholders: {}", prompts::PLACEHOLDERS.iter()
+        .map(|p| format!("{{{{{}}}}}", p)).collect::<Vec<_>>().join(", ")).dimmed());
+    if !Path::new(prompts::PROMPTS_DIR).exists() {
+        println!("{}", "Run 'ark ai prompts --init' to customize them.".dimmed());
+    }
+}
+
+// Single exit point for prompts: scrub secrets, then either show the
+// prompt (--show-prompt) or send it. Returns None for a dry run.
+// Answers with a heading are printed here, streamed as they arrive.
+fn send(action: &str, prompt: &str, heading: Option<&str>, options: &AiOptions) -> Option<Result<String, String>> {
+    let (prompt, redacted) = scanner::scrub_text(prompt);
+
+    if options.show_prompt {
+        println!();
+        println!("{}", "Prompt (not sent):".yellow().bold().underline());
+        println!();
+        println!("{}", prompt);
+        println!();
+        if redacted > 0 {
+            println!("{} {} secret value(s) replaced with placeholders", "🔒".dimmed(), redacted);
+        }
+        return None;
+    }
+
+    if redacted > 0 {
+        status(options, &format!("🔒 Redacted {} secret value(s) from the prompt.", redacted));
+    }
+
+    let heading = match heading {
+        Some(h) => h,
+        None => return Some(provider::generate(action, &prompt, None, !options.no_cache)),
+    };
+
+    let started = Cell::new(false);
+    let print_token = |token: &str| {
+        let token = if started.get() { token } else { token.trim_start() };
+        if token.is_empty() {
+            return;
+        }
+        if !started.get() {
+            print_heading(heading);
+            started.set(true);
+        }
+        print!("{}", token);
+        io::stdout().flush().unwrap();
+    };
+
+    let result = provider::generate(action, &prompt, Some(&print_token), !options.no_cache);
+
+    if started.get() {
+        println!();
+    } else if let Ok(text) = &result {
+        print_heading(heading);
+        println!("{}", text);
+    }
+
+    Some(result)
+}
+
+fn print_heading(heading: &str) {
+    println!();
+    println!("{}", heading.green().bold().underline());
+    println!();
+}
+
+// Progress messages go to stderr when stdout carries JSON or SARIF
+fn status(options: &AiOptions, message: &str) {
+    if options.format == "text" || options.format.is_empty() {
+        println!("{}", message.dimmed());
+    } else {
+        eprintln!("{}", message.dimmed());
     }
 }
@@ -2,7 +2,7 @@ use colored::Colorize;
 use crate::core::{repo, branch};
 use std::fs;
 
-pub fn run(action: &str, name: Option<&str>, new_name: Option<&str>) {
+pub fn run(action: &str, name: Option<&str>, new_name: Option<&str>, unset: bool) {
     if !repo::is_initialized() {
         eprintln!("{}", "Error: Not an Ark repository. Run 'ark start' first.".red().bold());
         return;
@@ -67,8 +67,9 @@ pub fn run(action: &str, name: Option<&str>, new_name: Option<&str>) {
                 _ => eprintln!("{}", "Error: Usage: ark branch rename <old> <new>".red()),
             }
         }
+        "upstream" => upstream(name, new_name, unset),
         _ => {
-            eprintln!("{} Unknown action '{}'. Use: new, go, list, delete, rename",
+            eprintln!("{} Unknown action '{}'. Use: new, go, list, delete, rename, upstream",
                 "Error:".red().bold(), action);
         }
     }
@@ -125,3 +126,60 @@ fn rename_branch(old_name: &str, new_name: &str) {
         new_name.cyan()
     );
 }
+
+// Show or set where the current branch is pushed to and pulled from
+fn upstream(remote: Option<&str>, remote_branch: Option<&str>, unset: bool) {
+    let current = branch::get_current_branch();
+    let mut branch_data = match branch::load_branch(&current) {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            return;
+        }
+    };
+
+    if unset {
+        branch_data.upstream = None;
+        match branch::save_branch(&branch_data) {
+            Ok(_) => println!("{} {}", "✓ Upstream removed for".green().bold(), current.cyan()),
+            Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+        }
+        return;
+    }
+
+    let remote = match remote {
+        Some(r) => r,
+        None => {
+            match &branch_data.upstream {
+                Some(u) => println!("{} {} {} {}/{}", "Upstream:".dimmed(), current.cyan(), "→".dimmed(), u.remote.cyan(), u.branch.cyan()),
+                None => {
+                    println!("{} {}", "No upstream set for".yellow(), current.cyan());
+                    println!("{}", "  Set one with: ark branch upstream <remote> [<branch>]".dimmed());
+                }
+            }
+            return;
+        }
+    };
+
+    if crate::cli::remote::url(remote).is_none() {
+        eprintln!("{} Remote '{}' not found.", "Error:".red().bold(), remote);
+        return;
+    }
+
+    let upstream = branch::Upstream {
+        remote: remote.to_string(),
+        branch: remote_branch.unwrap_or(&current).to_string(),
+    };
+    branch_data.upstream = Some(upstream.clone());
+
+    match branch::save_branch(&branch_data) {
+        Ok(_) => println!("{} {} {} {}/{}",
+            "✓ Upstream set:".green().bold(),
+            current.cyan(),
+            "→".dimmed(),
+            upstream.remote.cyan(),
+            upstream.branch.cyan()
+        ),
+        Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+    }
+}
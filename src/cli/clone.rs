@@ -1,13 +1,14 @@
 use colored::Colorize;
+use crate::cli::import;
 use crate::git::git_wrapper;
 use std::path::Path;
 
-pub fn run(url: &str, dir_name: Option<&str>) {
+pub fn run(url: &str, dir_name: Option<&str>, first_parent: bool) {
     let folder = match dir_name {
         Some(d) => d.to_string(),
         None => url
             .split('/')
-            .last()
+            .next_back()
             .unwrap_or("repo")
             .replace(".git", ""),
     };
@@ -21,10 +22,8 @@ pub fn run(url: &str, dir_name: Option<&str>) {
         return;
     }
 
-    let result = git_wrapper::clone(url, &folder);
-
-    if !result.success {
-        eprintln!("{} {}", "Clone failed:".red().bold(), result.output);
+    if let Err(e) = git_wrapper::clone(url, &folder) {
+        eprintln!("{} {}", "Clone failed:".red().bold(), e);
         return;
     }
 
@@ -35,6 +34,7 @@ pub fn run(url: &str, dir_name: Option<&str>) {
         Ok(_) => {
             println!("{}", "✓ Cloned successfully!".green().bold());
             println!("{} {}", "✓ Ark initialized in:".green(), folder.cyan());
+            import::import_history(first_parent);
             println!();
             println!("{} cd {}", "Next:".dimmed(), folder.cyan());
         }
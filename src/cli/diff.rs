@@ -1,5 +1,6 @@
 use colored::Colorize;
 use crate::core::{repo, tracker, commit};
+use crate::security::scanner;
 
 pub fn run(commit_id: Option<&str>) {
     if !repo::is_initialized() {
@@ -33,6 +34,8 @@ fn show_current_diff() {
                 // Show file content preview
                 if let Ok(content) = std::fs::read_to_string(&f.path) {
                     for line in content.lines().take(10) {
+                        // Never echo secrets into terminal scrollback
+                        let line = scanner::redact_line(line);
                         println!("  {} {}", "+".green(), line.green());
                     }
                     if content.lines().count() > 10 {
@@ -77,7 +80,7 @@ fn show_commit_diff(id: &str) {
             println!("{}", "Files in this commit:".bold().underline());
             println!();
 
-            for (path, _) in &c.files_snapshot {
+            for path in c.files_snapshot.keys() {
                 println!("  {} {}", "●".cyan(), path);
             }
 
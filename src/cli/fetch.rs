@@ -0,0 +1,43 @@
+use colored::Colorize;
+use crate::core::{ark_remote, branch, repo};
+use crate::cli::remote;
+use crate::git::git_wrapper;
+
+// `ark fetch`: bring in what a remote has without touching local branches
+pub fn run(remote_name: Option<&str>) {
+    if !repo::is_initialized() {
+        eprintln!("{}", "Error: Not an Ark repository. Run 'ark start' first.".red().bold());
+        return;
+    }
+
+    let upstream = branch::load_branch(&branch::get_current_branch()).ok().and_then(|b| b.upstream);
+    let name = remote::resolve(remote_name, upstream.as_ref());
+
+    println!("{} {}", "Fetching from".dimmed(), name.cyan());
+    match ark_remote::url(&name) {
+        Some(url) => {
+            fetch_ark(&name, &url);
+        }
+        None => match git_wrapper::fetch(&name) {
+            Ok(()) => println!("{}", "✓ Fetched.".green().bold()),
+            Err(e) => eprintln!("{} {}", "Error fetching:".red().bold(), e),
+        },
+    }
+}
+
+// Fetch from an Ark remote and report what came in. Returns false on failure.
+pub fn fetch_ark(name: &str, url: &str) -> bool {
+    match ark_remote::fetch(name, url) {
+        Ok(summary) => {
+            println!("{} {}",
+                "✓ Fetched.".green().bold(),
+                format!("{} commit(s), {} updated branch(es), {} tag(s)", summary.commits, summary.branches, summary.tags).dimmed()
+            );
+            true
+        }
+        Err(e) => {
+            eprintln!("{} {}", "Error fetching:".red().bold(), e);
+            false
+        }
+    }
+}
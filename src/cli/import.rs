@@ -0,0 +1,42 @@
+use colored::Colorize;
+use crate::core::repo;
+use crate::git::{git_wrapper, import};
+
+// `ark import`: bring in the git history of this directory. Safe to
+// re-run; an interrupted import continues where it stopped.
+pub fn run(first_parent: bool) {
+    if !repo::is_initialized() {
+        eprintln!("{}", "Error: Not an Ark repository. Run 'ark start' first.".red().bold());
+        return;
+    }
+
+    if !git_wrapper::is_worktree_root() {
+        eprintln!("{} Not a git repository; there is no history to import.", "Error:".red().bold());
+        return;
+    }
+
+    import_history(first_parent);
+}
+
+// Import and report; shared by `ark start` and `ark clone`
+pub fn import_history(first_parent: bool) {
+    println!("{}", "Importing git history...".dimmed());
+
+    match import::import(first_parent) {
+        Ok(summary) if summary.commits == 0 => {
+            println!("{}", "✓ Git history is already imported.".green());
+        }
+        Ok(summary) => {
+            println!("{} {} commit(s), {} branch(es), {} tag(s)",
+                "✓ Imported".green().bold(),
+                summary.commits,
+                summary.branches,
+                summary.tags
+            );
+        }
+        Err(e) => {
+            eprintln!("{} {}", "Error importing git history:".red().bold(), e);
+            eprintln!("{}", "  Run 'ark import' to continue where it stopped.".dimmed());
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use colored::Colorize;
+use crate::core::{repo, commit, lint, merge, message};
+
+// `ark lint-history`: check every saved message against .ark/lint.json
+pub fn run() {
+    if !repo::is_initialized() {
+        eprintln!("{}", "Error: Not an Ark repository. Run 'ark start' first.".red().bold());
+        return;
+    }
+
+    let config = match lint::load_config() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            return;
+        }
+    };
+
+    if config.is_empty() {
+        println!("{} {}", "No message rules configured.".yellow(), format!("Add them to {}.", lint::CONFIG_FILE).dimmed());
+        return;
+    }
+
+    let mut commits: Vec<commit::Commit> = commit::all_commit_ids().iter()
+        .filter_map(|id| commit::load_commit(id).ok())
+        .collect();
+    commits.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+    println!("{}", format!("Checking {} saved message(s) against {}...", commits.len(), lint::CONFIG_FILE).dimmed());
+    println!();
+
+    let generated = config.for_generated();
+    let mut failed = 0;
+    for c in &commits {
+        let rules = if c.merge_parent.is_some() && merge::is_default_message(&c.message) { &generated } else { &config };
+        let violations = lint::check(&c.message, &c.branch, rules);
+        if violations.is_empty() {
+            continue;
+        }
+
+        failed += 1;
+        println!("  {} {} {}", c.id.cyan(), c.branch.dimmed(), c.message.lines().next().unwrap_or(""));
+        for v in &violations {
+            println!("    {} {}", format!("[{}]", v.rule).red(), v.message);
+        }
+        println!();
+    }
+
+    if failed == 0 {
+        println!("{}", "✓ Every saved message follows the rules.".green().bold());
+        return;
+    }
+
+    println!("{}", format!("✗ {} of {} message(s) break the rules.", failed, commits.len()).red().bold());
+    std::process::exit(1);
+}
+
+// Refuse a message that breaks the configured rules. Returns false when
+// the operation must be aborted.
+pub fn message_gate(message: &str, branch: &str) -> bool {
+    let config = match lint::load_config() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            return false;
+        }
+    };
+
+    let violations = lint::check(message, branch, &config);
+    if violations.is_empty() {
+        return true;
+    }
+
+    eprintln!("{} {}", "✗ Message rejected:".red().bold(), message.lines().next().unwrap_or("").yellow());
+    for v in &violations {
+        eprintln!("  {} {}", format!("[{}]", v.rule).red(), v.message);
+    }
+    eprintln!("{}", format!("  Rules are set in {}.", lint::CONFIG_FILE).dimmed());
+    false
+}
+
+// message_gate for a message Ark wrote. When it breaks the rules, also
+// suggest one the user can save with.
+pub fn generated_gate(message: &str, branch: &str) -> bool {
+    if message_gate(message, branch) {
+        return true;
+    }
+
+    eprintln!("  {} ark save \"{}\"", "Suggested:".yellow(), message::suggest(message));
+    false
+}
+
+// Violations of the configured rules, as one line each, for feeding back
+// to the AI when a generated message breaks them
+pub fn violations(message: &str, branch: &str) -> Vec<String> {
+    let config = lint::load_config().unwrap_or_default();
+    lint::check(message, branch, &config).iter()
+        .map(|v| format!("{} ({})", v.message, v.rule))
+        .collect()
+}
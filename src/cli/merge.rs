@@ -1,12 +1,39 @@
+use std::fs;
+use std::collections::HashMap;
 use colored::Colorize;
-use crate::core::{repo, branch, commit, tracker};
+use crate::core::{repo, branch, commit, merge, object, tracker};
+use crate::cli::lint;
 
-pub fn run(branch_name: &str) {
+pub fn run(branch_name: Option<&str>, message: Option<&str>, cont: bool, abort: bool) {
     if !repo::is_initialized() {
         eprintln!("{}", "Error: Not an Ark repository. Run 'ark start' first.".red().bold());
         return;
     }
 
+    if cont {
+        continue_merge();
+        return;
+    }
+
+    if abort {
+        abort_merge();
+        return;
+    }
+
+    let branch_name = match branch_name {
+        Some(b) => b,
+        None => {
+            eprintln!("{} Name the branch to merge: ark merge <branch>", "Error:".red().bold());
+            return;
+        }
+    };
+
+    if merge::in_progress() {
+        eprintln!("{} A merge is already in progress. Run 'ark merge --continue' or 'ark merge --abort'.",
+            "Error:".red().bold());
+        return;
+    }
+
     let current = branch::get_current_branch();
 
     // Cannot merge branch into itself
@@ -21,6 +48,17 @@ pub fn run(branch_name: &str) {
         return;
     }
 
+    // Only a message the user wrote is held to the lint rules
+    let merge_message = match message {
+        Some(m) if !m.trim().is_empty() => {
+            if !lint::message_gate(m, &current) {
+                return;
+            }
+            m.to_string()
+        }
+        _ => merge::default_message(branch_name, &current),
+    };
+
     println!("{} {} {} {}",
         "Merging".dimmed(),
         branch_name.cyan().bold(),
@@ -38,45 +76,215 @@ pub fn run(branch_name: &str) {
         }
     };
 
-    if source_branch.commit_ids.is_empty() {
-        println!("{}", "Nothing to merge. Source branch has no commits.".yellow());
+    let source_id = match source_branch.commit_ids.last() {
+        Some(id) => id.clone(),
+        None => {
+            println!("{}", "Nothing to merge. Source branch has no commits.".yellow());
+            return;
+        }
+    };
+
+    let source_snapshot = match commit::load_commit(&source_id) {
+        Ok(c) => c.files_snapshot,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            return;
+        }
+    };
+
+    // Find the common ancestor to merge against
+    let ours_id = commit::load_history().last().cloned();
+    let base_id = ours_id.as_deref().and_then(|id| merge::common_ancestor(id, &source_id));
+
+    if let Some(ours) = &ours_id
+        && merge::ancestors(ours).contains(&source_id)
+    {
+        println!("{}", "Already up to date.".green());
         return;
     }
 
-    // Get latest snapshot from source branch
-    let source_snapshot_path = format!(".ark/snapshots/{}.json", branch_name);
-    let source_snapshot: std::collections::HashMap<String, String> = 
-        std::fs::read_to_string(&source_snapshot_path)
-            .ok()
-            .and_then(|c| serde_json::from_str(&c).ok())
-            .unwrap_or_default();
+    let base_snapshot = base_id.as_deref()
+        .and_then(|id| commit::load_commit(id).ok())
+        .map(|c| c.files_snapshot)
+        .unwrap_or_default();
+    let ours_snapshot = tracker::load_snapshot();
+
+    let outcome = match merge::merge_snapshots(
+        &base_snapshot,
+        &ours_snapshot,
+        &source_snapshot,
+        &format!("ours ({})", current),
+        &format!("theirs ({})", branch_name),
+    ) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            return;
+        }
+    };
+
+    // Never overwrite uncommitted work in files the merge has to write
+    let blocked: Vec<&String> = outcome.writes.iter()
+        .filter(|(path, content)| {
+            let working = fs::read(path).ok().map(|c| tracker::hash_content(&c));
+            let merged = content.as_ref().map(|c| tracker::hash_content(c));
+            working != ours_snapshot.get(path).cloned() && working != merged
+        })
+        .map(|(path, _)| path)
+        .collect();
 
-    if source_snapshot.is_empty() {
-        println!("{}", "Nothing to merge. Source branch has no snapshot.".yellow());
+    if !blocked.is_empty() {
+        eprintln!("{} Your uncommitted changes to these files would be overwritten by the merge:",
+            "Error:".red().bold());
+        for path in blocked {
+            eprintln!("  {}", path.yellow());
+        }
+        eprintln!("{}", "  Save your changes first: ark save".dimmed());
         return;
     }
 
-    // Check for conflicts with current changes
-    let current_changes = tracker::scan_changes();
-    if !current_changes.is_empty() {
-        println!("{}", "⚠ Warning: You have uncommitted changes.".yellow().bold());
-        println!("{}", "  Save your changes first: ark save".dimmed());
+    for (path, content) in &outcome.writes {
+        if let Err(e) = tracker::write_file(path, content.as_deref()) {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            return;
+        }
+    }
+
+    for path in &outcome.kept_ours {
+        println!("{} {} {}",
+            "⚠".yellow(),
+            path.yellow(),
+            "changed on both branches and can't be merged line by line; kept this branch's version".dimmed()
+        );
+    }
+
+    if !outcome.conflicts.is_empty() {
+        let state = merge::MergeState {
+            branch: branch_name.to_string(),
+            into: current.clone(),
+            source_commit: source_id,
+            message: merge_message,
+            base_commit: base_id,
+            snapshot: outcome.snapshot,
+            conflicts: outcome.conflicts.clone(),
+            touched: outcome.writes.iter().map(|(p, _)| p.clone()).collect(),
+        };
+
+        if let Err(e) = merge::save_state(&state) {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            return;
+        }
+
+        println!("{}", format!("⚠ Merge stopped with conflicts in {} file(s):", outcome.conflicts.len()).yellow().bold());
+        for path in &outcome.conflicts {
+            println!("  {} {}", "conflict:".red(), path.yellow());
+        }
         println!();
+        println!("{} {}",
+            "Tip:".dimmed(),
+            "Edit the files between the <<<<<<< and >>>>>>> markers, or run 'ark ai resolve'. Then run 'ark merge --continue'.".dimmed()
+        );
+        return;
+    }
+
+    save_merge(&merge_message, outcome.snapshot, source_id);
+}
+
+fn continue_merge() {
+    let state = match merge::load_state() {
+        Some(s) => s,
+        None => {
+            eprintln!("{} No merge in progress.", "Error:".red().bold());
+            return;
+        }
+    };
+
+    // Every conflicted file must be free of markers
+    let unresolved: Vec<&String> = state.conflicts.iter()
+        .filter(|path| fs::read_to_string(path).map(|c| merge::has_markers(&c)).unwrap_or(false))
+        .collect();
+
+    if !unresolved.is_empty() {
+        eprintln!("{} These files still have conflict markers:", "Error:".red().bold());
+        for path in unresolved {
+            eprintln!("  {}", path.yellow());
+        }
+        return;
+    }
+
+    let mut snapshot = state.snapshot.clone();
+    for path in &state.conflicts {
+        match fs::read(path) {
+            Ok(content) => match object::store(&content) {
+                Ok(hash) => {
+                    snapshot.insert(path.clone(), hash);
+                }
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red().bold(), e);
+                    return;
+                }
+            },
+            Err(_) => {
+                snapshot.remove(path);
+            }
+        }
+    }
+
+    let merge_message = if state.message.is_empty() {
+        merge::default_message(&state.branch, &state.into)
+    } else {
+        state.message.clone()
+    };
+    save_merge(&merge_message, snapshot, state.source_commit);
+}
+
+fn abort_merge() {
+    let state = match merge::load_state() {
+        Some(s) => s,
+        None => {
+            eprintln!("{} No merge in progress.", "Error:".red().bold());
+            return;
+        }
+    };
+
+    // Put back this branch's version of every file the merge wrote
+    let snapshot = tracker::load_snapshot();
+    for path in &state.touched {
+        let content = snapshot.get(path).and_then(|h| object::load(h));
+        if snapshot.contains_key(path) && content.is_none() {
+            eprintln!("{} {} {}", "⚠".yellow(), path.yellow(), "could not be restored; its content was not retained".dimmed());
+            continue;
+        }
+        if let Err(e) = tracker::write_file(path, content.as_deref()) {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            return;
+        }
     }
 
-    // Create merge commit message
-    let merge_message = format!("merge: {} into {}", branch_name, current);
+    match merge::clear_state() {
+        Ok(_) => println!("{}", "✓ Merge aborted.".green().bold()),
+        Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+    }
+}
+
+fn save_merge(message: &str, snapshot: HashMap<String, String>, source_id: String) {
+    let options = commit::CommitOptions {
+        merge_parent: Some(source_id),
+        ..Default::default()
+    };
 
-    // Save merge commit with source snapshot
-    match commit::save_commit(&merge_message, source_snapshot) {
+    match commit::save_commit_with(message, snapshot, options) {
         Ok(id) => {
+            if let Err(e) = merge::clear_state() {
+                eprintln!("{} {}", "Error:".red().bold(), e);
+            }
             println!("{}", "✓ Merge successful!".green().bold());
             println!("  {} {}", "commit:".dimmed(), id.cyan());
-            println!("  {} {}", "message:".dimmed(), merge_message.cyan());
+            println!("  {} {}", "message:".dimmed(), message.cyan());
             println!();
             println!("{} {}",
                 "Tip:".dimmed(),
-                "Run 'ark check' to see merged changes.".dimmed()
+                "Run 'ark history' to see the merge.".dimmed()
             );
         }
         Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
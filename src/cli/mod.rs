@@ -16,3 +16,6 @@ pub mod clone;
 pub mod tag;
 pub mod stash;
 pub mod restore;
+pub mod lint;
+pub mod import;
+pub mod fetch;
@@ -1,29 +1,58 @@
 use colored::Colorize;
-use crate::core::repo;
-use crate::git::git_wrapper;
+use crate::core::{ark_remote, repo};
+use crate::cli::{fetch, push};
+use crate::git::git_wrapper::{self, PullOutcome};
 
-pub fn run() {
+pub fn run(remote: Option<&str>, force: bool) {
     if !repo::is_initialized() {
         eprintln!("{}", "Error: Not an Ark repository. Run 'ark start' first.".red().bold());
         return;
     }
 
-    match git_wrapper::get_remote() {
-        None => {
-            println!("{}", "No remote configured.".yellow().bold());
-            println!("  ark remote add <your-github-url>");
-            return;
-        }
-        Some(remote) => {
-            println!("  {} {}", "remote:".dimmed(), remote.cyan());
-        }
+    let target = match push::prepare(remote) {
+        Some(t) => t,
+        None => return,
+    };
+
+    println!("{}", "Pulling from remote...".dimmed());
+    if let Some(url) = &target.ark_url {
+        pull_ark(&target, url, force);
+        return;
     }
 
-    println!("{}", "Pulling from GitHub...".dimmed());
-    let pull = git_wrapper::pull();
-    if pull.success {
-        println!("{}", "✓ Pull successful.".green().bold());
-    } else {
-        eprintln!("{} {}", "Error pulling:".red().bold(), pull.output);
+    match git_wrapper::pull(&target.remote, &target.remote_branch) {
+        Ok(outcome) => println!("{}", describe(&outcome).green().bold()),
+        Err(e) => eprintln!("{} {}", "Error pulling:".red().bold(), e),
+    }
+}
+
+pub fn describe(outcome: &PullOutcome) -> &'static str {
+    match outcome {
+        PullOutcome::NoRemoteHistory => "✓ Nothing to pull yet; the remote branch doesn't exist.",
+        PullOutcome::UpToDate => "✓ Already up to date.",
+        PullOutcome::FastForward => "✓ Pull successful (fast-forward).",
+        PullOutcome::Merged => "✓ Pull successful (merged).",
     }
 }
+
+// Fetch from an Ark remote and move the current branch forward. Returns
+// false when the branch could not be updated.
+pub fn pull_ark(target: &push::Target, url: &str, force: bool) -> bool {
+    if !fetch::fetch_ark(&target.remote, url) {
+        return false;
+    }
+
+    let message = match ark_remote::integrate(&target.remote, &target.branch, &target.remote_branch, force) {
+        Ok(None) => describe(&PullOutcome::NoRemoteHistory),
+        Ok(Some(ark_remote::Update::UpToDate)) => describe(&PullOutcome::UpToDate),
+        Ok(Some(ark_remote::Update::Forced)) => "✓ Pull successful (replaced local saves).",
+        Ok(Some(_)) => describe(&PullOutcome::FastForward),
+        Err(e) => {
+            eprintln!("{} {}", "Error pulling:".red().bold(), e);
+            return false;
+        }
+    };
+
+    println!("{}", message.green().bold());
+    true
+}
@@ -1,53 +1,243 @@
 use colored::Colorize;
-use crate::core::repo;
-use crate::git::git_wrapper;
+use std::collections::HashMap;
+use crate::core::{repo, ark_remote, branch, commit, diff, tracker};
+use crate::cli::{remote, scan};
+use crate::security::scanner;
+use crate::git::{export, git_wrapper};
 
-pub fn run() {
+// Where the current Ark branch is pushed to and pulled from
+pub struct Target {
+    pub remote: String,
+    pub branch: String,
+    pub remote_branch: String,
+    // Set when the remote is an Ark remote rather than git
+    pub ark_url: Option<String>,
+}
+
+pub fn run(allow_secrets: bool, remote: Option<&str>, force: bool) {
     if !repo::is_initialized() {
         eprintln!("{}", "Error: Not an Ark repository. Run 'ark start' first.".red().bold());
         return;
     }
 
-    if !git_wrapper::is_git_repo() {
-        let result = git_wrapper::init();
-        if !result.success {
-            eprintln!("{} {}", "Error:".red().bold(), result.output);
-            return;
-        }
+    let target = match prepare(remote) {
+        Some(t) => t,
+        None => return,
+    };
+
+    if let Some(url) = &target.ark_url {
+        push_ark(&target, url, allow_secrets, force);
+        return;
+    }
+
+    if !export(&target, allow_secrets) {
+        return;
     }
 
-    match git_wrapper::get_remote() {
+    push_target(&target, force);
+}
+
+// Resolve the remote and remote branch for the current Ark branch and,
+// for git remotes, check out the git branch of the same name. Prints why
+// and returns None when there is nowhere to push.
+pub fn prepare(remote: Option<&str>) -> Option<Target> {
+    let branch_name = branch::get_current_branch();
+    let upstream = branch::load_branch(&branch_name).ok().and_then(|b| b.upstream);
+
+    let remote_name = remote::resolve(remote, upstream.as_ref());
+
+    // The upstream's branch only applies when pushing to its remote
+    let remote_branch = match &upstream {
+        Some(u) if u.remote == remote_name => u.branch.clone(),
+        _ => branch_name.clone(),
+    };
+
+    match remote::url(&remote_name) {
+        None if remote.is_some() || !remote::all().is_empty() => {
+            eprintln!("{} Remote '{}' not found.", "Error:".red().bold(), remote_name);
+            if remote.is_none() {
+                eprintln!("{}", "  Choose one with: ark remote default <name>".dimmed());
+            }
+            return None;
+        }
         None => {
             println!("{}", "No remote configured.".yellow().bold());
+            println!("{}", "Add a remote first:".dimmed());
             println!("  ark remote add <your-github-url>");
-            return;
+            println!();
+            println!("{}", "Example:".dimmed());
+            println!("  ark remote add https://github.com/username/repo.git");
+            return None;
+        }
+        Some(url) => {
+            println!("  {} {} {}", "remote:".dimmed(), remote_name.cyan(), url.dimmed());
+            println!("  {} {} {} {}", "branch:".dimmed(), branch_name.cyan(), "→".dimmed(), remote_branch.cyan());
+        }
+    }
+
+    // Ark remotes take saves as they are; git needs none of the below
+    let ark_url = ark_remote::url(&remote_name);
+    if ark_url.is_some() {
+        return Some(Target { remote: remote_name, branch: branch_name, remote_branch, ark_url });
+    }
+
+    if !git_wrapper::is_git_repo() {
+        println!("{}", "Initializing Git backend...".dimmed());
+        if let Err(e) = git_wrapper::init() {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            return None;
+        }
+        println!("{}", "✓ Git initialized.".green());
+    }
+
+    if let Err(e) = git_wrapper::exclude_ark() {
+        eprintln!("{} {}", "Error:".red().bold(), e);
+        return None;
+    }
+
+    if git_wrapper::current_branch().as_deref() != Some(branch_name.as_str())
+        && let Err(e) = git_wrapper::checkout_branch(&branch_name, export::start_point(&branch_name).as_deref())
+    {
+        eprintln!("{} Could not check out git branch '{}': {}", "Error:".red().bold(), branch_name, e);
+        return None;
+    }
+
+    Some(Target { remote: remote_name, branch: branch_name, remote_branch, ark_url })
+}
+
+// Replay the branch's unexported saves as git commits. Returns false when
+// the push must be aborted.
+pub fn export(target: &Target, allow_secrets: bool) -> bool {
+    warn_unsaved();
+
+    let pending = export::pending(&target.branch);
+    let allowed_secrets = match gate_saves(&pending, allow_secrets) {
+        Some(findings) => findings,
+        None => return false,
+    };
+
+    if pending.is_empty() {
+        println!("{}", "✓ Nothing new to export.".green());
+        return true;
+    }
+
+    println!("{}", format!("Exporting {} save(s) to git...", pending.len()).dimmed());
+    match export::export_branch(&target.branch, &allowed_secrets) {
+        Ok(count) => {
+            if let Err(e) = git_wrapper::reset_index() {
+                eprintln!("{} {}", "Error:".red().bold(), e);
+                return false;
+            }
+            println!("{}", format!("✓ Exported {} commit(s).", count).green());
+            true
         }
-        Some(remote) => {
-            println!("  {} {}", "remote:".dimmed(), remote.cyan());
+        Err(e) => {
+            eprintln!("{} {}", "Error exporting:".red().bold(), e);
+            false
         }
     }
+}
+
+fn warn_unsaved() {
+    let unsaved = tracker::scan_changes().len();
+    if unsaved > 0 {
+        println!("{} {}",
+            format!("⚠ {} unsaved change(s) are not included.", unsaved).yellow(),
+            "Save them first: ark save".dimmed()
+        );
+    }
+}
+
+// Refuse to push secrets unless explicitly overridden. The versions these
+// saves send come from the object store, so those are what get scanned,
+// not the working tree. Findings a save was made with --allow-secrets for
+// pass. Returns the overridden findings.
+fn gate_saves(ids: &[String], allow_secrets: bool) -> Option<Vec<String>> {
+    let mut sources = Vec::new();
+    let mut allowed: HashMap<String, Vec<String>> = HashMap::new();
+    for id in ids {
+        let Ok(c) = commit::load_commit(id) else { continue };
+        let changed: HashMap<String, String> = diff::commit_changes(&c).into_iter()
+            .filter_map(|change| c.files_snapshot.get(&change.path).map(|h| (change.path, h.clone())))
+            .collect();
+        allowed.insert(c.id.clone(), c.allowed_secrets.clone());
+        sources.push((c.id, changed));
+    }
 
-    println!("{}", "Staging changes...".dimmed());
-    git_wrapper::add_all();
+    let results = scanner::scan_snapshots(&sources).results.into_iter()
+        .filter(|r| {
+            let finding = format!("{}:{} {}", r.file, r.line_number, r.issue);
+            !r.commit.as_ref().and_then(|id| allowed.get(id)).is_some_and(|a| a.contains(&finding))
+        })
+        .collect();
+    scan::findings_gate(results, allow_secrets)
+}
+
+// Push and remember the destination as the branch's upstream if it has none
+pub fn push_target(target: &Target, force: bool) {
+    println!("{}", "Pushing to remote...".dimmed());
+    if let Err(e) = git_wrapper::push(&target.remote, &target.branch, &target.remote_branch, force) {
+        eprintln!("{} {}", "Error pushing:".red().bold(), e);
+        return;
+    }
 
-    println!("{}", "Committing changes...".dimmed());
-    let commit = git_wrapper::commit("ark push");
-    if !commit.success {
-        if commit.output.contains("nothing to commit") {
-            println!("{}", "✓ Nothing new to commit.".green());
-        } else {
-            eprintln!("{} {}", "Error:".red().bold(), commit.output);
+    println!("{}", "✓ Pushed successfully!".green().bold());
+    record_upstream(target);
+}
+
+// Send the branch's saves, and the tags on them, to an Ark remote
+pub fn push_ark(target: &Target, url: &str, allow_secrets: bool, force: bool) {
+    warn_unsaved();
+    if gate_saves(&ark_remote::unpushed(url, &target.branch), allow_secrets).is_none() {
+        return;
+    }
+
+    println!("{}", "Pushing to remote...".dimmed());
+    let summary = match ark_remote::push(url, &target.branch, &target.remote_branch, force) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{} {}", "Error pushing:".red().bold(), e);
             return;
         }
-    } else {
-        println!("{}", "✓ Changes committed.".green());
+    };
+
+    match summary.update {
+        ark_remote::Update::UpToDate => println!("{}", "✓ Everything up to date.".green().bold()),
+        ark_remote::Update::Forced => println!("{}", "✓ Pushed successfully! (forced)".green().bold()),
+        _ => println!("{}", "✓ Pushed successfully!".green().bold()),
+    }
+    println!("{}", format!("  {} commit(s), {} tag(s) sent", summary.commits, summary.tags).dimmed());
+    for name in &summary.skipped_tags {
+        println!("{} {}", format!("⚠ Tag '{}' points elsewhere on the remote and was not pushed.", name).yellow(),
+            "Use --force to replace it.".dimmed());
     }
 
-    println!("{}", "Pushing to GitHub...".dimmed());
-    let push = git_wrapper::push();
-    if push.success {
-        println!("{}", "✓ Pushed successfully!".green().bold());
-    } else {
-        eprintln!("{} {}", "Error pushing:".red().bold(), push.output);
+    record_upstream(target);
+}
+
+fn record_upstream(target: &Target) {
+    if let Ok(mut b) = branch::load_branch(&target.branch)
+        && b.upstream.is_none()
+    {
+        b.upstream = Some(branch::Upstream {
+            remote: target.remote.clone(),
+            branch: target.remote_branch.clone(),
+        });
+        if let Err(e) = branch::save_branch(&b) {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+        }
     }
 }
+
+// Record overridden scanner findings as a commit trailer for auditing
+pub fn commit_message(subject: &str, allowed_secrets: &[String]) -> String {
+    if allowed_secrets.is_empty() {
+        return subject.to_string();
+    }
+
+    let trailers: Vec<String> = allowed_secrets.iter()
+        .map(|f| format!("Ark-Allowed-Secret: {}", f))
+        .collect();
+
+    format!("{}\n\n{}", subject, trailers.join("\n"))
+}
@@ -1,50 +1,276 @@
 use colored::Colorize;
-use crate::git::git_wrapper;
+use crate::core::{ark_remote, branch, repo};
+use crate::git::git_wrapper::{self, GitError};
 
-pub fn run(action: &str, url: Option<&str>) {
+const ORIGIN: &str = "origin";
+
+pub fn run(action: &str, name: Option<&str>, value: Option<&str>, unset: bool) {
     match action {
         "add" => {
-            match url {
-                None => {
-                    eprintln!("{}", "Error: URL required. Usage: ark remote add <url>".red());
-                }
-                Some(u) => {
-                    // Check if remote already exists
-                    if git_wrapper::get_remote().is_some() {
-                        eprintln!("{}", "Error: Remote already exists.".red());
-                        eprintln!("  To update: git remote set-url origin <url>");
-                        return;
-                    }
-
-                    // Init git if not already
-                    if !git_wrapper::is_git_repo() {
-                        git_wrapper::init();
-                    }
-
-                    let result = git_wrapper::set_remote(u);
-                    if result.success {
-                        println!("{} {}", "✓ Remote added:".green().bold(), u.cyan());
-                        println!("{}", "You can now use 'ark sync' to push changes.".dimmed());
-                    } else {
-                        eprintln!("{} {}", "Error:".red().bold(), result.output);
-                    }
-                }
+            match (name, value) {
+                (Some(n), Some(u)) => add(n, u),
+                // `ark remote add <url>` names the remote origin
+                (Some(u), None) => add(ORIGIN, u),
+                _ => eprintln!("{}", "Error: URL required. Usage: ark remote add [<name>] <url>".red()),
             }
         }
-        "show" => {
-            match git_wrapper::get_remote() {
-                Some(remote) => {
-                    println!("{} {}", "Remote:".dimmed(), remote.cyan());
-                }
-                None => {
-                    println!("{}", "No remote configured.".yellow());
-                    println!("{}", "Add one with: ark remote add <url>".dimmed());
+        "list" | "show" => list(),
+        "remove" => {
+            match name {
+                None => eprintln!("{}", "Error: Remote name required. Usage: ark remote remove <name>".red()),
+                Some(n) => remove(n),
+            }
+        }
+        "rename" => {
+            match (name, value) {
+                (Some(old), Some(new)) => rename(old, new),
+                _ => eprintln!("{}", "Error: Usage: ark remote rename <old> <new>".red()),
+            }
+        }
+        "set-url" => {
+            match (name, value) {
+                (Some(n), Some(u)) => {
+                    let result = if ark_remote::url(n).is_some() {
+                        ark_remote::set_url(n, u)
+                    } else {
+                        git_wrapper::set_remote_url(n, u).map_err(|e| e.to_string())
+                    };
+                    match result {
+                        Ok(()) => println!("{} {} {} {}", "✓ Remote updated:".green().bold(), n.cyan(), "→".dimmed(), u.cyan()),
+                        Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+                    }
                 }
+                _ => eprintln!("{}", "Error: Usage: ark remote set-url <name> <url>".red()),
             }
         }
+        "default" => set_default(name, unset),
         _ => {
-            eprintln!("{} Unknown action '{}'. Use: add, show",
+            eprintln!("{} Unknown action '{}'. Use: add, list, remove, rename, set-url, default",
                 "Error:".red().bold(), action);
         }
     }
 }
+
+// Remote used when neither --remote nor the branch's upstream names one:
+// the configured default, else origin, else the only remote there is
+pub fn default_remote() -> String {
+    if let Some(name) = repo::load_config().ok().and_then(|c| c.default_remote) {
+        return name;
+    }
+
+    let remotes = all();
+    match remotes.as_slice() {
+        [(only, _)] => only.clone(),
+        _ => ORIGIN.to_string(),
+    }
+}
+
+// The remote a command uses: the one it was given, else the branch's
+// upstream remote, else the default
+pub fn resolve(explicit: Option<&str>, upstream: Option<&branch::Upstream>) -> String {
+    explicit.map(|r| r.to_string())
+        .or_else(|| upstream.map(|u| u.remote.clone()))
+        .unwrap_or_else(default_remote)
+}
+
+// Git and Ark remotes as (name, url), sorted by name
+pub fn all() -> Vec<(String, String)> {
+    let mut remotes = git_wrapper::remotes();
+    remotes.extend(ark_remote::load());
+    remotes.sort();
+    remotes
+}
+
+pub fn url(name: &str) -> Option<String> {
+    ark_remote::url(name).or_else(|| git_wrapper::get_remote(name))
+}
+
+fn add(name: &str, url: &str) {
+    if ark_remote::is_ark_url(url) {
+        add_ark(name, url);
+        return;
+    }
+
+    if ark_remote::url(name).is_some() {
+        eprintln!("{} Remote '{}' already exists.", "Error:".red().bold(), name);
+        eprintln!("  To update: ark remote set-url {} <url>", name);
+        return;
+    }
+
+    // Init git if not already
+    if !git_wrapper::is_git_repo()
+        && let Err(e) = git_wrapper::init()
+    {
+        eprintln!("{} {}", "Error:".red().bold(), e);
+        return;
+    }
+
+    match git_wrapper::add_remote(name, url) {
+        Ok(()) => {
+            println!("{} {} {}", "✓ Remote added:".green().bold(), name.cyan(), url.cyan());
+            println!("{}", "You can now use 'ark sync' to push changes.".dimmed());
+        }
+        Err(e @ GitError::RemoteExists(_)) => {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            eprintln!("  To update: ark remote set-url {} <url>", name);
+        }
+        Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+    }
+}
+
+// Ark remotes live in .ark, so they need an Ark repository but no git
+fn add_ark(name: &str, url: &str) {
+    if !repo::is_initialized() {
+        eprintln!("{}", "Error: Not an Ark repository. Run 'ark start' first.".red().bold());
+        return;
+    }
+
+    if git_wrapper::get_remote(name).is_some() {
+        eprintln!("{} Remote '{}' already exists.", "Error:".red().bold(), name);
+        eprintln!("  To update: ark remote set-url {} <url>", name);
+        return;
+    }
+
+    match ark_remote::add(name, url) {
+        Ok(()) => {
+            println!("{} {} {} {}", "✓ Ark remote added:".green().bold(), name.cyan(), url.cyan(), "(ark)".dimmed());
+            println!("{}", "You can now use 'ark push' to share your saves, branches and tags.".dimmed());
+        }
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            eprintln!("  To update: ark remote set-url {} <url>", name);
+        }
+    }
+}
+
+fn list() {
+    let remotes = all();
+    if remotes.is_empty() {
+        println!("{}", "No remote configured.".yellow());
+        println!("{}", "Add one with: ark remote add <url>".dimmed());
+        return;
+    }
+
+    let default = default_remote();
+    println!("{}", "Remotes:".bold());
+    for (name, url) in &remotes {
+        let kind = if ark_remote::url(name).is_some() { " (ark)" } else { "" };
+        if *name == default {
+            println!("  {} {} {}{} {}", "→".green().bold(), name.green().bold(), url.dimmed(), kind.dimmed(), "(default)".dimmed());
+        } else {
+            println!("    {} {}{}", name, url.dimmed(), kind.dimmed());
+        }
+    }
+}
+
+fn remove(name: &str) {
+    let result = if ark_remote::url(name).is_some() {
+        ark_remote::remove(name)
+    } else {
+        git_wrapper::remove_remote(name).map_err(|e| e.to_string())
+    };
+    if let Err(e) = result {
+        eprintln!("{} {}", "Error:".red().bold(), e);
+        return;
+    }
+
+    // Nothing should keep pointing at a remote that is gone
+    let cleared = update_references(name, None);
+    println!("{} {}", "✓ Remote removed:".green().bold(), name.cyan());
+    if cleared > 0 {
+        println!("{}", format!("  Cleared the upstream of {} branch(es).", cleared).dimmed());
+    }
+}
+
+fn rename(name: &str, new_name: &str) {
+    let result = if url(new_name).is_some() {
+        Err(format!("Remote '{}' already exists.", new_name))
+    } else if ark_remote::url(name).is_some() {
+        ark_remote::rename(name, new_name)
+    } else {
+        git_wrapper::rename_remote(name, new_name).map_err(|e| e.to_string())
+    };
+    if let Err(e) = result {
+        eprintln!("{} {}", "Error:".red().bold(), e);
+        return;
+    }
+
+    update_references(name, Some(new_name));
+    println!("{} {} {} {}",
+        "✓ Remote renamed:".green().bold(),
+        name.red(),
+        "→".dimmed(),
+        new_name.cyan()
+    );
+}
+
+// Point branch upstreams and the default remote at `new_name`, or clear
+// them when the remote was removed. Returns the number of branches touched.
+fn update_references(name: &str, new_name: Option<&str>) -> usize {
+    if !repo::is_initialized() {
+        return 0;
+    }
+
+    let mut touched = 0;
+    for b in branch::list_branches() {
+        let Ok(mut data) = branch::load_branch(&b) else { continue };
+        if data.upstream.as_ref().is_none_or(|u| u.remote != name) {
+            continue;
+        }
+
+        data.upstream = match new_name {
+            Some(new) => data.upstream.map(|u| branch::Upstream { remote: new.to_string(), ..u }),
+            None => None,
+        };
+        if let Err(e) = branch::save_branch(&data) {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            continue;
+        }
+        touched += 1;
+    }
+
+    if let Ok(mut config) = repo::load_config()
+        && config.default_remote.as_deref() == Some(name)
+    {
+        config.default_remote = new_name.map(|n| n.to_string());
+        if let Err(e) = repo::save_config(&config) {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+        }
+    }
+
+    touched
+}
+
+// Show or set the remote push, pull and sync use by default
+fn set_default(name: Option<&str>, unset: bool) {
+    if !repo::is_initialized() {
+        eprintln!("{}", "Error: Not an Ark repository. Run 'ark start' first.".red().bold());
+        return;
+    }
+
+    let mut config = match repo::load_config() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            return;
+        }
+    };
+
+    if !unset && name.is_none() {
+        println!("{} {}", "Default remote:".dimmed(), default_remote().cyan());
+        return;
+    }
+
+    if let Some(n) = name
+        && url(n).is_none()
+    {
+        eprintln!("{} Remote '{}' not found.", "Error:".red().bold(), n);
+        return;
+    }
+
+    config.default_remote = if unset { None } else { name.map(|n| n.to_string()) };
+    match repo::save_config(&config) {
+        Ok(()) => println!("{} {}", "✓ Default remote:".green().bold(), default_remote().cyan()),
+        Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+    }
+}
@@ -1,7 +1,8 @@
 use colored::Colorize;
-use crate::core::{repo, tracker, commit};
+use crate::core::{repo, tracker, commit, branch};
+use crate::cli::{lint, scan};
 
-pub fn run(message: Option<String>) {
+pub fn run(message: Option<String>, allow_secrets: bool, fail_on: Option<&str>) {
     if !repo::is_initialized() {
         eprintln!("{}", "Error: Not an Ark repository. Run 'ark start' first.".red().bold());
         return;
@@ -14,16 +15,37 @@ pub fn run(message: Option<String>) {
         return;
     }
 
+    // Use provided message or generate one
+    let (msg, generated) = match message {
+        Some(m) if !m.trim().is_empty() => (m, false),
+        _ => (crate::core::message::generate(&changes), true),
+    };
+
+    let current = branch::get_current_branch();
+    let passed = if generated { lint::generated_gate(&msg, &current) } else { lint::message_gate(&msg, &current) };
+    if !passed {
+        return;
+    }
+
+    // Refuse to save secrets unless explicitly overridden
+    let allowed_secrets = match scan::secret_gate(&changed_paths(&changes), allow_secrets) {
+        Some(findings) => findings,
+        None => return,
+    };
+
+    // Optional AI review gate
+    if let Some(severity) = fail_on
+        && !crate::cli::ai::review_gate(&changes, severity)
+    {
+        return;
+    }
+
     // Build current snapshot from scanned files
     let snapshot = tracker::build_snapshot();
 
-    // Use provided message or generate one
-    let msg = match message {
-        Some(m) if !m.trim().is_empty() => m,
-        _ => generate_message(&changes),
-    };
+    let options = commit::CommitOptions { allowed_secrets, ..Default::default() };
 
-    match commit::save_commit(&msg, snapshot) {
+    match commit::save_commit_with(&msg, snapshot, options) {
         Ok(id) => {
             println!("{}", "Changes saved successfully!".green().bold());
             println!("  {} {}", "id:".dimmed(), id.cyan());
@@ -41,15 +63,10 @@ pub fn run(message: Option<String>) {
     }
 }
 
-fn generate_message(changes: &[tracker::FileStatus]) -> String {
-    let new = changes.iter().filter(|f| f.status == tracker::Status::New).count();
-    let modified = changes.iter().filter(|f| f.status == tracker::Status::Modified).count();
-    let deleted = changes.iter().filter(|f| f.status == tracker::Status::Deleted).count();
-
-    let mut parts = Vec::new();
-    if new > 0 { parts.push(format!("{} new", new)); }
-    if modified > 0 { parts.push(format!("{} modified", modified)); }
-    if deleted > 0 { parts.push(format!("{} deleted", deleted)); }
-
-    format!("Auto: {}", parts.join(", "))
+// Files that will be written into the new snapshot
+pub fn changed_paths(changes: &[tracker::FileStatus]) -> Vec<String> {
+    changes.iter()
+        .filter(|f| f.status != tracker::Status::Deleted)
+        .map(|f| f.path.clone())
+        .collect()
 }
@@ -1,18 +1,39 @@
 use colored::Colorize;
-use crate::core::repo;
-use crate::security::scanner;
+use std::collections::HashMap;
+use crate::core::{repo, commit};
+use crate::security::{scanner, report};
+use crate::cli::{stash, tag};
 
-pub fn run() {
+pub fn run(history: bool, format: &str, reveal: bool) {
     if !repo::is_initialized() {
         eprintln!("{}", "Error: Not an Ark repository. Run 'ark start' first.".red().bold());
         return;
     }
 
+    if !["text", "json", "sarif"].contains(&format) {
+        eprintln!("{} Unknown format '{}'. Use: text, json, sarif",
+            "Error:".red().bold(), format);
+        return;
+    }
+
+    if history {
+        scan_history(format, reveal);
+        return;
+    }
+
+    let mut results = scanner::scan_files();
+    if !reveal {
+        scanner::redact(&mut results);
+    }
+
+    if format != "text" {
+        print_report(&results, format);
+        return;
+    }
+
     println!("{}", "Scanning for secrets and sensitive data...".dimmed());
     println!();
 
-    let results = scanner::scan_files();
-
     if results.is_empty() {
         println!("{}", "✓ No secrets or sensitive data found.".green().bold());
         return;
@@ -31,3 +52,120 @@ pub fn run() {
 
     println!("{}", "⚠ Review these files before syncing to remote.".yellow().bold());
 }
+
+// Scan every saved version reachable from branches, tags and stashes
+fn scan_history(format: &str, reveal: bool) {
+    let mut ids = commit::all_commit_ids();
+    for t in tag::load_tags() {
+        if !ids.contains(&t.commit_id) {
+            ids.push(t.commit_id);
+        }
+    }
+
+    // Findings are credited to the first save that has them
+    let mut sources: Vec<(String, HashMap<String, String>)> = commit::parents_first(&ids).into_iter()
+        .map(|c| (c.id, c.files_snapshot))
+        .collect();
+
+    for s in stash::load_stashes() {
+        sources.push((format!("stash@{}", s.id), s.snapshot));
+    }
+
+    let mut scan = scanner::scan_snapshots(&sources);
+    if !reveal {
+        scanner::redact(&mut scan.results);
+    }
+
+    if format != "text" {
+        print_report(&scan.results, format);
+        return;
+    }
+
+    println!("{}", "Scanning saved history for secrets and sensitive data...".dimmed());
+    println!();
+
+    println!("  {} {} saves, {} unique file versions",
+        "scanned:".dimmed(),
+        sources.len().to_string().cyan(),
+        scan.blobs_scanned.to_string().cyan()
+    );
+    if scan.blobs_missing > 0 {
+        println!("  {} {} file versions saved before content was retained",
+            "skipped:".dimmed(),
+            scan.blobs_missing.to_string().yellow()
+        );
+    }
+    println!();
+
+    if scan.results.is_empty() {
+        println!("{}", "✓ No secrets or sensitive data found in history.".green().bold());
+        return;
+    }
+
+    println!("{}", format!("⚠ Found {} potential issue(s) in history:", scan.results.len()).red().bold());
+    println!();
+
+    for result in &scan.results {
+        println!("  {} {}", "file:".dimmed(), result.file.yellow().bold());
+        println!("  {} {}", "line:".dimmed(), result.line_number.to_string().cyan());
+        println!("  {} {}", "introduced:".dimmed(), result.commit.clone().unwrap_or_default().cyan());
+        println!("  {} {}", "issue:".dimmed(), result.issue.red());
+        println!("  {} {}", "code:".dimmed(), result.line.dimmed());
+        println!();
+    }
+
+    println!("{}", "⚠ Deleting a file does not remove it from history. Rotate these secrets.".yellow().bold());
+}
+
+// Machine-readable output goes to stdout without any decoration
+fn print_report(results: &[scanner::ScanResult], format: &str) {
+    match format {
+        "json" => println!("{}", report::to_json(results)),
+        "sarif" => println!("{}", report::to_sarif(results)),
+        _ => {}
+    }
+}
+
+// Pre-save / pre-push secret gate over the changed files.
+// Returns None when the operation must be aborted, otherwise the
+// findings that were explicitly allowed (empty when the files are clean).
+pub fn secret_gate(paths: &[String], allow_secrets: bool) -> Option<Vec<String>> {
+    findings_gate(scanner::scan_paths(paths), allow_secrets)
+}
+
+// The gate over findings that were already collected, e.g. from saved
+// file versions instead of the working tree
+pub fn findings_gate(mut results: Vec<scanner::ScanResult>, allow_secrets: bool) -> Option<Vec<String>> {
+    scanner::redact(&mut results);
+
+    if results.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let findings: Vec<String> = results.iter()
+        .map(|r| format!("{}:{} {}", r.file, r.line_number, r.issue))
+        .collect();
+
+    if allow_secrets {
+        println!("{}", format!("⚠ Proceeding with {} potential secret(s) (--allow-secrets):", results.len()).yellow().bold());
+        for finding in &findings {
+            println!("  {} {}", "!".yellow(), finding.yellow());
+        }
+        println!();
+        return Some(findings);
+    }
+
+    eprintln!("{}", format!("✗ Blocked: found {} potential secret(s) in changed files:", results.len()).red().bold());
+    eprintln!();
+
+    for result in &results {
+        eprintln!("  {} {}", "file:".dimmed(), result.file.yellow().bold());
+        eprintln!("  {} {}", "line:".dimmed(), result.line_number.to_string().cyan());
+        eprintln!("  {} {}", "issue:".dimmed(), result.issue.red());
+        eprintln!("  {} {}", "code:".dimmed(), result.line.dimmed());
+        eprintln!();
+    }
+
+    eprintln!("{}", "Remove the secrets, or re-run with --allow-secrets to override.".yellow());
+    None
+}
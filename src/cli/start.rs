@@ -2,9 +2,10 @@ use std::env;
 use std::io::{self, Write};
 use colored::Colorize;
 use crate::core::repo;
+use crate::cli::import;
 use crate::git::git_wrapper;
 
-pub fn run() {
+pub fn run(first_parent: bool) {
     let project_name = env::current_dir()
         .ok()
         .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
@@ -19,6 +20,12 @@ pub fn run() {
             println!("  {} .ark/config.json", "created".cyan());
             println!();
 
+            // Bring in the history of a git checkout rooted here
+            if git_wrapper::is_worktree_root() && git_wrapper::rev_parse("HEAD").is_some() {
+                import::import_history(first_parent);
+                println!();
+            }
+
             // Ask for remote setup
             print!("{}", "Do you want to add a GitHub remote? (y/n): ".dimmed());
             io::stdout().flush().unwrap();
@@ -35,15 +42,16 @@ pub fn run() {
                 let url = url.trim();
 
                 if !url.is_empty() {
-                    if !git_wrapper::is_git_repo() {
-                        git_wrapper::init();
+                    if !git_wrapper::is_git_repo()
+                        && let Err(e) = git_wrapper::init()
+                    {
+                        eprintln!("{} {}", "Error:".red().bold(), e);
+                        return;
                     }
 
-                    let result = git_wrapper::set_remote(url);
-                    if result.success {
-                        println!("{} {}", "✓ Remote added:".green().bold(), url.cyan());
-                    } else {
-                        eprintln!("{} {}", "Error adding remote:".red(), result.output);
+                    match git_wrapper::add_remote("origin", url) {
+                        Ok(()) => println!("{} {}", "✓ Remote added:".green().bold(), url.cyan()),
+                        Err(e) => eprintln!("{} {}", "Error adding remote:".red(), e),
                     }
                 }
             }
@@ -2,7 +2,7 @@ use colored::Colorize;
 use std::fs;
 use std::path::Path;
 use serde::{Deserialize, Serialize};
-use crate::core::{repo, tracker};
+use crate::core::{repo, tracker, object};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Stash {
@@ -44,6 +44,10 @@ fn stash_save(message: Option<&str>) {
     let id = stashes.len();
 
     let snapshot = tracker::build_snapshot();
+    if let Err(e) = object::store_snapshot(&snapshot) {
+        eprintln!("{} {}", "Error:".red().bold(), e);
+        return;
+    }
     let msg = message.unwrap_or("WIP stash").to_string();
 
     let stash = Stash {
@@ -125,7 +129,7 @@ fn stash_drop() {
     println!("  {} {}", "message:".dimmed(), stash.message);
 }
 
-fn load_stashes() -> Vec<Stash> {
+pub fn load_stashes() -> Vec<Stash> {
     let path = Path::new(".ark/stash/stashes.json");
     if !path.exists() {
         return Vec::new();
@@ -1,76 +1,40 @@
 use colored::Colorize;
 use crate::core::repo;
-use crate::git::git_wrapper;
+use crate::cli::{pull, push};
+use crate::git::git_wrapper::{self, GitError};
 
-pub fn run() {
+pub fn run(allow_secrets: bool, remote: Option<&str>) {
     if !repo::is_initialized() {
         eprintln!("{}", "Error: Not an Ark repository. Run 'ark start' first.".red().bold());
         return;
     }
 
-    if !git_wrapper::is_git_repo() {
-        println!("{}", "Initializing Git backend...".dimmed());
-        let result = git_wrapper::init();
-        if !result.success {
-            eprintln!("{} {}", "Error:".red().bold(), result.output);
-            return;
-        }
-        println!("{}", "✓ Git initialized.".green());
-    }
-
-    // Check remote
-    match git_wrapper::get_remote() {
-        None => {
-            println!("{}", "No remote configured.".yellow().bold());
-            println!("{}", "Add a remote first:".dimmed());
-            println!("  ark remote add <your-github-url>");
-            println!();
-            println!("{}", "Example:".dimmed());
-            println!("  ark remote add https://github.com/username/repo.git");
-            return;
-        }
-        Some(remote) => {
-            println!("  {} {}", "remote:".dimmed(), remote.cyan());
-        }
-    }
+    let target = match push::prepare(remote) {
+        Some(t) => t,
+        None => return,
+    };
 
     // Pull
     println!("{}", "Pulling latest changes...".dimmed());
-    let pull = git_wrapper::pull();
-    if pull.success {
-        println!("{}", "✓ Pull successful.".green());
-    } else {
-        println!("{} {}", "⚠ Pull warning:".yellow(), pull.output.dimmed());
-    }
-
-    // Stage
-    println!("{}", "Staging changes...".dimmed());
-    let add = git_wrapper::add_all();
-    if !add.success {
-        eprintln!("{} {}", "Error staging files:".red().bold(), add.output);
+    if let Some(url) = &target.ark_url {
+        if pull::pull_ark(&target, url, false) {
+            push::push_ark(&target, url, allow_secrets, false);
+        }
         return;
     }
 
-    // Commit
-    println!("{}", "Committing changes...".dimmed());
-    let commit = git_wrapper::commit("ark sync");
-    if !commit.success {
-        if commit.output.contains("nothing to commit") {
-            println!("{}", "✓ Nothing new to commit.".green());
-        } else {
-            eprintln!("{} {}", "Error committing:".red().bold(), commit.output);
+    match git_wrapper::pull(&target.remote, &target.remote_branch) {
+        Ok(outcome) => println!("{}", pull::describe(&outcome).green()),
+        Err(e @ GitError::Conflicts(_)) => {
+            eprintln!("{} {}", "Error pulling:".red().bold(), e);
             return;
         }
-    } else {
-        println!("{}", "✓ Changes committed.".green());
+        Err(e) => println!("{} {}", "⚠ Pull warning:".yellow(), e.to_string().dimmed()),
     }
 
-    // Push with upstream set
-    println!("{}", "Pushing to remote...".dimmed());
-    let push = git_wrapper::push();
-    if push.success {
-        println!("{}", "✓ Pushed successfully!".green().bold());
-    } else {
-        eprintln!("{} {}", "Error pushing:".red().bold(), push.output);
+    if !push::export(&target, allow_secrets) {
+        return;
     }
+
+    push::push_target(&target, false);
 }
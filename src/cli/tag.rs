@@ -76,27 +76,7 @@ fn create_tag(name: Option<&str>, message: Option<&str>) {
 }
 
 fn list_tags() {
-    let tags_dir = Path::new(".ark/tags");
-
-    if !tags_dir.exists() {
-        println!("{}", "No tags found.".yellow());
-        return;
-    }
-
-    let mut tags: Vec<Tag> = Vec::new();
-
-    if let Ok(entries) = fs::read_dir(tags_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.extension().map(|e| e == "json").unwrap_or(false) {
-                if let Ok(content) = fs::read_to_string(&path) {
-                    if let Ok(tag) = serde_json::from_str::<Tag>(&content) {
-                        tags.push(tag);
-                    }
-                }
-            }
-        }
-    }
+    let tags = load_tags();
 
     if tags.is_empty() {
         println!("{}", "No tags found.".yellow());
@@ -117,6 +97,26 @@ fn list_tags() {
     }
 }
 
+pub fn load_tags() -> Vec<Tag> {
+    let tags_dir = Path::new(".ark/tags");
+    let mut tags: Vec<Tag> = Vec::new();
+
+    if let Ok(entries) = fs::read_dir(tags_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().map(|e| e == "json").unwrap_or(false)
+                && let Ok(content) = fs::read_to_string(&path)
+                && let Ok(tag) = serde_json::from_str::<Tag>(&content)
+            {
+                tags.push(tag);
+            }
+        }
+    }
+
+    tags.sort_by(|a, b| a.name.cmp(&b.name));
+    tags
+}
+
 fn delete_tag(name: Option<&str>) {
     let tag_name = match name {
         Some(n) => n,
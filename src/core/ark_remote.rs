@@ -0,0 +1,428 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::collections::BTreeMap;
+use crate::core::{branch, commit, merge, object, tracker};
+use crate::cli::tag::Tag;
+
+// Ark remotes are directories laid out like .ark (commits, objects,
+// branches, tags) without a working tree, e.g. file:///srv/ark/project.ark.
+// They are listed in .ark/remotes.json; the branches last fetched from
+// each are kept under .ark/refs/<remote>/.
+
+const REMOTES_FILE: &str = ".ark/remotes.json";
+const REFS_DIR: &str = ".ark/refs";
+const LOCAL: &str = ".ark";
+const STORE_DIRS: [&str; 4] = ["commits", "objects", "branches", "tags"];
+// Held on the remote while a push updates it
+const LOCK_FILE: &str = "push.lock";
+
+// What a push or pull did to the branch it updated
+#[derive(Debug, PartialEq)]
+pub enum Update {
+    Created,
+    UpToDate,
+    FastForward,
+    Forced,
+}
+
+#[derive(Debug)]
+pub struct PushSummary {
+    pub update: Update,
+    pub commits: usize,
+    pub tags: usize,
+    // Tags that point elsewhere on the remote and were left alone
+    pub skipped_tags: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct FetchSummary {
+    pub commits: usize,
+    pub branches: usize,
+    pub tags: usize,
+}
+
+// URLs whose path ends in .ark name an Ark remote; anything else is git
+pub fn is_ark_url(url: &str) -> bool {
+    path(url).extension().is_some_and(|e| e == "ark")
+}
+
+pub fn path(url: &str) -> PathBuf {
+    PathBuf::from(url.strip_prefix("file://").unwrap_or(url).trim_end_matches('/'))
+}
+
+pub fn load() -> BTreeMap<String, String> {
+    fs::read_to_string(REMOTES_FILE)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save(remotes: &BTreeMap<String, String>) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(remotes)
+        .map_err(|e| format!("Failed to serialize remotes: {}", e))?;
+
+    fs::write(REMOTES_FILE, json)
+        .map_err(|e| format!("Failed to write remotes: {}", e))
+}
+
+pub fn url(name: &str) -> Option<String> {
+    load().get(name).cloned()
+}
+
+pub fn add(name: &str, url: &str) -> Result<(), String> {
+    let mut remotes = load();
+    if remotes.contains_key(name) {
+        return Err(format!("Remote '{}' already exists.", name));
+    }
+    remotes.insert(name.to_string(), url.to_string());
+    save(&remotes)
+}
+
+pub fn remove(name: &str) -> Result<(), String> {
+    let mut remotes = load();
+    if remotes.remove(name).is_none() {
+        return Err(format!("Remote '{}' not found.", name));
+    }
+    save(&remotes)?;
+
+    let refs = Path::new(REFS_DIR).join(name);
+    if refs.exists() {
+        fs::remove_dir_all(&refs)
+            .map_err(|e| format!("Failed to remove {}: {}", refs.display(), e))?;
+    }
+    Ok(())
+}
+
+pub fn rename(name: &str, new_name: &str) -> Result<(), String> {
+    let mut remotes = load();
+    let url = remotes.remove(name)
+        .ok_or_else(|| format!("Remote '{}' not found.", name))?;
+    remotes.insert(new_name.to_string(), url);
+    save(&remotes)?;
+
+    let refs = Path::new(REFS_DIR).join(name);
+    if refs.exists() {
+        fs::rename(&refs, Path::new(REFS_DIR).join(new_name))
+            .map_err(|e| format!("Failed to rename {}: {}", refs.display(), e))?;
+    }
+    Ok(())
+}
+
+pub fn set_url(name: &str, url: &str) -> Result<(), String> {
+    let mut remotes = load();
+    match remotes.get_mut(name) {
+        Some(u) => *u = url.to_string(),
+        None => return Err(format!("Remote '{}' not found.", name)),
+    }
+    save(&remotes)
+}
+
+// Saves of a local branch that the remote doesn't have yet
+pub fn unpushed(url: &str, branch_name: &str) -> Vec<String> {
+    let root = path(url);
+    branch::load_branch(branch_name)
+        .map(|b| b.commit_ids)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|id| !commit_file(&root, id).exists())
+        .collect()
+}
+
+// Send a branch with everything its history needs. The remote branch
+// only moves forward unless `force` is set.
+pub fn push(url: &str, branch_name: &str, remote_branch: &str, force: bool) -> Result<PushSummary, String> {
+    let root = path(url);
+    for dir in STORE_DIRS {
+        fs::create_dir_all(root.join(dir))
+            .map_err(|e| format!("Failed to create {}: {}", root.join(dir).display(), e))?;
+    }
+
+    let local = branch::load_branch(branch_name)?;
+    let _lock = Lock::acquire(&root)?;
+    let update = match read_branch(&root, remote_branch)? {
+        None => Update::Created,
+        Some(r) if same_history(&r, &local) => Update::UpToDate,
+        Some(r) if fast_forward(&r, &local) => Update::FastForward,
+        Some(_) if force => Update::Forced,
+        Some(_) => return Err(format!(
+            "Rejected: '{}' on the remote has saves that '{}' doesn't. Pull first, or push with --force to replace them.",
+            remote_branch, branch_name
+        )),
+    };
+
+    let ids = tip(&local).map(|t| merge::ancestors(t)).unwrap_or_default();
+    let commits = copy_commits(Path::new(LOCAL), &root, &ids)?;
+
+    write_branch(&root, &branch::Branch {
+        name: remote_branch.to_string(),
+        commit_ids: local.commit_ids,
+        base: local.base,
+        upstream: None,
+    })?;
+
+    let (tags, skipped_tags) = copy_tags(Path::new(LOCAL), &root, force)?;
+
+    Ok(PushSummary { update, commits, tags, skipped_tags })
+}
+
+// Copy every commit, object and tag the remote has, and record its
+// branches under .ark/refs/<name>/. Local branches are not touched.
+pub fn fetch(name: &str, url: &str) -> Result<FetchSummary, String> {
+    let root = path(url);
+    if !root.join("branches").is_dir() || !root.join("commits").is_dir() {
+        return Err(format!("{} is not an Ark remote.", root.display()));
+    }
+
+    let ids: Vec<String> = json_stems(&root.join("commits"));
+    let mut summary = FetchSummary {
+        commits: copy_commits(&root, Path::new(LOCAL), &ids)?,
+        ..Default::default()
+    };
+
+    let refs = Path::new(REFS_DIR).join(name);
+    fs::create_dir_all(&refs)
+        .map_err(|e| format!("Failed to create {}: {}", refs.display(), e))?;
+
+    let remote_branches = json_stems(&root.join("branches"));
+    for b in &remote_branches {
+        let content = fs::read_to_string(root.join("branches").join(format!("{}.json", b)))
+            .map_err(|e| format!("Failed to read remote branch '{}': {}", b, e))?;
+        let tracked = refs.join(format!("{}.json", b));
+        if fs::read_to_string(&tracked).ok().as_deref() != Some(content.as_str()) {
+            fs::write(&tracked, content)
+                .map_err(|e| format!("Failed to write {}: {}", tracked.display(), e))?;
+            summary.branches += 1;
+        }
+    }
+
+    // Branches deleted on the remote
+    for b in json_stems(&refs) {
+        if !remote_branches.contains(&b) {
+            let _ = fs::remove_file(refs.join(format!("{}.json", b)));
+        }
+    }
+
+    // Local tags win over the remote's
+    summary.tags = copy_tags(&root, Path::new(LOCAL), false)?.0;
+
+    Ok(summary)
+}
+
+// Branch `branch_name` of remote `name` as of the last fetch
+pub fn tracking(name: &str, branch_name: &str) -> Option<branch::Branch> {
+    let content = fs::read_to_string(Path::new(REFS_DIR).join(name).join(format!("{}.json", branch_name))).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+// Move the current branch to the fetched remote branch and update the
+// working tree. Diverged histories are refused unless `force` is set, in
+// which case local saves the remote doesn't have are dropped from the
+// branch. Returns None when the remote has no such branch.
+pub fn integrate(remote: &str, branch_name: &str, remote_branch: &str, force: bool) -> Result<Option<Update>, String> {
+    let Some(theirs) = tracking(remote, remote_branch) else { return Ok(None) };
+    let mut ours = branch::load_branch(branch_name)?;
+
+    let update = if same_history(&ours, &theirs) || fast_forward(&theirs, &ours) {
+        return Ok(Some(Update::UpToDate));
+    } else if fast_forward(&ours, &theirs) {
+        Update::FastForward
+    } else if force {
+        Update::Forced
+    } else {
+        return Err(format!(
+            "'{}' and {}/{} have diverged. Merge them by hand, or pull with --force to replace your saves with the remote's.",
+            branch_name, remote, remote_branch
+        ));
+    };
+
+    if !tracker::scan_changes().is_empty() {
+        return Err("You have unsaved changes. Save or stash them before pulling.".to_string());
+    }
+
+    let old = tracker::load_snapshot();
+    let new = match tip(&theirs) {
+        Some(id) => commit::load_commit(id)?.files_snapshot,
+        None => Default::default(),
+    };
+
+    // Check every object is here before touching the working tree
+    let mut writes = Vec::new();
+    for (path, hash) in &new {
+        if old.get(path) != Some(hash) {
+            let content = object::load(hash)
+                .ok_or_else(|| format!("Content of {} is missing; fetch again.", path))?;
+            writes.push((path, content));
+        }
+    }
+
+    for path in old.keys().filter(|p| !new.contains_key(*p)) {
+        tracker::write_file(path, None)?;
+    }
+    for (path, content) in &writes {
+        tracker::write_file(path, Some(content))?;
+    }
+
+    ours.commit_ids = theirs.commit_ids;
+    ours.base = theirs.base;
+    branch::save_branch(&ours)?;
+
+    let json = serde_json::to_string_pretty(&new)
+        .map_err(|e| format!("Failed to serialize snapshot: {}", e))?;
+    fs::write(format!(".ark/snapshots/{}.json", branch_name), json)
+        .map_err(|e| format!("Failed to write snapshot: {}", e))?;
+
+    Ok(Some(update))
+}
+
+fn tip(b: &branch::Branch) -> Option<&String> {
+    b.commit_ids.last().or(b.base.as_ref())
+}
+
+fn same_history(a: &branch::Branch, b: &branch::Branch) -> bool {
+    a.base == b.base && a.commit_ids == b.commit_ids
+}
+
+// `new` keeps all of `old`'s history: it starts from the same commit and
+// `old`'s saves come first
+fn fast_forward(old: &branch::Branch, new: &branch::Branch) -> bool {
+    tip(old).is_none() || (old.base == new.base && new.commit_ids.starts_with(&old.commit_ids))
+}
+
+fn commit_file(root: &Path, id: &str) -> PathBuf {
+    root.join("commits").join(format!("{}.json", id))
+}
+
+// A lock file only one push can create; removed when dropped
+struct Lock(PathBuf);
+
+impl Lock {
+    fn acquire(root: &Path) -> Result<Lock, String> {
+        let path = root.join(LOCK_FILE);
+        match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(_) => Ok(Lock(path)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => Err(format!(
+                "Another push to {} is in progress. If it isn't, remove {}.",
+                root.display(), path.display()
+            )),
+            Err(e) => Err(format!("Failed to lock {}: {}", root.display(), e)),
+        }
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+// None only when the remote has no such branch
+fn read_branch(root: &Path, name: &str) -> Result<Option<branch::Branch>, String> {
+    let content = match fs::read_to_string(root.join("branches").join(format!("{}.json", name))) {
+        Ok(c) => c,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read remote branch '{}': {}", name, e)),
+    };
+
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse remote branch '{}': {}", name, e))
+}
+
+// Written to a temporary file first, so readers never see half a branch
+fn write_branch(root: &Path, b: &branch::Branch) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(b)
+        .map_err(|e| format!("Failed to serialize branch: {}", e))?;
+
+    let target = root.join("branches").join(format!("{}.json", b.name));
+    let temp = root.join("branches").join(format!("{}.json.tmp", b.name));
+    fs::write(&temp, json)
+        .map_err(|e| format!("Failed to write branch: {}", e))?;
+    fs::rename(&temp, &target)
+        .map_err(|e| format!("Failed to write branch: {}", e))
+}
+
+// Names of the .json files in a directory
+fn json_stems(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .map(|entries| entries.flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == "json"))
+            .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().to_string()))
+            .collect())
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+// Copy commits `to` doesn't have, objects first, so a commit never
+// arrives without its content. Returns the number of commits copied.
+fn copy_commits(from: &Path, to: &Path, ids: &[String]) -> Result<usize, String> {
+    fs::create_dir_all(to.join("objects"))
+        .map_err(|e| format!("Failed to create objects directory: {}", e))?;
+
+    let mut count = 0;
+    for id in ids {
+        if commit_file(to, id).exists() {
+            continue;
+        }
+
+        let content = fs::read_to_string(commit_file(from, id))
+            .map_err(|_| format!("Commit '{}' not found.", id))?;
+        let c: commit::Commit = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse commit: {}", e))?;
+
+        for hash in c.files_snapshot.values() {
+            let source = from.join("objects").join(hash);
+            let target = to.join("objects").join(hash);
+            // Content that was never retained stays missing on both sides
+            if !target.exists() && source.exists() {
+                fs::copy(&source, &target)
+                    .map_err(|e| format!("Failed to copy object {}: {}", hash, e))?;
+            }
+        }
+
+        fs::write(commit_file(to, id), content)
+            .map_err(|e| format!("Failed to write commit: {}", e))?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+// Copy tags whose commit `to` has. A tag `to` already has on another
+// commit is only replaced with `force`. Returns copied and skipped tags.
+fn copy_tags(from: &Path, to: &Path, force: bool) -> Result<(usize, Vec<String>), String> {
+    fs::create_dir_all(to.join("tags"))
+        .map_err(|e| format!("Failed to create tags directory: {}", e))?;
+
+    let mut copied = 0;
+    let mut skipped = Vec::new();
+
+    for name in json_stems(&from.join("tags")) {
+        let file = format!("{}.json", name);
+        let content = fs::read_to_string(from.join("tags").join(&file))
+            .map_err(|e| format!("Failed to read tag '{}': {}", name, e))?;
+        let Ok(t) = serde_json::from_str::<Tag>(&content) else { continue };
+        if !commit_file(to, &t.commit_id).exists() {
+            continue;
+        }
+
+        let existing = fs::read_to_string(to.join("tags").join(&file)).ok()
+            .and_then(|c| serde_json::from_str::<Tag>(&c).ok());
+        match existing {
+            Some(e) if e.commit_id == t.commit_id => continue,
+            Some(_) if !force => {
+                skipped.push(name);
+                continue;
+            }
+            _ => {}
+        }
+
+        fs::write(to.join("tags").join(&file), content)
+            .map_err(|e| format!("Failed to write tag: {}", e))?;
+        copied += 1;
+    }
+
+    Ok((copied, skipped))
+}
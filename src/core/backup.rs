@@ -0,0 +1,88 @@
+use std::fs;
+use std::path::Path;
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::core::object;
+
+// Backups of files taken before Ark itself rewrites them (e.g. `ark ai fix
+// --apply`). Each entry maps a path to the hash of its previous content in
+// the object store, or None when the file did not exist.
+const BACKUPS_FILE: &str = ".ark/backups.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Backup {
+    pub id: usize,
+    pub message: String,
+    pub timestamp: String,
+    pub files: HashMap<String, Option<String>>,
+}
+
+pub fn load_backups() -> Vec<Backup> {
+    fs::read_to_string(BACKUPS_FILE)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_backups(backups: &[Backup]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(backups)
+        .map_err(|e| format!("Failed to serialize backups: {}", e))?;
+
+    fs::write(BACKUPS_FILE, json)
+        .map_err(|e| format!("Failed to write backups: {}", e))
+}
+
+pub fn create(message: &str, paths: &[String]) -> Result<Backup, String> {
+    let mut files = HashMap::new();
+
+    for path in paths {
+        let hash = match fs::read(path) {
+            Ok(content) => Some(object::store(&content)?),
+            Err(_) => None,
+        };
+        files.insert(path.clone(), hash);
+    }
+
+    let mut backups = load_backups();
+    let backup = Backup {
+        id: backups.last().map(|b| b.id + 1).unwrap_or(0),
+        message: message.to_string(),
+        timestamp: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        files,
+    };
+
+    backups.push(backup.clone());
+    save_backups(&backups)?;
+
+    Ok(backup)
+}
+
+// Put every file of the latest backup back the way it was and drop the entry
+pub fn restore_latest() -> Result<Option<Backup>, String> {
+    let mut backups = load_backups();
+
+    let backup = match backups.pop() {
+        Some(b) => b,
+        None => return Ok(None),
+    };
+
+    for (path, hash) in &backup.files {
+        match hash {
+            Some(hash) => {
+                let content = object::load(hash)
+                    .ok_or_else(|| format!("Failed to restore {}: backup content missing", path))?;
+                fs::write(path, content)
+                    .map_err(|e| format!("Failed to restore {}: {}", path, e))?;
+            }
+            None => {
+                if Path::new(path).exists() {
+                    fs::remove_file(path)
+                        .map_err(|e| format!("Failed to remove {}: {}", path, e))?;
+                }
+            }
+        }
+    }
+
+    save_backups(&backups)?;
+    Ok(Some(backup))
+}
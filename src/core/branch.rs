@@ -6,6 +6,18 @@ use serde::{Deserialize, Serialize};
 pub struct Branch {
     pub name: String,
     pub commit_ids: Vec<String>,
+    // Commit the branch was created from, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    // Where `ark push` and `ark pull` go for this branch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<Upstream>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Upstream {
+    pub remote: String,
+    pub branch: String,
 }
 
 pub fn get_current_branch() -> String {
@@ -43,10 +55,17 @@ pub fn create_branch(name: &str) -> Result<(), String> {
             .map_err(|e| format!("Failed to copy snapshot: {}", e))?;
     }
 
-    // New branch starts with empty commit history
+    // New branch starts with empty commit history on top of the
+    // current branch's latest commit
+    let base = load_branch(&current)
+        .ok()
+        .and_then(|b| b.commit_ids.last().cloned().or(b.base));
+
     let branch = Branch {
         name: name.to_string(),
         commit_ids: Vec::new(),
+        base,
+        upstream: None,
     };
 
     save_branch(&branch)
@@ -80,10 +99,10 @@ pub fn list_branches() -> Vec<String> {
     if let Ok(entries) = fs::read_dir(dir) {
         for entry in entries.flatten() {
             let path = entry.path();
-            if path.extension().map(|e| e == "json").unwrap_or(false) {
-                if let Some(stem) = path.file_stem() {
-                    branches.push(stem.to_string_lossy().to_string());
-                }
+            if path.extension().map(|e| e == "json").unwrap_or(false)
+                && let Some(stem) = path.file_stem()
+            {
+                branches.push(stem.to_string_lossy().to_string());
             }
         }
     }
@@ -0,0 +1,163 @@
+use crate::core::conventional;
+
+// Keep a Changelog (https://keepachangelog.com) sections, in file order
+pub const SECTIONS: &[&str] = &["Added", "Changed", "Deprecated", "Removed", "Fixed", "Security"];
+
+pub const FILE: &str = "CHANGELOG.md";
+
+const HEADER: &str = "# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/).
+";
+
+// Messages that say nothing about what changed
+const GENERIC: &[&str] = &[
+    "update", "updates", "updated", "wip", "fix", "fixes", "fixed", "changes", "change",
+    "misc", "stuff", "cleanup", "minor", "tweaks", "save", "commit", "work", "more",
+];
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub section: &'static str,
+    pub text: String,
+}
+
+// Changelog section for a conventional commit type. Maintenance types
+// are left out of the changelog.
+pub fn section_for(kind: &str) -> Option<&'static str> {
+    match kind {
+        "feat" | "add" => Some("Added"),
+        "fix" | "bugfix" | "hotfix" => Some("Fixed"),
+        "deprecate" => Some("Deprecated"),
+        "revert" | "remove" => Some("Removed"),
+        "security" | "sec" => Some("Security"),
+        "chore" | "ci" | "build" | "test" | "tests" | "merge" => None,
+        _ => Some("Changed"),
+    }
+}
+
+// Entry for a commit from its message alone. Messages without a
+// conventional prefix go under Changed.
+pub fn entry(message: &str) -> Option<Entry> {
+    let first = message.lines().next().unwrap_or("").trim();
+    if first.is_empty() {
+        return None;
+    }
+
+    let parsed = match conventional::parse(message) {
+        Some(p) => p,
+        None => return Some(Entry { section: "Changed", text: capitalize(first) }),
+    };
+
+    let section = section_for(&parsed.kind)?;
+    let mut text = capitalize(&parsed.description);
+    if let Some(scope) = &parsed.scope {
+        text = format!("**{}:** {}", scope, text);
+    }
+    if parsed.breaking {
+        text = format!("**Breaking:** {}", text);
+    }
+
+    Some(Entry { section, text })
+}
+
+// Whether a message is too vague to describe the change on its own
+pub fn is_vague(message: &str) -> bool {
+    let first = message.lines().next().unwrap_or("").trim();
+
+    // Messages generated by `ark save` without one
+    if first.starts_with("Auto:") {
+        return true;
+    }
+
+    let description = conventional::parse(message)
+        .map(|m| m.description)
+        .unwrap_or_else(|| first.to_string())
+        .to_lowercase();
+    let words: Vec<&str> = description
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    words.len() < 2 || words.iter().all(|w| GENERIC.contains(w) || w.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// One release section. `title` is a version or "Unreleased".
+pub fn render_release(title: &str, date: Option<&str>, entries: &[Entry]) -> String {
+    let mut out = match date {
+        Some(d) => format!("## [{}] - {}\n", title, d),
+        None => format!("## [{}]\n", title),
+    };
+
+    for section in SECTIONS {
+        let mut lines: Vec<&str> = Vec::new();
+        for e in entries.iter().filter(|e| e.section == *section) {
+            if !lines.contains(&e.text.as_str()) {
+                lines.push(&e.text);
+            }
+        }
+        if lines.is_empty() {
+            continue;
+        }
+
+        out.push_str(&format!("\n### {}\n\n", section));
+        for line in lines {
+            out.push_str(&format!("- {}\n", line));
+        }
+    }
+
+    out
+}
+
+// Put a release section into the changelog, replacing an existing
+// section for the same release. New releases go below [Unreleased] and
+// above every older release.
+pub fn update(existing: Option<&str>, title: &str, release: &str) -> String {
+    let existing = match existing {
+        Some(e) if !e.trim().is_empty() => e,
+        _ => return format!("{}\n{}", HEADER, release),
+    };
+
+    let lines: Vec<&str> = existing.split_inclusive('\n').collect();
+    let heading = format!("## [{}]", title);
+    let is_release = |l: &str| l.starts_with("## ");
+
+    let (start, end) = match lines.iter().position(|l| l.starts_with(&heading)) {
+        Some(start) => {
+            let end = lines[start + 1..].iter()
+                .position(|l| is_release(l) || (l.starts_with('[') && l.contains("]: ")))
+                .map(|p| start + 1 + p)
+                .unwrap_or(lines.len());
+            (start, end)
+        }
+        None => {
+            let at = lines.iter()
+                .position(|l| is_release(l) && !l.starts_with("## [Unreleased]"))
+                .or_else(|| lines.iter().position(|l| l.starts_with('[') && l.contains("]: ")))
+                .unwrap_or(lines.len());
+            (at, at)
+        }
+    };
+
+    let mut out: String = lines[..start].concat();
+    if !out.is_empty() && !out.ends_with("\n\n") {
+        out.push_str(if out.ends_with('\n') { "\n" } else { "\n\n" });
+    }
+    out.push_str(release);
+    if end < lines.len() {
+        out.push('\n');
+        out.push_str(&lines[end..].concat());
+    }
+
+    out
+}
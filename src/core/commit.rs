@@ -2,7 +2,7 @@ use std::fs;
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use chrono::Local;
-use crate::core::branch;
+use crate::core::{branch, merge, object};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Commit {
@@ -11,6 +11,26 @@ pub struct Commit {
     pub timestamp: String,
     pub branch: String,
     pub files_snapshot: HashMap<String, String>,
+    // Secret scanner findings that were explicitly overridden with --allow-secrets
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_secrets: Vec<String>,
+    // The commit this one was saved on top of. Commits saved by older
+    // versions don't record it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    // Head of the branch that was merged in, for merge commits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge_parent: Option<String>,
+    // "Name <email>" from the git identity, when one is configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+}
+
+// Optional metadata recorded alongside a commit
+#[derive(Debug, Default)]
+pub struct CommitOptions {
+    pub allowed_secrets: Vec<String>,
+    pub merge_parent: Option<String>,
 }
 
 pub fn generate_id() -> String {
@@ -22,9 +42,20 @@ pub fn generate_id() -> String {
     format!("{:x}", hasher.finish())
 }
 
-pub fn save_commit(message: &str, snapshot: HashMap<String, String>) -> Result<String, String> {
+pub fn save_commit_with(
+    message: &str,
+    snapshot: HashMap<String, String>,
+    options: CommitOptions,
+) -> Result<String, String> {
+    // Only the merge itself may save while a merge is waiting on conflicts
+    if options.merge_parent.is_none() && merge::in_progress() {
+        return Err("A merge is in progress. Resolve the conflicts and run 'ark merge --continue', or 'ark merge --abort'.".to_string());
+    }
+
     let id = generate_id();
     let current_branch = branch::get_current_branch();
+    let mut branch_data = branch::load_branch(&current_branch)
+        .map_err(|e| format!("Failed to load branch: {}", e))?;
 
     let commit = Commit {
         id: id.clone(),
@@ -32,14 +63,17 @@ pub fn save_commit(message: &str, snapshot: HashMap<String, String>) -> Result<S
         timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
         branch: current_branch.clone(),
         files_snapshot: snapshot.clone(),
+        allowed_secrets: options.allowed_secrets,
+        parent: branch_data.commit_ids.last().or(branch_data.base.as_ref()).cloned(),
+        merge_parent: options.merge_parent,
+        author: crate::git::git_wrapper::identity(),
     };
 
-    // Save commit metadata
-    let commit_json = serde_json::to_string_pretty(&commit)
-        .map_err(|e| format!("Failed to serialize commit: {}", e))?;
+    // Retain file contents so the commit can be inspected later
+    object::store_snapshot(&snapshot)?;
 
-    fs::write(format!(".ark/commits/{}.json", id), commit_json)
-        .map_err(|e| format!("Failed to write commit: {}", e))?;
+    // Save commit metadata
+    write_commit(&commit)?;
 
     // Update latest snapshot for current branch
     let snapshot_json = serde_json::to_string_pretty(&snapshot)
@@ -51,15 +85,20 @@ pub fn save_commit(message: &str, snapshot: HashMap<String, String>) -> Result<S
     ).map_err(|e| format!("Failed to write snapshot: {}", e))?;
 
     // Append commit id to current branch history
-    let mut branch_data = branch::load_branch(&current_branch)
-        .map_err(|e| format!("Failed to load branch: {}", e))?;
-
     branch_data.commit_ids.push(id.clone());
     branch::save_branch(&branch_data)?;
 
     Ok(id)
 }
 
+pub fn write_commit(commit: &Commit) -> Result<(), String> {
+    let commit_json = serde_json::to_string_pretty(commit)
+        .map_err(|e| format!("Failed to serialize commit: {}", e))?;
+
+    fs::write(format!(".ark/commits/{}.json", commit.id), commit_json)
+        .map_err(|e| format!("Failed to write commit: {}", e))
+}
+
 pub fn load_history() -> Vec<String> {
     let current_branch = branch::get_current_branch();
 
@@ -68,6 +107,72 @@ pub fn load_history() -> Vec<String> {
         .unwrap_or_default()
 }
 
+// Every commit reachable from any branch, branch by branch in save order.
+// A branch's base commit may come after the branch; see parents_first.
+pub fn all_commit_ids() -> Vec<String> {
+    let mut ids: Vec<String> = Vec::new();
+
+    for name in branch::list_branches() {
+        if let Ok(b) = branch::load_branch(&name) {
+            for id in b.commit_ids {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+    }
+
+    ids
+}
+
+// The commit this one was saved on top of: the recorded parent, else the
+// previous save on its branch, or for the first save on a branch, the
+// commit the branch was created from. None when none of these is known.
+pub fn parent_id(commit: &Commit) -> Option<String> {
+    if commit.parent.is_some() {
+        return commit.parent.clone();
+    }
+
+    let b = branch::load_branch(&commit.branch).ok()?;
+    match b.commit_ids.iter().position(|id| id == &commit.id)? {
+        0 => b.base,
+        pos => Some(b.commit_ids[pos - 1].clone()),
+    }
+}
+
+// Load commits ordered so each comes after the ones it was saved on top
+// of. Timestamps can't be trusted for this: they have one-second
+// resolution and imported ones are whatever git recorded.
+pub fn parents_first(ids: &[String]) -> Vec<Commit> {
+    let mut pending: HashMap<String, Commit> = ids.iter()
+        .filter_map(|id| load_commit(id).ok().map(|c| (id.clone(), c)))
+        .collect();
+    let mut ordered = Vec::new();
+
+    // Depth-first without recursion, imported histories can be long
+    for id in ids {
+        let mut stack = vec![(id.clone(), false)];
+        while let Some((id, visited)) = stack.pop() {
+            if visited {
+                if let Some(c) = pending.remove(&id) {
+                    ordered.push(c);
+                }
+                continue;
+            }
+            let Some(c) = pending.get(&id) else { continue };
+            let parents: Vec<String> = parent_id(c).into_iter().chain(c.merge_parent.clone()).collect();
+            stack.push((id, true));
+            for p in parents {
+                if pending.contains_key(&p) {
+                    stack.push((p, false));
+                }
+            }
+        }
+    }
+
+    ordered
+}
+
 pub fn load_commit(id: &str) -> Result<Commit, String> {
     let content = fs::read_to_string(format!(".ark/commits/{}.json", id))
         .map_err(|_| format!("Commit '{}' not found.", id))?;
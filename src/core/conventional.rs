@@ -0,0 +1,47 @@
+// Conventional commit messages: `type(scope)!: description`
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub kind: String,
+    pub scope: Option<String>,
+    pub breaking: bool,
+    pub description: String,
+}
+
+pub fn parse(message: &str) -> Option<Message> {
+    let first = message.lines().next()?.trim();
+    let (head, description) = first.split_once(':')?;
+    let description = description.trim();
+
+    let (head, breaking) = match head.strip_suffix('!') {
+        Some(h) => (h, true),
+        None => (head, false),
+    };
+
+    let (kind, scope) = match head.split_once('(') {
+        Some((kind, rest)) => {
+            let scope = rest.strip_suffix(')')?.trim();
+            if scope.is_empty() {
+                return None;
+            }
+            (kind, Some(scope.to_string()))
+        }
+        None => (head, None),
+    };
+
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphabetic()) || description.is_empty() {
+        return None;
+    }
+
+    // A `BREAKING CHANGE:` footer marks a breaking change too
+    let breaking = breaking || message.lines().skip(1).any(|l| {
+        l.starts_with("BREAKING CHANGE:") || l.starts_with("BREAKING-CHANGE:")
+    });
+
+    Some(Message {
+        kind: kind.to_lowercase(),
+        scope,
+        breaking,
+        description: description.to_string(),
+    })
+}
@@ -0,0 +1,123 @@
+use std::fs;
+use std::collections::HashMap;
+use similar::TextDiff;
+use crate::core::{tracker, object, commit};
+use crate::core::tracker::{FileStatus, Status};
+
+const CONTEXT_LINES: usize = 3;
+
+pub struct FileDiff {
+    // Unified diff hunks, each starting with its @@ header
+    pub hunks: Vec<String>,
+    // Why the diff is incomplete, e.g. binary file
+    pub note: Option<String>,
+}
+
+// Content of a file as of the last commit on the current branch
+pub fn previous_content(path: &str) -> Option<String> {
+    let snapshot = tracker::load_snapshot();
+    let hash = snapshot.get(path)?;
+    object::load(hash).and_then(|bytes| String::from_utf8(bytes).ok())
+}
+
+pub fn file_diff(change: &FileStatus) -> FileDiff {
+    let mut diff = FileDiff {
+        hunks: Vec::new(),
+        note: None,
+    };
+
+    let new = match change.status {
+        Status::Deleted | Status::Unchanged => return diff,
+        _ => match fs::read(&change.path) {
+            Ok(bytes) => match String::from_utf8(bytes) {
+                Ok(text) => text,
+                Err(_) => {
+                    diff.note = Some("binary file".to_string());
+                    return diff;
+                }
+            },
+            Err(_) => return diff,
+        },
+    };
+
+    let old = match change.status {
+        Status::Modified => match previous_content(&change.path) {
+            Some(text) => text,
+            None => {
+                diff.note = Some("previous version not retained, showing full content".to_string());
+                String::new()
+            }
+        },
+        _ => String::new(),
+    };
+
+    diff.hunks = hunks(&old, &new);
+    diff
+}
+
+// A file changed between two snapshots
+pub struct SnapshotChange {
+    pub path: String,
+    pub diff: FileDiff,
+}
+
+// Line-level changes between two saved snapshots, sorted by path.
+// Content comes from the object store.
+pub fn snapshot_changes(old: &HashMap<String, String>, new: &HashMap<String, String>) -> Vec<SnapshotChange> {
+    let mut paths: Vec<&String> = old.keys().chain(new.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut changes = Vec::new();
+
+    for path in paths {
+        let (old_hash, new_hash) = (old.get(path), new.get(path));
+        if old_hash == new_hash {
+            continue;
+        }
+
+        let mut diff = FileDiff {
+            hunks: Vec::new(),
+            note: None,
+        };
+
+        let old_text = old_hash.map(|h| load_text(h));
+        let new_text = new_hash.map(|h| load_text(h));
+
+        match (old_text, new_text) {
+            (Some(Err(note)), _) | (_, Some(Err(note))) => diff.note = Some(note),
+            (old_text, new_text) => {
+                let old_text = old_text.and_then(|t| t.ok()).unwrap_or_default();
+                let new_text = new_text.and_then(|t| t.ok()).unwrap_or_default();
+                diff.hunks = hunks(&old_text, &new_text);
+            }
+        }
+
+        changes.push(SnapshotChange { path: path.clone(), diff });
+    }
+
+    changes
+}
+
+// What a commit changed relative to the commit it was saved on top of
+pub fn commit_changes(c: &commit::Commit) -> Vec<SnapshotChange> {
+    let parent = commit::parent_id(c)
+        .and_then(|id| commit::load_commit(&id).ok())
+        .map(|p| p.files_snapshot)
+        .unwrap_or_default();
+
+    snapshot_changes(&parent, &c.files_snapshot)
+}
+
+fn load_text(hash: &str) -> Result<String, String> {
+    let bytes = object::load(hash).ok_or_else(|| "content not retained".to_string())?;
+    String::from_utf8(bytes).map_err(|_| "binary file".to_string())
+}
+
+pub fn hunks(old: &str, new: &str) -> Vec<String> {
+    let text_diff = TextDiff::from_lines(old, new);
+    let mut unified = text_diff.unified_diff();
+    unified.context_radius(CONTEXT_LINES).missing_newline_hint(false);
+
+    unified.iter_hunks().map(|h| h.to_string()).collect()
+}
@@ -0,0 +1,157 @@
+use std::fs;
+use serde::{Deserialize, Serialize};
+use crate::core::conventional;
+
+// Commit message rules, read from .ark/lint.json. Every rule is off
+// unless configured.
+pub const CONFIG_FILE: &str = ".ark/lint.json";
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct LintConfig {
+    // Allowed conventional commit types; when set, every message must be
+    // of the form `type(scope): description`
+    #[serde(default)]
+    pub types: Vec<String>,
+    // Allowed scopes; when set, a scope is required
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    #[serde(default)]
+    pub max_subject_length: Option<usize>,
+    // Require an issue reference such as #123 or ABC-123
+    #[serde(default)]
+    pub require_issue: bool,
+    // Branches where work-in-progress messages are refused
+    #[serde(default)]
+    pub no_wip_on: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Violation {
+    pub rule: &'static str,
+    pub message: String,
+}
+
+impl LintConfig {
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+            && self.scopes.is_empty()
+            && self.max_subject_length.is_none()
+            && !self.require_issue
+            && self.no_wip_on.is_empty()
+    }
+
+    // Rules for messages Ark writes itself, such as the default merge
+    // message, which can't carry a configured type or scope
+    pub fn for_generated(&self) -> LintConfig {
+        LintConfig { types: Vec::new(), scopes: Vec::new(), ..self.clone() }
+    }
+}
+
+pub fn load_config() -> Result<LintConfig, String> {
+    let content = match fs::read_to_string(CONFIG_FILE) {
+        Ok(c) => c,
+        Err(_) => return Ok(LintConfig::default()),
+    };
+
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", CONFIG_FILE, e))
+}
+
+// Check a message saved on `branch` against the rules
+pub fn check(message: &str, branch: &str, config: &LintConfig) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let subject = message.lines().next().unwrap_or("").trim();
+
+    if subject.is_empty() {
+        violations.push(Violation { rule: "subject", message: "The message is empty.".to_string() });
+        return violations;
+    }
+
+    if let Some(max) = config.max_subject_length {
+        let len = subject.chars().count();
+        if len > max {
+            violations.push(Violation {
+                rule: "max_subject_length",
+                message: format!("Subject is {} characters; the limit is {}.", len, max),
+            });
+        }
+    }
+
+    if !config.types.is_empty() || !config.scopes.is_empty() {
+        match conventional::parse(message) {
+            None => violations.push(Violation {
+                rule: "types",
+                message: format!("Use the form 'type(scope): description', e.g. '{}: add login page'.",
+                    config.types.first().map(|t| t.as_str()).unwrap_or("feat")),
+            }),
+            Some(parsed) => {
+                if !config.types.is_empty() && !config.types.contains(&parsed.kind) {
+                    violations.push(Violation {
+                        rule: "types",
+                        message: format!("Type '{}' is not allowed. Use one of: {}.", parsed.kind, config.types.join(", ")),
+                    });
+                }
+
+                if !config.scopes.is_empty() {
+                    match &parsed.scope {
+                        None => violations.push(Violation {
+                            rule: "scopes",
+                            message: format!("A scope is required. Use one of: {}.", config.scopes.join(", ")),
+                        }),
+                        Some(scope) if !config.scopes.contains(scope) => violations.push(Violation {
+                            rule: "scopes",
+                            message: format!("Scope '{}' is not allowed. Use one of: {}.", scope, config.scopes.join(", ")),
+                        }),
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    if config.require_issue && !has_issue_reference(message) {
+        violations.push(Violation {
+            rule: "require_issue",
+            message: "Reference an issue, e.g. #123 or ABC-123.".to_string(),
+        });
+    }
+
+    if config.no_wip_on.iter().any(|b| b == branch) && is_wip(message) {
+        violations.push(Violation {
+            rule: "no_wip_on",
+            message: format!("Work-in-progress saves are not allowed on '{}'.", branch),
+        });
+    }
+
+    violations
+}
+
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '#' || c == '-'))
+        .filter(|w| !w.is_empty())
+}
+
+// #123, or a tracker key such as ABC-123
+pub fn has_issue_reference(message: &str) -> bool {
+    words(message).any(|w| {
+        if let Some(n) = w.strip_prefix('#') {
+            return !n.is_empty() && n.chars().all(|c| c.is_ascii_digit());
+        }
+        match w.rsplit_once('-') {
+            Some((key, n)) => {
+                key.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+                    && key.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+                    && !n.is_empty()
+                    && n.chars().all(|c| c.is_ascii_digit())
+            }
+            None => false,
+        }
+    })
+}
+
+fn is_wip(message: &str) -> bool {
+    let subject = message.lines().next().unwrap_or("").to_lowercase();
+    subject.starts_with("fixup!")
+        || subject.starts_with("squash!")
+        || words(&subject).any(|w| w.trim_matches('-') == "wip")
+}
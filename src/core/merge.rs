@@ -0,0 +1,423 @@
+use std::fs;
+use std::path::Path;
+use std::collections::{HashMap, HashSet, VecDeque};
+use serde::{Deserialize, Serialize};
+use similar::{Algorithm, DiffTag, capture_diff_slices};
+use crate::core::{commit, object, tracker};
+
+const STATE_FILE: &str = ".ark/merge.json";
+
+// A merge that stopped on conflicts, waiting for `ark merge --continue`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MergeState {
+    pub branch: String,
+    pub into: String,
+    pub source_commit: String,
+    #[serde(default)]
+    pub message: String,
+    pub base_commit: Option<String>,
+    // Snapshot of the merge result; conflicted files keep this branch's
+    // version until they are resolved
+    pub snapshot: HashMap<String, String>,
+    pub conflicts: Vec<String>,
+    // Every file the merge wrote, so --abort can put them back
+    pub touched: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Chunk {
+    Clean(Vec<String>),
+    Conflict {
+        base: Vec<String>,
+        ours: Vec<String>,
+        theirs: Vec<String>,
+    },
+}
+
+// A conflicted file split at its conflict markers
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Text(String),
+    Conflict {
+        ours: String,
+        base: String,
+        theirs: String,
+        // The conflict as it appears in the file, markers included
+        raw: String,
+    },
+}
+
+// Result of merging two snapshots
+#[derive(Debug, Default)]
+pub struct Outcome {
+    pub snapshot: HashMap<String, String>,
+    // Files to write into the working tree; None deletes the file
+    pub writes: Vec<(String, Option<Vec<u8>>)>,
+    pub conflicts: Vec<String>,
+    // Binary files changed on both sides; this branch's version is kept
+    pub kept_ours: Vec<String>,
+}
+
+// Message for a merge saved without -m
+pub fn default_message(branch: &str, into: &str) -> String {
+    format!("merge: {} into {}", branch, into)
+}
+
+pub fn is_default_message(message: &str) -> bool {
+    message.strip_prefix("merge: ").is_some_and(|rest| rest.contains(" into ") && !rest.contains('\n'))
+}
+
+pub fn load_state() -> Option<MergeState> {
+    let content = fs::read_to_string(STATE_FILE).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn in_progress() -> bool {
+    Path::new(STATE_FILE).exists()
+}
+
+pub fn save_state(state: &MergeState) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(state)
+        .map_err(|e| format!("Failed to serialize merge state: {}", e))?;
+
+    fs::write(STATE_FILE, json)
+        .map_err(|e| format!("Failed to write merge state: {}", e))
+}
+
+pub fn clear_state() -> Result<(), String> {
+    if !in_progress() {
+        return Ok(());
+    }
+
+    fs::remove_file(STATE_FILE)
+        .map_err(|e| format!("Failed to remove merge state: {}", e))
+}
+
+// Every commit reachable from `id`, nearest first
+pub fn ancestors(id: &str) -> Vec<String> {
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut order = Vec::new();
+    let mut queue = VecDeque::from([id.to_string()]);
+
+    while let Some(id) = queue.pop_front() {
+        if !seen.insert(id.clone()) {
+            continue;
+        }
+
+        let c = match commit::load_commit(&id) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+        order.push(id);
+
+        if let Some(parent) = commit::parent_id(&c) {
+            queue.push_back(parent);
+        }
+        if let Some(parent) = c.merge_parent {
+            queue.push_back(parent);
+        }
+    }
+
+    order
+}
+
+// Nearest commit reachable from both heads
+pub fn common_ancestor(ours: &str, theirs: &str) -> Option<String> {
+    let ours: HashSet<String> = ancestors(ours).into_iter().collect();
+
+    ancestors(theirs).into_iter().find(|id| ours.contains(id))
+}
+
+fn split_lines(text: &str) -> Vec<String> {
+    text.split_inclusive('\n').map(|l| l.to_string()).collect()
+}
+
+// Changed base ranges and their replacement lines
+fn changes(base: &[String], other: &[String]) -> Vec<(usize, usize, Vec<String>)> {
+    capture_diff_slices(Algorithm::Myers, base, other)
+        .iter()
+        .map(|op| op.as_tag_tuple())
+        .filter(|(tag, _, _)| *tag != DiffTag::Equal)
+        .map(|(_, old, new)| (old.start, old.end, other[new].to_vec()))
+        .collect()
+}
+
+// Base lines start..end with the given changes applied
+fn apply_changes(base: &[String], start: usize, end: usize, changes: &[(usize, usize, Vec<String>)]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut pos = start;
+
+    for (s, e, lines) in changes {
+        out.extend_from_slice(&base[pos..*s]);
+        out.extend(lines.iter().cloned());
+        pos = *e;
+    }
+    out.extend_from_slice(&base[pos..end]);
+
+    out
+}
+
+// Three-way line merge. Changes from both sides that touch or overlap
+// the same base lines are a conflict unless they are identical.
+pub fn merge_text(base: &str, ours: &str, theirs: &str) -> Vec<Chunk> {
+    let base = split_lines(base);
+    let ours_changes = changes(&base, &split_lines(ours));
+    let theirs_changes = changes(&base, &split_lines(theirs));
+
+    let mut chunks = Vec::new();
+    let mut clean: Vec<String> = Vec::new();
+    let (mut i, mut j, mut pos) = (0, 0, 0);
+
+    while i < ours_changes.len() || j < theirs_changes.len() {
+        let next_ours = ours_changes.get(i).map(|c| c.0).unwrap_or(usize::MAX);
+        let next_theirs = theirs_changes.get(j).map(|c| c.0).unwrap_or(usize::MAX);
+        let start = next_ours.min(next_theirs);
+        let mut end = start;
+        let (oi, tj) = (i, j);
+
+        // Grow the region until no change from either side touches it
+        loop {
+            if let Some(c) = ours_changes.get(i) && c.0 <= end {
+                end = end.max(c.1);
+                i += 1;
+                continue;
+            }
+            if let Some(c) = theirs_changes.get(j) && c.0 <= end {
+                end = end.max(c.1);
+                j += 1;
+                continue;
+            }
+            break;
+        }
+
+        clean.extend_from_slice(&base[pos..start]);
+        pos = end;
+
+        let ours_region = apply_changes(&base, start, end, &ours_changes[oi..i]);
+        let theirs_region = apply_changes(&base, start, end, &theirs_changes[tj..j]);
+
+        if tj == j || ours_region == theirs_region {
+            clean.extend(ours_region);
+        } else if oi == i {
+            clean.extend(theirs_region);
+        } else {
+            if !clean.is_empty() {
+                chunks.push(Chunk::Clean(std::mem::take(&mut clean)));
+            }
+            chunks.push(Chunk::Conflict {
+                base: base[start..end].to_vec(),
+                ours: ours_region,
+                theirs: theirs_region,
+            });
+        }
+    }
+
+    clean.extend_from_slice(&base[pos..]);
+    if !clean.is_empty() {
+        chunks.push(Chunk::Clean(clean));
+    }
+
+    chunks
+}
+
+pub fn has_conflicts(chunks: &[Chunk]) -> bool {
+    chunks.iter().any(|c| matches!(c, Chunk::Conflict { .. }))
+}
+
+fn push_section(out: &mut String, lines: &[String]) {
+    for line in lines {
+        out.push_str(line);
+    }
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+// Write chunks out, with conflicts between diff3-style markers
+pub fn render(chunks: &[Chunk], ours_label: &str, theirs_label: &str) -> String {
+    let mut out = String::new();
+
+    for chunk in chunks {
+        match chunk {
+            Chunk::Clean(lines) => {
+                for line in lines {
+                    out.push_str(line);
+                }
+            }
+            Chunk::Conflict { base, ours, theirs } => {
+                if !out.is_empty() && !out.ends_with('\n') {
+                    out.push('\n');
+                }
+                out.push_str(&format!("<<<<<<< {}\n", ours_label));
+                push_section(&mut out, ours);
+                out.push_str("||||||| base\n");
+                push_section(&mut out, base);
+                out.push_str("=======\n");
+                push_section(&mut out, theirs);
+                out.push_str(&format!(">>>>>>> {}\n", theirs_label));
+            }
+        }
+    }
+
+    out
+}
+
+fn is_marker(line: &str, marker: &str) -> bool {
+    line.strip_prefix(marker)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(' ') || rest.starts_with('\n') || rest.starts_with('\r'))
+}
+
+pub fn has_markers(text: &str) -> bool {
+    let mut open = false;
+
+    for line in text.lines() {
+        if is_marker(line, "<<<<<<<") {
+            open = true;
+        } else if open && is_marker(line, ">>>>>>>") {
+            return true;
+        }
+    }
+
+    false
+}
+
+// Split a file with conflict markers into plain text and conflicts.
+// Files without a base section (two-way markers) get an empty base.
+pub fn parse_conflicts(text: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut plain = String::new();
+    let mut lines = text.split_inclusive('\n').peekable();
+
+    while let Some(line) = lines.next() {
+        if !is_marker(line, "<<<<<<<") {
+            plain.push_str(line);
+            continue;
+        }
+
+        let (mut ours, mut base, mut theirs) = (String::new(), String::new(), String::new());
+        let mut section = 0;
+        let mut raw = line.to_string();
+        let mut closed = false;
+
+        for line in lines.by_ref() {
+            raw.push_str(line);
+            if is_marker(line, "|||||||") && section == 0 {
+                section = 1;
+            } else if is_marker(line, "=======") && section < 2 {
+                section = 2;
+            } else if is_marker(line, ">>>>>>>") {
+                closed = true;
+                break;
+            } else {
+                match section {
+                    0 => ours.push_str(line),
+                    1 => base.push_str(line),
+                    _ => theirs.push_str(line),
+                }
+            }
+        }
+
+        // An unterminated marker is left as ordinary text
+        if !closed || section < 2 {
+            plain.push_str(&raw);
+            continue;
+        }
+
+        if !plain.is_empty() {
+            segments.push(Segment::Text(std::mem::take(&mut plain)));
+        }
+        segments.push(Segment::Conflict { ours, base, theirs, raw });
+    }
+
+    if !plain.is_empty() {
+        segments.push(Segment::Text(plain));
+    }
+
+    segments
+}
+
+fn load_text(hash: Option<&String>) -> Option<String> {
+    match hash {
+        None => Some(String::new()),
+        Some(h) => object::load(h).and_then(|c| String::from_utf8(c).ok()),
+    }
+}
+
+// Merge `theirs` into `ours` given the snapshot of their common ancestor
+pub fn merge_snapshots(
+    base: &HashMap<String, String>,
+    ours: &HashMap<String, String>,
+    theirs: &HashMap<String, String>,
+    ours_label: &str,
+    theirs_label: &str,
+) -> Result<Outcome, String> {
+    let mut outcome = Outcome {
+        snapshot: ours.clone(),
+        ..Outcome::default()
+    };
+
+    let mut paths: Vec<&String> = ours.keys().chain(theirs.keys()).chain(base.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    for path in paths {
+        let (b, o, t) = (base.get(path), ours.get(path), theirs.get(path));
+
+        // Unchanged on their side, or both made the same change
+        if o == t || b == t {
+            continue;
+        }
+
+        // Only they changed it
+        if b == o {
+            match t {
+                Some(hash) => {
+                    let content = object::load(hash)
+                        .ok_or_else(|| format!("Content of '{}' on the other branch is not available.", path))?;
+                    outcome.snapshot.insert(path.clone(), hash.clone());
+                    outcome.writes.push((path.clone(), Some(content)));
+                }
+                None => {
+                    outcome.snapshot.remove(path);
+                    outcome.writes.push((path.clone(), None));
+                }
+            }
+            continue;
+        }
+
+        // Both changed it differently
+        let ours_text = load_text(o).or_else(|| {
+            fs::read(path).ok()
+                .filter(|c| Some(&tracker::hash_content(c)) == o)
+                .and_then(|c| String::from_utf8(c).ok())
+        });
+        let (ours_text, theirs_text) = match (ours_text, load_text(t)) {
+            (Some(o), Some(t)) => (o, t),
+            _ => {
+                outcome.kept_ours.push(path.clone());
+                continue;
+            }
+        };
+        // Base content from saves made before contents were retained is
+        // unknown, so the whole file is treated as changed on both sides
+        let base_text = load_text(b).unwrap_or_default();
+
+        let chunks = merge_text(&base_text, &ours_text, &theirs_text);
+        let merged = render(&chunks, ours_label, theirs_label);
+
+        if has_conflicts(&chunks) {
+            outcome.conflicts.push(path.clone());
+        } else if merged.is_empty() && (o.is_none() || t.is_none()) {
+            // Deleted on one side, emptied on the other
+            outcome.snapshot.remove(path);
+            outcome.writes.push((path.clone(), None));
+            continue;
+        } else {
+            let hash = object::store(merged.as_bytes())?;
+            outcome.snapshot.insert(path.clone(), hash);
+        }
+        outcome.writes.push((path.clone(), Some(merged.into_bytes())));
+    }
+
+    Ok(outcome)
+}
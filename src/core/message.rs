@@ -0,0 +1,394 @@
+use std::path::Path;
+use crate::core::{diff, lint};
+use crate::core::tracker::{FileStatus, Status};
+
+// Offline commit messages: a conventional commit message guessed from
+// which kinds of files changed and which definitions were added or
+// removed, for when no AI provider is available.
+
+const MAX_SUBJECT: usize = 72;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Source,
+    Test,
+    Docs,
+    Config,
+    Ci,
+}
+
+// Definitions added, removed or changed in one file
+#[derive(Debug, Default)]
+struct Definitions {
+    added: Vec<String>,
+    removed: Vec<String>,
+    changed: Vec<String>,
+}
+
+pub fn generate(changes: &[FileStatus]) -> String {
+    let config = lint::load_config().unwrap_or_default();
+    let (kind, scope, description) = summarize(changes);
+
+    // Stay within the project's message rules where a guess allows it
+    let kind = if config.types.is_empty() || config.types.iter().any(|t| t == kind) {
+        kind.to_string()
+    } else if config.types.iter().any(|t| t == "chore") {
+        "chore".to_string()
+    } else {
+        config.types[0].clone()
+    };
+    let scope = allowed_scope(scope, changes, &config);
+    let max = config.max_subject_length.unwrap_or(MAX_SUBJECT);
+
+    let mut prefix = match &scope {
+        Some(s) => format!("{}({}): ", kind, s),
+        None => format!("{}: ", kind),
+    };
+    // An optional scope goes before the description does
+    if config.scopes.is_empty() && prefix.chars().count() + 10 > max {
+        prefix = format!("{}: ", kind);
+    }
+
+    fit(&prefix, &description, "", max)
+}
+
+// A message based on `message` that the rules can accept, with a
+// placeholder for what only the user knows, e.g. the issue it fixes
+pub fn suggest(message: &str) -> String {
+    let config = lint::load_config().unwrap_or_default();
+    let subject = message.lines().next().unwrap_or("").trim();
+
+    let suffix = if config.require_issue && !lint::has_issue_reference(subject) { " (#<issue>)" } else { "" };
+    let (prefix, description) = match subject.split_once(": ") {
+        Some((p, d)) => (format!("{}: ", p), d),
+        None => (String::new(), subject),
+    };
+
+    fit(&prefix, description, suffix, config.max_subject_length.unwrap_or(MAX_SUBJECT))
+}
+
+// The guessed scope when the rules allow it. Required scopes fall back to
+// one named in a changed path, else the first configured one.
+fn allowed_scope(scope: Option<String>, changes: &[FileStatus], config: &lint::LintConfig) -> Option<String> {
+    if config.scopes.is_empty() || scope.as_ref().is_some_and(|s| config.scopes.contains(s)) {
+        return scope;
+    }
+
+    config.scopes.iter()
+        .find(|s| changes.iter().any(|c| c.path.split('/').any(|part| stem(part) == **s)))
+        .or(config.scopes.first())
+        .cloned()
+}
+
+// prefix, description and suffix within `max` characters, shortening the
+// description first
+fn fit(prefix: &str, description: &str, suffix: &str, max: usize) -> String {
+    let room = max.saturating_sub(prefix.chars().count() + suffix.chars().count());
+    let mut short = shorten(description, room);
+    // A first word longer than the room is cut instead
+    if short.is_empty() {
+        short = description.chars().take(room).collect();
+    }
+    format!("{}{}{}", prefix, short, suffix)
+}
+
+fn summarize(changes: &[FileStatus]) -> (&'static str, Option<String>, String) {
+    if changes.is_empty() {
+        return ("chore", None, "update files".to_string());
+    }
+
+    let kinds: Vec<Kind> = changes.iter().map(|c| classify(&c.path)).collect();
+    let scope = common_scope(changes);
+
+    let only = |k: Kind| kinds.iter().all(|x| *x == k);
+    if only(Kind::Docs) {
+        return ("docs", None, format!("update {}", file_list(changes)));
+    }
+    if only(Kind::Ci) {
+        return ("ci", None, format!("update {}", file_list(changes)));
+    }
+    if kinds.iter().all(|k| matches!(k, Kind::Config | Kind::Ci)) {
+        return ("chore", None, format!("update {}", file_list(changes)));
+    }
+
+    let sources: Vec<&FileStatus> = changes.iter()
+        .zip(&kinds)
+        .filter(|(_, k)| **k == Kind::Source)
+        .map(|(c, _)| c)
+        .collect();
+
+    if sources.is_empty() {
+        let tests: Vec<&FileStatus> = changes.iter().zip(&kinds)
+            .filter(|(_, k)| **k == Kind::Test)
+            .map(|(c, _)| c)
+            .collect();
+        let verb = if tests.iter().all(|c| c.status == Status::New) { "add" } else { "update" };
+        let subjects: Vec<String> = tests.iter().map(|c| test_subject(&c.path)).collect();
+        return ("test", scope, format!("{} tests for {}", verb, join_names(&subjects)));
+    }
+
+    let mut defs = Definitions::default();
+    for change in &sources {
+        let found = definitions(change);
+        defs.added.extend(found.added);
+        defs.removed.extend(found.removed);
+        defs.changed.extend(found.changed);
+    }
+
+    let new_files: Vec<&&FileStatus> = sources.iter().filter(|c| c.status == Status::New).collect();
+    let deleted: Vec<&&FileStatus> = sources.iter().filter(|c| c.status == Status::Deleted).collect();
+
+    if !defs.added.is_empty() {
+        return ("feat", scope, format!("add {}", join_names(&defs.added)));
+    }
+    if !new_files.is_empty() {
+        let names: Vec<String> = new_files.iter().map(|c| stem(&c.path)).collect();
+        return ("feat", scope, format!("add {}", join_names(&names)));
+    }
+    if !defs.removed.is_empty() {
+        return ("refactor", scope, format!("remove {}", join_names(&defs.removed)));
+    }
+    if !deleted.is_empty() {
+        let names: Vec<String> = deleted.iter().map(|c| stem(&c.path)).collect();
+        return ("refactor", scope, format!("remove {}", join_names(&names)));
+    }
+    if !defs.changed.is_empty() {
+        return ("refactor", scope, format!("change {}", join_names(&defs.changed)));
+    }
+
+    // Only function bodies changed
+    let names: Vec<String> = sources.iter().map(|c| stem(&c.path)).collect();
+    ("fix", scope, format!("update {}", join_names(&names)))
+}
+
+fn classify(path: &str) -> Kind {
+    let path = path.trim_start_matches("./").to_lowercase();
+    let name = Path::new(&path).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = Path::new(&path).extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+    let dirs: Vec<&str> = path.split('/').collect();
+    let in_dir = |d: &str| dirs[..dirs.len() - 1].contains(&d);
+
+    if path.starts_with(".github/workflows/") || name == ".gitlab-ci.yml" || name == ".travis.yml" {
+        return Kind::Ci;
+    }
+    if in_dir("tests") || in_dir("test") || in_dir("__tests__")
+        || name.starts_with("test_")
+        || name.contains("_test.")
+        || name.contains(".test.")
+        || name.contains(".spec.")
+    {
+        return Kind::Test;
+    }
+    if in_dir("docs") || in_dir("doc")
+        || ["md", "rst", "adoc"].contains(&ext.as_str())
+        || name.starts_with("license")
+        || name == "readme" || name == "changelog"
+    {
+        return Kind::Docs;
+    }
+    if ["toml", "yaml", "yml", "json", "ini", "cfg", "lock", "conf"].contains(&ext.as_str())
+        || name.starts_with('.')
+        || ["dockerfile", "makefile", "requirements.txt", "setup.py"].contains(&name.as_str())
+    {
+        return Kind::Config;
+    }
+
+    Kind::Source
+}
+
+// The module every changed file belongs to, e.g. `core` for
+// src/core/branch.rs, or a file's stem for files directly under src
+fn common_scope(changes: &[FileStatus]) -> Option<String> {
+    let mut scope: Option<String> = None;
+
+    for change in changes {
+        let path = change.path.trim_start_matches("./");
+        let mut parts: Vec<&str> = path.split('/').collect();
+        if parts.len() > 1 && ["src", "lib", "app", "pkg"].contains(&parts[0]) {
+            parts.remove(0);
+        }
+        // Test and doc folders say nothing about the module
+        if parts.len() > 1 && ["tests", "test", "docs", "doc"].contains(&parts[0]) {
+            continue;
+        }
+
+        let this = if parts.len() > 1 {
+            parts[0].to_string()
+        } else if path.contains('/') {
+            stem(path)
+        } else {
+            return None;
+        };
+
+        match &scope {
+            None => scope = Some(this),
+            Some(s) if *s == this => {}
+            Some(_) => return None,
+        }
+    }
+
+    scope.filter(|s| !["main", "lib", "mod", "index", "__init__"].contains(&s.as_str()))
+}
+
+fn stem(path: &str) -> String {
+    Path::new(path).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+fn test_subject(path: &str) -> String {
+    let s = stem(path);
+    let s = s.strip_prefix("test_").unwrap_or(&s);
+    let s = s.strip_suffix("_test").unwrap_or(s);
+    let s = s.split('.').next().unwrap_or(s);
+    humanize(s)
+}
+
+fn file_list(changes: &[FileStatus]) -> String {
+    let names: Vec<String> = changes.iter()
+        .map(|c| Path::new(&c.path).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default())
+        .collect();
+    join_list(&names)
+}
+
+// Identifiers as words: rename_branch and renameBranch become "rename branch"
+fn humanize(name: &str) -> String {
+    let mut words = String::new();
+    let mut prev_lower = false;
+
+    for c in name.chars() {
+        if c == '_' || c == '-' {
+            words.push(' ');
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower {
+            words.push(' ');
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        words.extend(c.to_lowercase());
+    }
+
+    words.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn join_names(names: &[String]) -> String {
+    let mut unique: Vec<String> = Vec::new();
+    for name in names.iter().map(|n| humanize(n)) {
+        if !name.is_empty() && !unique.contains(&name) {
+            unique.push(name);
+        }
+    }
+    join_list(&unique)
+}
+
+fn join_list(items: &[String]) -> String {
+    match items.len() {
+        0 => "files".to_string(),
+        1 => items[0].clone(),
+        2 => format!("{} and {}", items[0], items[1]),
+        n => format!("{}, {} and {} more", items[0], items[1], n - 2),
+    }
+}
+
+fn shorten(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+
+    // Drop whole words and keep the list readable
+    let mut out = String::new();
+    for word in text.split_whitespace() {
+        if out.chars().count() + word.chars().count() + 1 > max {
+            break;
+        }
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+    out.trim_end_matches([',', ' ']).trim_end_matches(" and").to_string()
+}
+
+// Definitions in the added and removed lines of a file's diff. A name
+// both added and removed had its signature changed, unless only the
+// braces around the body moved.
+fn definitions(change: &FileStatus) -> Definitions {
+    let ext = Path::new(&change.path).extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let file_diff = diff::file_diff(change);
+
+    let mut added: Vec<(String, String)> = Vec::new();
+    let mut removed: Vec<(String, String)> = Vec::new();
+
+    for line in file_diff.hunks.iter().flat_map(|h| h.lines()) {
+        if line.starts_with("+++") || line.starts_with("---") {
+            continue;
+        }
+        let (list, code) = match line.chars().next() {
+            Some('+') => (&mut added, &line[1..]),
+            Some('-') => (&mut removed, &line[1..]),
+            _ => continue,
+        };
+        if let Some(name) = definition(code, &ext)
+            && !list.iter().any(|(n, _)| *n == name)
+        {
+            let signature = code.trim().trim_end_matches(['{', '}', ':', ';', ' ']).to_string();
+            list.push((name, signature));
+        }
+    }
+
+    let mut defs = Definitions::default();
+    for (name, signature) in &added {
+        match removed.iter().find(|(n, _)| n == name) {
+            Some((_, old)) if old == signature => {}
+            Some(_) => defs.changed.push(name.clone()),
+            None => defs.added.push(name.clone()),
+        }
+    }
+    for (name, _) in &removed {
+        if !added.iter().any(|(n, _)| n == name) {
+            defs.removed.push(name.clone());
+        }
+    }
+
+    defs
+}
+
+// Name of the function, type or class defined on this line, if any
+fn definition(line: &str, ext: &str) -> Option<String> {
+    let keywords: &[&str] = match ext {
+        "rs" => &["fn", "struct", "enum", "trait"],
+        "py" => &["def", "class"],
+        "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" => &["function", "class", "const", "let"],
+        _ => return None,
+    };
+    let modifiers = ["pub(crate)", "pub(super)", "pub", "async", "const", "unsafe", "export", "default", "extern"];
+
+    let mut rest = line.trim();
+    // Only top-level and impl-level definitions; nested closures and
+    // local variables are noise
+    if line.len() - line.trim_start().len() > 4 {
+        return None;
+    }
+
+    loop {
+        let word = rest.split_whitespace().next()?;
+        if keywords.contains(&word) {
+            let after = rest[word.len()..].trim_start();
+            let name: String = after.chars().take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '$').collect();
+            if name.is_empty() {
+                return None;
+            }
+            // JS variables only count when they hold a function
+            if word == "const" || word == "let" {
+                let value = after[name.len()..].trim_start().strip_prefix('=')?.trim_start();
+                if !(value.starts_with('(') || value.starts_with("async") || value.starts_with("function")) {
+                    return None;
+                }
+            }
+            return Some(name);
+        }
+        if !modifiers.contains(&word) {
+            return None;
+        }
+        rest = rest[word.len()..].trim_start();
+    }
+}
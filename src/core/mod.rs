@@ -2,3 +2,13 @@ pub mod repo;
 pub mod tracker;
 pub mod commit;
 pub mod branch;
+pub mod object;
+pub mod diff;
+pub mod patch;
+pub mod backup;
+pub mod merge;
+pub mod conventional;
+pub mod changelog;
+pub mod lint;
+pub mod message;
+pub mod ark_remote;
@@ -0,0 +1,61 @@
+use std::fs;
+use std::path::Path;
+use std::collections::HashMap;
+use crate::core::tracker;
+
+// File contents are stored under .ark/objects/<hash>, keyed by the same
+// hash that snapshots record, so any snapshot can be resolved to content.
+const OBJECTS_DIR: &str = ".ark/objects";
+
+pub fn object_path(hash: &str) -> String {
+    format!("{}/{}", OBJECTS_DIR, hash)
+}
+
+pub fn exists(hash: &str) -> bool {
+    Path::new(&object_path(hash)).exists()
+}
+
+pub fn store(content: &[u8]) -> Result<String, String> {
+    let hash = tracker::hash_content(content);
+
+    if exists(&hash) {
+        return Ok(hash);
+    }
+
+    fs::create_dir_all(OBJECTS_DIR)
+        .map_err(|e| format!("Failed to create objects directory: {}", e))?;
+
+    fs::write(object_path(&hash), content)
+        .map_err(|e| format!("Failed to write object: {}", e))?;
+
+    Ok(hash)
+}
+
+pub fn load(hash: &str) -> Option<Vec<u8>> {
+    fs::read(object_path(hash)).ok()
+}
+
+// Store the working tree content of every file in the snapshot that is
+// not yet in the object store
+pub fn store_snapshot(snapshot: &HashMap<String, String>) -> Result<(), String> {
+    for (path, hash) in snapshot {
+        if exists(hash) {
+            continue;
+        }
+
+        let content = match fs::read(path) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+
+        // File changed since the snapshot was built; the stored hash
+        // would point at the wrong content
+        if &tracker::hash_content(&content) != hash {
+            continue;
+        }
+
+        store(&content)?;
+    }
+
+    Ok(())
+}
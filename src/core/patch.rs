@@ -0,0 +1,205 @@
+use std::path::{Component, Path};
+
+// A hunk of a unified diff. Lines keep their ' ', '-' or '+' prefix.
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FilePatch {
+    pub path: String,
+    pub is_new: bool,
+    pub hunks: Vec<Hunk>,
+}
+
+impl Hunk {
+    // Lines the hunk expects to find in the current file
+    pub fn old_lines(&self) -> Vec<&str> {
+        self.lines.iter()
+            .filter(|l| l.starts_with(' ') || l.starts_with('-'))
+            .map(|l| &l[1..])
+            .collect()
+    }
+
+    // Lines the hunk leaves behind
+    pub fn new_lines(&self) -> Vec<&str> {
+        self.lines.iter()
+            .filter(|l| l.starts_with(' ') || l.starts_with('+'))
+            .map(|l| &l[1..])
+            .collect()
+    }
+}
+
+// Parse unified diffs for one or more files. Tolerates the prose and code
+// fences models tend to wrap around them.
+pub fn parse(text: &str) -> Vec<FilePatch> {
+    let mut patches: Vec<FilePatch> = Vec::new();
+    let mut old_path: Option<String> = None;
+    let lines: Vec<&str> = text.lines().collect();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+
+        if let Some(rest) = line.strip_prefix("--- ") {
+            old_path = Some(strip_path(rest));
+        } else if let Some(rest) = line.strip_prefix("+++ ") {
+            let new_path = strip_path(rest);
+            let is_new = old_path.as_deref() == Some("/dev/null");
+            patches.push(FilePatch { path: new_path, is_new, hunks: Vec::new() });
+            old_path = None;
+        } else if line.starts_with("@@") && !patches.is_empty() {
+            let (old_start, counts) = parse_header(line);
+            let mut hunk = Hunk { old_start, lines: Vec::new() };
+            // The header says how many old and new lines the hunk has, so a
+            // removed line that starts with "-- " is not a file header
+            let (mut old_left, mut new_left) = counts.unwrap_or((usize::MAX, usize::MAX));
+            i += 1;
+
+            while i < lines.len() && (old_left > 0 || new_left > 0) {
+                let l = lines[i];
+                if l.starts_with("@@") || l.starts_with("```") || (counts.is_none() && l.starts_with("--- ")) {
+                    break;
+                }
+                match l.chars().next() {
+                    // Empty lines are context lines that lost their leading space
+                    Some(' ') | None if old_left > 0 && new_left > 0 => {
+                        old_left -= 1;
+                        new_left -= 1;
+                    }
+                    Some('-') if old_left > 0 => old_left -= 1,
+                    Some('+') if new_left > 0 => new_left -= 1,
+                    Some('\\') => {
+                        i += 1;
+                        continue;
+                    }
+                    _ => break,
+                }
+                hunk.lines.push(if l.is_empty() { " ".to_string() } else { l.to_string() });
+                i += 1;
+            }
+
+            // Without counts, trailing blank context may belong after the hunk
+            while counts.is_none() && hunk.lines.last().is_some_and(|l| l == " ") {
+                hunk.lines.pop();
+            }
+
+            if hunk.lines.iter().any(|l| l.starts_with('+') || l.starts_with('-')) {
+                patches.last_mut().unwrap().hunks.push(hunk);
+            }
+            continue;
+        }
+
+        i += 1;
+    }
+
+    patches.retain(|p| !p.hunks.is_empty() && p.path != "/dev/null");
+    patches
+}
+
+// Patches must stay inside the project and out of Ark's own data
+pub fn is_safe_path(path: &str) -> bool {
+    let p = Path::new(path);
+    !path.is_empty()
+        && !p.is_absolute()
+        && p.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        && !p.components().any(|c| c.as_os_str() == ".ark" || c.as_os_str() == ".git")
+}
+
+// Find where the hunk's old lines sit in the file, starting at the line
+// number the hunk claims and searching outwards. Trailing whitespace is
+// ignored. Returns the 0-based line index.
+pub fn locate(file_lines: &[&str], hunk: &Hunk) -> Option<usize> {
+    let old = hunk.old_lines();
+
+    if old.is_empty() {
+        return Some(hunk.old_start.min(file_lines.len()));
+    }
+    if old.len() > file_lines.len() {
+        return None;
+    }
+
+    let matches_at = |start: usize| {
+        old.iter().enumerate().all(|(k, l)| file_lines[start + k].trim_end() == l.trim_end())
+    };
+
+    let last = file_lines.len() - old.len();
+    let hint = hunk.old_start.saturating_sub(1).min(last);
+
+    for distance in 0..=last {
+        if hint >= distance && matches_at(hint - distance) {
+            return Some(hint - distance);
+        }
+        if distance > 0 && hint + distance <= last && matches_at(hint + distance) {
+            return Some(hint + distance);
+        }
+    }
+
+    None
+}
+
+// Apply located hunks, given as (hunk, 0-based start line). Overlapping
+// hunks are an error.
+pub fn apply(content: &str, hunks: &[(Hunk, usize)]) -> Result<String, String> {
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    let mut ordered: Vec<&(Hunk, usize)> = hunks.iter().collect();
+    ordered.sort_by_key(|(_, start)| *start);
+
+    for pair in ordered.windows(2) {
+        let (first, start) = pair[0];
+        if start + first.old_lines().len() > pair[1].1 {
+            return Err("Overlapping hunks.".to_string());
+        }
+    }
+
+    // Bottom-up so earlier line numbers stay valid
+    for (hunk, start) in ordered.into_iter().rev() {
+        let old_len = hunk.old_lines().len();
+        let new: Vec<String> = hunk.new_lines().iter().map(|l| l.to_string()).collect();
+        lines.splice(*start..*start + old_len, new);
+    }
+
+    // Keep the file's line endings; lines() has dropped any \r
+    let ending = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut result = lines.join(ending);
+    if content.ends_with('\n') || content.is_empty() {
+        result.push_str(ending);
+    }
+    Ok(result)
+}
+
+fn strip_path(raw: &str) -> String {
+    // Drop a trailing timestamp ("path\t2024-01-01 ...")
+    let path = raw.split('\t').next().unwrap_or(raw).trim();
+    if path == "/dev/null" {
+        return path.to_string();
+    }
+    path.strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path)
+        .trim_start_matches("./")
+        .to_string()
+}
+
+// Old start line and, when given, the old and new line counts of a
+// `@@ -a,b +c,d @@` header. A missing count means one line.
+fn parse_header(header: &str) -> (usize, Option<(usize, usize)>) {
+    let mut fields = header.split_whitespace().skip(1);
+    let old = fields.next().and_then(|f| f.strip_prefix('-')).and_then(parse_range);
+    let new = fields.next().and_then(|f| f.strip_prefix('+')).and_then(parse_range);
+
+    let old_start = old.map(|(start, _)| start).unwrap_or(1);
+    match (old, new) {
+        (Some((_, old_count)), Some((_, new_count))) => (old_start, Some((old_count, new_count))),
+        _ => (old_start, None),
+    }
+}
+
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
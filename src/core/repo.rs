@@ -9,6 +9,9 @@ pub struct ArkConfig {
     pub version: String,
     pub created_at: String,
     pub project_name: String,
+    // Remote used when neither --remote nor the branch's upstream names one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_remote: Option<String>,
 }
 
 pub fn init(project_name: &str) -> Result<(), String> {
@@ -31,6 +34,9 @@ pub fn init(project_name: &str) -> Result<(), String> {
     fs::create_dir(".ark/branches")
         .map_err(|e| format!("Failed to create branches directory: {}", e))?;
 
+    fs::create_dir(".ark/objects")
+        .map_err(|e| format!("Failed to create objects directory: {}", e))?;
+
     // Create default main branch
     branch::create_branch("main")?;
 
@@ -41,15 +47,10 @@ pub fn init(project_name: &str) -> Result<(), String> {
         version: "0.1.0".to_string(),
         created_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
         project_name: project_name.to_string(),
+        default_remote: None,
     };
 
-    let config_json = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize config: {}", e))?;
-
-    fs::write(".ark/config.json", config_json)
-        .map_err(|e| format!("Failed to write config: {}", e))?;
-
-    Ok(())
+    save_config(&config)
 }
 
 pub fn is_initialized() -> bool {
@@ -63,3 +64,11 @@ pub fn load_config() -> Result<ArkConfig, String> {
     serde_json::from_str(&content)
         .map_err(|e| format!("Failed to parse config: {}", e))
 }
+
+pub fn save_config(config: &ArkConfig) -> Result<(), String> {
+    let config_json = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+
+    fs::write(".ark/config.json", config_json)
+        .map_err(|e| format!("Failed to write config: {}", e))
+}
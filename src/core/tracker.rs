@@ -48,7 +48,7 @@ pub fn scan_changes() -> Vec<FileStatus> {
                 status: Status::New,
             }),
             Some(old_hash) => {
-                if old_hash != hash {
+                if old_hash != hash && !legacy_match(old_hash, path) {
                     results.push(FileStatus {
                         path: path.clone(),
                         status: Status::Modified,
@@ -94,17 +94,54 @@ fn scan_dir(dir: &Path, files: &mut HashMap<String, String>) {
     }
 }
 
-fn should_ignore(path: &str) -> bool {
+// Write content into the working tree; None deletes the file
+pub fn write_file(path: &str, content: Option<&[u8]>) -> Result<(), String> {
+    match content {
+        Some(c) => {
+            if let Some(parent) = Path::new(path).parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create directory for {}: {}", path, e))?;
+            }
+            fs::write(path, c).map_err(|e| format!("Failed to write {}: {}", path, e))
+        }
+        None => {
+            if Path::new(path).exists() {
+                fs::remove_file(path).map_err(|e| format!("Failed to remove {}: {}", path, e))?;
+            }
+            Ok(())
+        }
+    }
+}
+
+pub fn should_ignore(path: &str) -> bool {
     let ignore_list = [".ark", "target", ".git", ".env"];
     ignore_list.iter().any(|i| path.contains(i))
 }
 
 fn hash_file(path: &Path) -> String {
+    let content = fs::read(path).unwrap_or_default();
+    hash_content(&content)
+}
+
+// Content ids are sha256, hex encoded
+pub fn hash_content(content: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+
+    Sha256::digest(content).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Snapshots saved by older versions hold 64-bit DefaultHasher ids in
+// decimal; a file still matches one of those if its content is unchanged
+fn legacy_match(old_hash: &str, path: &str) -> bool {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
 
-    let content = fs::read(path).unwrap_or_default();
+    if old_hash.len() == 64 {
+        return false;
+    }
+
+    let Ok(content) = fs::read(path) else { return false };
     let mut hasher = DefaultHasher::new();
-    content.hash(&mut hasher);
-    hasher.finish().to_string()
+    content.as_slice().hash(&mut hasher);
+    hasher.finish().to_string() == old_hash
 }
@@ -0,0 +1,174 @@
+use std::fs;
+use std::collections::HashMap;
+use chrono::{Local, NaiveDateTime, Offset, TimeZone};
+use crate::core::{branch, commit, object};
+use crate::git::git_wrapper::{self, GitError};
+
+// Which Ark commit became which git commit, one "<ark-id> <git-sha>" pair
+// per line, so every sync only replays what is new
+pub const MAP_FILE: &str = ".ark/git-map";
+
+pub fn load_map() -> HashMap<String, String> {
+    fs::read_to_string(MAP_FILE)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| l.split_once(' '))
+        .map(|(ark, git)| (ark.trim().to_string(), git.trim().to_string()))
+        .collect()
+}
+
+pub fn record(ark_id: &str, sha: &str) -> Result<(), String> {
+    use std::io::Write;
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(MAP_FILE)
+        .map_err(|e| format!("Failed to open {}: {}", MAP_FILE, e))?;
+
+    writeln!(file, "{} {}", ark_id, sha)
+        .map_err(|e| format!("Failed to write {}: {}", MAP_FILE, e))
+}
+
+// Commits of a branch that have not been replayed into git yet
+pub fn pending(branch_name: &str) -> Vec<String> {
+    let map = load_map();
+    branch::load_branch(branch_name)
+        .map(|b| b.commit_ids)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|id| !map.contains_key(id))
+        .collect()
+}
+
+// Git commit a new git branch for an Ark branch should start from: its
+// latest exported commit, or the exported commit it was created from
+pub fn start_point(branch_name: &str) -> Option<String> {
+    let map = load_map();
+    let b = branch::load_branch(branch_name).ok()?;
+    b.commit_ids.iter().rev()
+        .chain(b.base.iter())
+        .find_map(|id| map.get(id).cloned())
+}
+
+// Replay every unexported commit of an Ark branch onto the git branch of
+// the same name and move that branch. Each git commit applies the Ark
+// commit's changes on top of the git branch, so history pulled from the
+// remote is kept. Returns the number of commits written.
+pub fn export_branch(branch_name: &str, extra_trailers: &[String]) -> Result<usize, GitError> {
+    let mut map = load_map();
+    let mut tip = git_wrapper::rev_parse(&format!("refs/heads/{}", branch_name));
+    let mut count = 0;
+
+    for id in pending(branch_name) {
+        let c = commit::load_commit(&id)?;
+
+        let first = match tip.clone() {
+            Some(t) => Some(t),
+            None => match commit::parent_id(&c) {
+                Some(p) => Some(export_commit(&p, &mut map, &mut count)?),
+                None => None,
+            },
+        };
+
+        // Move the branch after every commit so a failure part way through
+        // never leaves recorded commits unreachable from it
+        let sha = replay(&c, first, &mut map, &mut count, extra_trailers)?;
+        git_wrapper::update_branch(branch_name, &sha)?;
+        tip = Some(sha);
+    }
+
+    Ok(count)
+}
+
+// Export a commit that is not on the branch being exported, e.g. the
+// other side of a merge, on top of its own Ark parent
+fn export_commit(id: &str, map: &mut HashMap<String, String>, count: &mut usize) -> Result<String, GitError> {
+    if let Some(sha) = map.get(id) {
+        return Ok(sha.clone());
+    }
+
+    let c = commit::load_commit(id)?;
+    let first = match commit::parent_id(&c) {
+        Some(p) => Some(export_commit(&p, map, count)?),
+        None => None,
+    };
+
+    replay(&c, first, map, count, &[])
+}
+
+fn replay(
+    c: &commit::Commit,
+    first_parent: Option<String>,
+    map: &mut HashMap<String, String>,
+    count: &mut usize,
+    extra_trailers: &[String],
+) -> Result<String, GitError> {
+    let mut parents: Vec<String> = first_parent.iter().cloned().collect();
+    if let Some(merged) = &c.merge_parent {
+        let sha = export_commit(merged, map, count)?;
+        if !parents.contains(&sha) {
+            parents.push(sha);
+        }
+    }
+
+    let previous = commit::parent_id(c)
+        .and_then(|id| commit::load_commit(&id).ok())
+        .map(|p| p.files_snapshot)
+        .unwrap_or_default();
+
+    let mut paths: Vec<&String> = previous.keys().chain(c.files_snapshot.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut updates = Vec::new();
+    for path in paths {
+        let git_path = path.trim_start_matches("./").to_string();
+        match (previous.get(path), c.files_snapshot.get(path)) {
+            (old, Some(hash)) if old != Some(hash) => match object::load(hash) {
+                Some(content) => updates.push((git_path, Some(git_wrapper::hash_object(&content)?))),
+                None => eprintln!("  ⚠ {} in {}: content not retained; keeping the previous version", git_path, c.id),
+            },
+            (Some(_), None) => updates.push((git_path, None)),
+            _ => {}
+        }
+    }
+
+    let tree = git_wrapper::write_tree(parents.first().map(|p| p.as_str()), &updates)?;
+
+    // Overridden secret findings travel with the commit for auditing
+    let mut allowed = c.allowed_secrets.clone();
+    for finding in extra_trailers {
+        if updates.iter().any(|(p, _)| finding.starts_with(&format!("{}:", p)) || finding.starts_with(&format!("./{}:", p)))
+            && !allowed.contains(finding)
+        {
+            allowed.push(finding.clone());
+        }
+    }
+    let message = crate::cli::push::commit_message(&c.message, &allowed);
+
+    let identity = split_identity(c.author.as_deref());
+    let authorship = git_wrapper::Authorship {
+        author: identity.as_ref().map(|(n, e)| (n.as_str(), e.as_str())),
+        time: git_time(&c.timestamp),
+    };
+
+    let sha = git_wrapper::commit_tree(&tree, &parents, &message, &authorship)?;
+    record(&c.id, &sha)?;
+    map.insert(c.id.clone(), sha.clone());
+    *count += 1;
+    Ok(sha)
+}
+
+// Ark timestamps are local time; git wants unix seconds and the UTC
+// offset in minutes
+fn git_time(timestamp: &str) -> Option<(i64, i32)> {
+    let naive = NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S").ok()?;
+    let local = Local.from_local_datetime(&naive).earliest()?;
+    Some((local.timestamp(), local.offset().fix().local_minus_utc() / 60))
+}
+
+fn split_identity(author: Option<&str>) -> Option<(String, String)> {
+    let (name, rest) = author?.split_once('<')?;
+    Some((name.trim().to_string(), rest.trim_end_matches('>').trim().to_string()))
+}
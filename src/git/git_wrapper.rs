@@ -1,85 +1,447 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
 use std::process::Command;
+use git2::build::CheckoutBuilder;
+use git2::{BranchType, ErrorClass, ErrorCode, Index, IndexEntry, IndexTime, Oid, Repository, ResetType, Signature, Time};
 
-pub struct GitResult {
-    pub success: bool,
-    pub output: String,
-}
+// Git access goes through libgit2. Only network transport (push, fetch,
+// clone) runs the `git` command, which brings the user's credential
+// helpers and SSH setup with it.
 
-fn run(args: &[&str]) -> GitResult {
-    let result = Command::new("git").args(args).output();
+#[derive(Debug)]
+pub enum GitError {
+    NotARepository,
+    RemoteNotFound(String),
+    RemoteExists(String),
+    NotFound(String),
+    // No user.name / user.email to sign commits with
+    MissingIdentity,
+    // Files that changed on both sides of a pull
+    Conflicts(Vec<String>),
+    // Switching branches would overwrite uncommitted changes
+    WouldOverwrite(String),
+    // A `git` command for network transport failed
+    Command { command: String, output: String },
+    // Reading or writing Ark's own data failed
+    Storage(String),
+    Library(git2::Error),
+}
 
-    match result {
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-            let combined = format!("{}{}", stdout, stderr).trim().to_string();
-            GitResult { success: output.status.success(), output: combined }
+impl fmt::Display for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GitError::NotARepository => write!(f, "Not a git repository."),
+            GitError::RemoteNotFound(name) => write!(f, "Remote '{}' not found.", name),
+            GitError::RemoteExists(name) => write!(f, "Remote '{}' already exists.", name),
+            GitError::NotFound(what) => write!(f, "{} not found.", what),
+            GitError::MissingIdentity => write!(f,
+                "No git identity configured. Run: git config --global user.name \"Your Name\" && git config --global user.email you@example.com"),
+            GitError::Conflicts(paths) => write!(f,
+                "Pulled changes conflict with yours in: {}. Nothing was merged.", paths.join(", ")),
+            GitError::WouldOverwrite(branch) => write!(f,
+                "Switching git to '{}' would overwrite uncommitted changes. Save or stash them first.", branch),
+            GitError::Command { command, output } => write!(f, "'{}' failed: {}", command, output),
+            GitError::Storage(message) => write!(f, "{}", message),
+            GitError::Library(e) => write!(f, "{}", e.message()),
         }
-        Err(e) => GitResult {
-            success: false,
-            output: format!("Failed to run git: {}", e),
-        },
+    }
+}
+
+impl From<git2::Error> for GitError {
+    fn from(e: git2::Error) -> Self {
+        GitError::Library(e)
+    }
+}
+
+impl From<String> for GitError {
+    fn from(message: String) -> Self {
+        GitError::Storage(message)
+    }
+}
+
+// What a pull did to the current branch
+#[derive(Debug, PartialEq)]
+pub enum PullOutcome {
+    NoRemoteHistory,
+    UpToDate,
+    FastForward,
+    Merged,
+}
+
+// Only the repository of this directory itself; an outer checkout the
+// project happens to sit in is not Ark's to touch
+pub fn open() -> Result<Repository, GitError> {
+    Repository::open(".").map_err(|_| GitError::NotARepository)
+}
+
+// Run `git` for network transport
+fn transport(args: &[&str]) -> Result<(), GitError> {
+    let command = format!("git {}", args.join(" "));
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| GitError::Command { command: command.clone(), output: e.to_string() })?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(GitError::Command { command, output: String::from_utf8_lossy(&output.stderr).trim().to_string() })
     }
 }
 
 pub fn is_git_repo() -> bool {
-    run(&["rev-parse", "--git-dir"]).success
+    open().is_ok()
+}
+
+// Whether this directory is the working tree of its repository, rather
+// than a bare repository or a .git directory
+pub fn is_worktree_root() -> bool {
+    let Ok(repo) = open() else { return false };
+    match (repo.workdir().and_then(|w| fs::canonicalize(w).ok()), fs::canonicalize(".").ok()) {
+        (Some(workdir), Some(cwd)) => workdir == cwd,
+        _ => false,
+    }
+}
+
+pub fn init() -> Result<(), GitError> {
+    let mut options = git2::RepositoryInitOptions::new();
+    options.initial_head("main");
+    Repository::init_opts(".", &options)?;
+    Ok(())
+}
+
+// Keep Ark's own data out of git through the repository's local exclude
+// file, so switching git branches never trips over .ark/HEAD
+pub fn exclude_ark() -> Result<(), GitError> {
+    let repo = open()?;
+    let path = repo.path().join("info").join("exclude");
+
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    if existing.lines().any(|l| l.trim() == ".ark/" || l.trim() == ".ark") {
+        return Ok(());
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let separator = if existing.is_empty() || existing.ends_with('\n') { "" } else { "\n" };
+    fs::write(&path, format!("{}{}.ark/\n", existing, separator))
+        .map_err(|e| GitError::Storage(format!("Failed to update git exclude file: {}", e)))
+}
+
+// Push the local branch to `remote_branch` on the remote and make git
+// track it. `force` allows replacing history the remote has.
+pub fn push(remote: &str, branch: &str, remote_branch: &str, force: bool) -> Result<(), GitError> {
+    open()?.find_remote(remote).map_err(|_| GitError::RemoteNotFound(remote.to_string()))?;
+    let refspec = format!("{}refs/heads/{}:refs/heads/{}", if force { "+" } else { "" }, branch, remote_branch);
+    transport(&["push", "--set-upstream", remote, &refspec])
+}
+
+pub fn fetch(remote: &str) -> Result<(), GitError> {
+    open()?.find_remote(remote).map_err(|_| GitError::RemoteNotFound(remote.to_string()))?;
+    transport(&["fetch", remote])
+}
+
+// Fetch `remote_branch` and merge it into the current branch. Conflicting
+// changes abort the pull before anything is written.
+pub fn pull(remote: &str, remote_branch: &str) -> Result<PullOutcome, GitError> {
+    let repo = open()?;
+    repo.find_remote(remote).map_err(|_| GitError::RemoteNotFound(remote.to_string()))?;
+
+    // ls-remote --exit-code exits with 2 when the branch doesn't exist
+    let listed = Command::new("git")
+        .args(["ls-remote", "--exit-code", "--heads", remote, remote_branch])
+        .output()
+        .map_err(|e| GitError::Command { command: "git ls-remote".to_string(), output: e.to_string() })?;
+    match listed.status.code() {
+        Some(0) => {}
+        Some(2) => return Ok(PullOutcome::NoRemoteHistory),
+        _ => return Err(GitError::Command {
+            command: format!("git ls-remote {}", remote),
+            output: String::from_utf8_lossy(&listed.stderr).trim().to_string(),
+        }),
+    }
+
+    let tracking = format!("refs/remotes/{}/{}", remote, remote_branch);
+    transport(&["fetch", remote, &format!("+refs/heads/{}:{}", remote_branch, tracking)])?;
+
+    let theirs = repo.find_reference(&tracking)?.peel_to_commit()?;
+    let annotated = repo.find_annotated_commit(theirs.id())?;
+    let (analysis, _) = repo.merge_analysis(&[&annotated])?;
+
+    if analysis.is_up_to_date() {
+        return Ok(PullOutcome::UpToDate);
+    }
+
+    let head_ref = repo.find_reference("HEAD")?
+        .symbolic_target()?
+        .map(|t| t.to_string())
+        .ok_or_else(|| GitError::NotFound("Current git branch".to_string()))?;
+
+    if analysis.is_unborn() || analysis.is_fast_forward() {
+        checkout(&repo, theirs.tree()?.as_object(), &head_ref)?;
+        repo.reference(&head_ref, theirs.id(), true, "ark pull: fast-forward")?;
+        return Ok(PullOutcome::FastForward);
+    }
+
+    let ours = repo.head()?.peel_to_commit()?;
+    let mut index = repo.merge_commits(&ours, &theirs, None)?;
+    if index.has_conflicts() {
+        let mut paths: Vec<String> = index.conflicts()?
+            .filter_map(|c| c.ok())
+            .filter_map(|c| c.our.or(c.their).or(c.ancestor))
+            .map(|e| String::from_utf8_lossy(&e.path).to_string())
+            .collect();
+        paths.dedup();
+        return Err(GitError::Conflicts(paths));
+    }
+
+    let tree = repo.find_tree(index.write_tree_to(&repo)?)?;
+    let signature = repo.signature().map_err(|_| GitError::MissingIdentity)?;
+
+    // Update the working tree first, so uncommitted changes in the way
+    // stop the pull before the merge commit exists
+    checkout(&repo, tree.as_object(), &head_ref)?;
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &format!("Merge {}/{}", remote, remote_branch),
+        &tree,
+        &[&ours, &theirs],
+    )?;
+
+    Ok(PullOutcome::Merged)
 }
 
-pub fn init() -> GitResult {
-    let result = run(&["init", "-b", "main"]);
-    if result.success { result } else {
-        let r = run(&["init"]);
-        run(&["checkout", "-b", "main"]);
-        r
+// Check out a tree without overwriting uncommitted changes
+fn checkout(repo: &Repository, target: &git2::Object, branch: &str) -> Result<(), GitError> {
+    repo.checkout_tree(target, Some(CheckoutBuilder::new().safe()))
+        .map_err(|e| {
+            if e.class() == ErrorClass::Checkout || e.code() == ErrorCode::Conflict {
+                GitError::WouldOverwrite(branch.trim_start_matches("refs/heads/").to_string())
+            } else {
+                GitError::Library(e)
+            }
+        })
+}
+
+pub fn get_remote(name: &str) -> Option<String> {
+    let repo = open().ok()?;
+    let remote = repo.find_remote(name).ok()?;
+    remote.url().ok().map(|u| u.to_string())
+}
+
+// Branch git has checked out, even before its first commit; None when
+// HEAD is detached
+pub fn current_branch() -> Option<String> {
+    let repo = open().ok()?;
+    let head = repo.find_reference("HEAD").ok()?;
+    head.symbolic_target().ok()??
+        .strip_prefix("refs/heads/")
+        .map(|b| b.to_string())
+}
+
+// Check out a git branch, creating it at `start` (or the current commit)
+// if needed. Uncommitted changes are carried over, as with `git checkout`.
+pub fn checkout_branch(name: &str, start: Option<&str>) -> Result<(), GitError> {
+    let repo = open()?;
+    let refname = format!("refs/heads/{}", name);
+
+    if repo.find_branch(name, BranchType::Local).is_err() {
+        let target = match start {
+            Some(s) => Some(repo.revparse_single(s)?.peel_to_commit()?),
+            None => repo.head().ok().and_then(|h| h.peel_to_commit().ok()),
+        };
+
+        match target {
+            Some(c) => {
+                repo.branch(name, &c, false)?;
+            }
+            // No commits yet: the new branch is born with its first commit
+            None => {
+                repo.set_head(&refname)?;
+                return Ok(());
+            }
+        }
     }
+
+    let tree = repo.find_reference(&refname)?.peel_to_tree()?;
+    checkout(&repo, tree.as_object(), &refname)?;
+    repo.set_head(&refname)?;
+    Ok(())
 }
 
-pub fn add_all() -> GitResult {
-    run(&["add", "."])
+// Configured remotes as (name, url), sorted by name
+pub fn remotes() -> Vec<(String, String)> {
+    let Ok(repo) = open() else { return Vec::new() };
+    let Ok(names) = repo.remotes() else { return Vec::new() };
+
+    let mut remotes: Vec<(String, String)> = names.iter()
+        .filter_map(|n| n.ok().flatten())
+        .filter_map(|n| get_remote(n).map(|url| (n.to_string(), url)))
+        .collect();
+    remotes.sort();
+    remotes
 }
 
-pub fn commit(message: &str) -> GitResult {
-    run(&["commit", "-m", message])
+pub fn add_remote(name: &str, url: &str) -> Result<(), GitError> {
+    let repo = open()?;
+    if repo.find_remote(name).is_ok() {
+        return Err(GitError::RemoteExists(name.to_string()));
+    }
+    repo.remote(name, url)?;
+    Ok(())
 }
 
-pub fn push() -> GitResult {
-    // Try normal push first
-    let result = run(&["push", "origin", "main"]);
-    if result.success { return result; }
+pub fn remove_remote(name: &str) -> Result<(), GitError> {
+    let repo = open()?;
+    repo.find_remote(name).map_err(|_| GitError::RemoteNotFound(name.to_string()))?;
+    repo.remote_delete(name)?;
+    Ok(())
+}
 
-    // Set upstream and push
-    let result2 = run(&["push", "--set-upstream", "origin", "main"]);
-    if result2.success { return result2; }
+// Rename a remote along with its fetch refspec and tracking branches
+pub fn rename_remote(name: &str, new_name: &str) -> Result<(), GitError> {
+    let repo = open()?;
+    repo.find_remote(name).map_err(|_| GitError::RemoteNotFound(name.to_string()))?;
+    if repo.find_remote(new_name).is_ok() {
+        return Err(GitError::RemoteExists(new_name.to_string()));
+    }
+    repo.remote_rename(name, new_name)?;
+    Ok(())
+}
 
-    result2
+pub fn set_remote_url(name: &str, url: &str) -> Result<(), GitError> {
+    let repo = open()?;
+    repo.find_remote(name).map_err(|_| GitError::RemoteNotFound(name.to_string()))?;
+    repo.remote_set_url(name, url)?;
+    Ok(())
 }
 
-pub fn pull() -> GitResult {
-    // Try pull with rebase to avoid merge conflicts
-    let result = run(&["pull", "origin", "main", "--allow-unrelated-histories", "--no-rebase"]);
-    if result.success { return result; }
+pub fn clone(url: &str, folder: &str) -> Result<(), GitError> {
+    transport(&["clone", url, folder])
+}
+
+// The configured git identity as "Name <email>"
+pub fn identity() -> Option<String> {
+    let config = match open() {
+        Ok(repo) => repo.config().ok()?,
+        Err(_) => git2::Config::open_default().ok()?,
+    };
+    let name = config.get_string("user.name").ok()?;
+    let email = config.get_string("user.email").ok()?;
+    if name.is_empty() { None } else { Some(format!("{} <{}>", name, email)) }
+}
+
+// Commit a revision points at, if it exists
+pub fn rev_parse(rev: &str) -> Option<String> {
+    let repo = open().ok()?;
+    let commit = repo.revparse_single(rev).ok()?.peel_to_commit().ok()?;
+    Some(commit.id().to_string())
+}
+
+pub fn hash_object(content: &[u8]) -> Result<String, GitError> {
+    Ok(open()?.blob(content)?.to_string())
+}
+
+// Write a tree made of `base`'s tree with some paths replaced by blobs
+// (Some) or removed (None), without touching the real index
+pub fn write_tree(base: Option<&str>, updates: &[(String, Option<String>)]) -> Result<String, GitError> {
+    let repo = open()?;
+    let mut index = Index::new()?;
+
+    if let Some(commit) = base {
+        index.read_tree(&repo.revparse_single(commit)?.peel_to_tree()?)?;
+    }
 
-    // If empty repo, just continue
-    if result.output.contains("couldn't find remote ref") 
-    || result.output.contains("no tracking information")
-    || result.output.contains("does not have") {
-        return GitResult { success: true, output: "No remote history yet.".to_string() };
+    for (path, blob) in updates {
+        // Paths git already has keep their mode; new ones take it from disk
+        let mode = index.get_path(Path::new(path), 0)
+            .map(|e| e.mode)
+            .unwrap_or_else(|| file_mode(path));
+        match blob {
+            Some(sha) => index.add(&IndexEntry {
+                ctime: IndexTime::new(0, 0),
+                mtime: IndexTime::new(0, 0),
+                dev: 0,
+                ino: 0,
+                mode,
+                uid: 0,
+                gid: 0,
+                file_size: 0,
+                id: Oid::from_str(sha)?,
+                flags: 0,
+                flags_extended: 0,
+                path: path.as_bytes().to_vec(),
+            })?,
+            None => {
+                let _ = index.remove_path(Path::new(path));
+            }
+        }
+    }
+
+    Ok(index.write_tree_to(&repo)?.to_string())
+}
+
+// Git mode for a file in the working tree: executable or not
+fn file_mode(path: &str) -> u32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o111 != 0) {
+            return 0o100755;
+        }
     }
+    0o100644
+}
 
-    result
+// Who wrote a commit and when: (name, email) and (unix seconds, UTC
+// offset in minutes). The committer is the configured identity.
+pub struct Authorship<'a> {
+    pub author: Option<(&'a str, &'a str)>,
+    pub time: Option<(i64, i32)>,
 }
 
-pub fn get_remote() -> Option<String> {
-    let result = run(&["remote", "get-url", "origin"]);
-    if result.success { Some(result.output) } else { None }
+pub fn commit_tree(tree: &str, parents: &[String], message: &str, authorship: &Authorship) -> Result<String, GitError> {
+    let repo = open()?;
+    let sign = |name: &str, email: &str| match authorship.time {
+        Some((seconds, offset)) => Signature::new(name, email, &Time::new(seconds, offset)),
+        None => Signature::now(name, email),
+    };
+
+    let configured = repo.signature().ok();
+    let committer = match (&configured, authorship.author) {
+        (Some(c), _) => sign(c.name().unwrap_or(""), c.email().unwrap_or(""))?,
+        (None, Some((name, email))) => sign(name, email)?,
+        (None, None) => return Err(GitError::MissingIdentity),
+    };
+    let author = match authorship.author {
+        Some((name, email)) => sign(name, email)?,
+        None => committer.clone(),
+    };
+
+    let tree = repo.find_tree(Oid::from_str(tree)?)?;
+    let parents = parents.iter()
+        .map(|p| repo.find_commit(Oid::from_str(p)?))
+        .collect::<Result<Vec<_>, git2::Error>>()?;
+    let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+
+    Ok(repo.commit(None, &author, &committer, message, &tree, &parent_refs)?.to_string())
 }
 
-#[allow(dead_code)]
-pub fn set_remote(url: &str) -> GitResult {
-    run(&["remote", "add", "origin", url])
+pub fn update_branch(name: &str, sha: &str) -> Result<(), GitError> {
+    open()?.reference(&format!("refs/heads/{}", name), Oid::from_str(sha)?, true, "ark: export")?;
+    Ok(())
 }
 
-pub fn clone(url: &str, folder: &str) -> GitResult {
-    run(&["clone", url, folder])
+// Point the index at HEAD again after moving the branch under it,
+// leaving the working tree alone
+pub fn reset_index() -> Result<(), GitError> {
+    let repo = open()?;
+    let head = repo.head()?.peel(git2::ObjectType::Commit)?;
+    repo.reset(&head, ResetType::Mixed, None)?;
+    Ok(())
 }
@@ -0,0 +1,321 @@
+use std::fs;
+use std::collections::HashMap;
+use chrono::{DateTime, Local};
+use colored::Colorize;
+use git2::{BranchType, ObjectType, Oid, Repository, Sort, TreeWalkMode, TreeWalkResult};
+use crate::core::{branch, commit, object, tracker};
+use crate::cli::tag;
+use crate::git::{export, git_wrapper::{self, GitError}};
+
+// Import git history into Ark. Each git commit becomes an Ark commit whose
+// id is the start of its sha, and is recorded in the git map as soon as it
+// is written, so an interrupted import picks up where it stopped and later
+// pushes don't export it again.
+
+const PROGRESS_EVERY: usize = 200;
+
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub commits: usize,
+    pub branches: usize,
+    pub tags: usize,
+}
+
+struct Importer {
+    repo: Repository,
+    // git sha -> Ark commit id
+    map: HashMap<String, String>,
+    // git blob sha -> Ark object hash
+    blobs: HashMap<Oid, String>,
+    // Merge commits whose second parent has no Ark commit yet
+    merges: Vec<(String, Oid)>,
+    imported: usize,
+    total: usize,
+}
+
+// Ark keeps branches as flat files, so "feature/login" becomes "feature-login"
+pub fn branch_name(git_name: &str) -> String {
+    git_name.replace('/', "-")
+}
+
+// `first_parent` imports only each branch's first-parent line; otherwise
+// the side lines of merges are imported too, as merged-<sha> branches
+pub fn import(first_parent: bool) -> Result<Summary, GitError> {
+    let mut summary = Summary::default();
+    let repo = git_wrapper::open()?;
+    if !git_wrapper::is_worktree_root() || repo.head().is_err() {
+        return Ok(summary);
+    }
+
+    git_wrapper::exclude_ark()?;
+
+    let total = {
+        let mut walk = repo.revwalk()?;
+        walk.push_glob("heads")?;
+        walk.push_glob("remotes")?;
+        walk.push_glob("tags")?;
+        walk.count()
+    };
+
+    let mut importer = Importer {
+        repo,
+        map: export::load_map().into_iter().map(|(ark, git)| (git, ark)).collect(),
+        blobs: HashMap::new(),
+        merges: Vec::new(),
+        imported: 0,
+        total,
+    };
+
+    let branches = importer.git_branches()?;
+    for (name, tip) in &branches {
+        importer.import_line(name, *tip)?;
+    }
+    summary.branches = branches.len();
+
+    // Link merges to their second parent, importing side lines first
+    while !importer.merges.is_empty() {
+        let merges = std::mem::take(&mut importer.merges);
+        for (id, parent) in merges {
+            let sha = parent.to_string();
+            if !first_parent && !importer.map.contains_key(&sha) {
+                importer.import_line(&format!("merged-{}", &sha[..7]), parent)?;
+                summary.branches += 1;
+            }
+            if let Some(parent_id) = importer.map.get(&sha).cloned() {
+                let mut c = commit::load_commit(&id)?;
+                c.merge_parent = Some(parent_id);
+                commit::write_commit(&c)?;
+            }
+        }
+    }
+
+    summary.tags = importer.import_tags()?;
+    summary.commits = importer.imported;
+
+    if let Some((name, _)) = branches.first() {
+        branch::set_current_branch(name)?;
+    }
+
+    Ok(summary)
+}
+
+impl Importer {
+    // Local branches, then remote-tracking branches without a local one,
+    // with the checked-out branch first
+    fn git_branches(&self) -> Result<Vec<(String, Oid)>, GitError> {
+        let current = git_wrapper::current_branch();
+        let mut branches: Vec<(String, Oid)> = Vec::new();
+
+        for kind in [BranchType::Local, BranchType::Remote] {
+            for entry in self.repo.branches(Some(kind))? {
+                let (b, _) = entry?;
+                let Some(full) = b.name()? else { continue };
+                let short = match kind {
+                    BranchType::Local => full,
+                    BranchType::Remote => match full.split_once('/') {
+                        Some((_, name)) if name != "HEAD" => name,
+                        _ => continue,
+                    },
+                };
+                let Ok(tip) = b.get().peel_to_commit().map(|c| c.id()) else { continue };
+
+                let name = branch_name(short);
+                if branches.iter().any(|(n, _)| *n == name) {
+                    continue;
+                }
+                if kind == BranchType::Local && current.as_deref() == Some(short) {
+                    branches.insert(0, (name, tip));
+                } else {
+                    branches.push((name, tip));
+                }
+            }
+        }
+
+        Ok(branches)
+    }
+
+    // Import the first-parent line ending at `tip` as Ark branch `name`.
+    // Commits already imported on other branches become the branch's base.
+    fn import_line(&mut self, name: &str, tip: Oid) -> Result<(), GitError> {
+        let mut b = branch::load_branch(name).unwrap_or(branch::Branch {
+            name: name.to_string(),
+            commit_ids: Vec::new(),
+            base: None,
+            upstream: None,
+        });
+
+        let mut walk = self.repo.revwalk()?;
+        walk.push(tip)?;
+        walk.simplify_first_parent()?;
+        walk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+        let line: Vec<Oid> = walk.collect::<Result<_, _>>()?;
+
+        for oid in line {
+            let sha = oid.to_string();
+            let existing = self.map.get(&sha).and_then(|id| commit::load_commit(id).ok());
+
+            let c = match existing {
+                Some(c) => {
+                    if c.branch != name {
+                        if b.commit_ids.is_empty() {
+                            b.base = Some(c.id.clone());
+                        }
+                    } else if !b.commit_ids.contains(&c.id) {
+                        b.commit_ids.push(c.id.clone());
+                        branch::save_branch(&b)?;
+                    }
+                    c
+                }
+                None => {
+                    let c = self.import_commit(oid, name)?;
+                    if !b.commit_ids.contains(&c.id) {
+                        b.commit_ids.push(c.id.clone());
+                    }
+                    branch::save_branch(&b)?;
+                    c
+                }
+            };
+
+            let git_commit = self.repo.find_commit(oid)?;
+            if git_commit.parent_count() > 1 && c.merge_parent.is_none() {
+                self.merges.push((c.id.clone(), git_commit.parent_id(1)?));
+            }
+        }
+
+        branch::save_branch(&b)?;
+
+        // The branch's latest snapshot, so the working tree shows as unchanged
+        if let Some(id) = b.commit_ids.last().or(b.base.as_ref()) {
+            let c = commit::load_commit(id)?;
+            let json = serde_json::to_string_pretty(&c.files_snapshot)
+                .map_err(|e| format!("Failed to serialize snapshot: {}", e))?;
+            fs::write(format!(".ark/snapshots/{}.json", name), json)
+                .map_err(|e| format!("Failed to write snapshot: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    fn import_commit(&mut self, oid: Oid, branch_name: &str) -> Result<commit::Commit, GitError> {
+        let sha = oid.to_string();
+        let git_commit = self.repo.find_commit(oid)?;
+        let author = git_commit.author();
+
+        let parent = match git_commit.parent_count() {
+            0 => None,
+            _ => self.map.get(&git_commit.parent_id(0)?.to_string()).cloned(),
+        };
+        let merge_parent = if git_commit.parent_count() > 1 {
+            self.map.get(&git_commit.parent_id(1)?.to_string()).cloned()
+        } else {
+            None
+        };
+
+        let c = commit::Commit {
+            id: sha[..16].to_string(),
+            message: String::from_utf8_lossy(git_commit.message_bytes()).trim_end().to_string(),
+            timestamp: local_time(author.when().seconds()),
+            branch: branch_name.to_string(),
+            files_snapshot: snapshot(&self.repo, &mut self.blobs, &git_commit.tree()?)?,
+            allowed_secrets: Vec::new(),
+            parent,
+            merge_parent,
+            author: Some(format!("{} <{}>",
+                String::from_utf8_lossy(author.name_bytes()),
+                String::from_utf8_lossy(author.email_bytes()))),
+        };
+
+        commit::write_commit(&c)?;
+        export::record(&c.id, &sha)?;
+        self.map.insert(sha, c.id.clone());
+
+        self.imported += 1;
+        if self.imported.is_multiple_of(PROGRESS_EVERY) {
+            println!("  {} {}/{}", "imported".dimmed(), self.imported, self.total);
+        }
+
+        Ok(c)
+    }
+
+    // Git tags on imported commits become Ark tags
+    fn import_tags(&self) -> Result<usize, GitError> {
+        fs::create_dir_all(".ark/tags")
+            .map_err(|e| format!("Failed to create tags directory: {}", e))?;
+
+        let names = self.repo.tag_names(None)?;
+        let mut count = 0;
+
+        for git_name in names.iter().filter_map(|n| n.ok().flatten()) {
+            let object = self.repo.revparse_single(&format!("refs/tags/{}", git_name))?;
+            let Ok(target) = object.peel_to_commit() else { continue };
+            let Some(commit_id) = self.map.get(&target.id().to_string()) else { continue };
+
+            let name = branch_name(git_name);
+            let path = format!(".ark/tags/{}.json", name);
+            if std::path::Path::new(&path).exists() {
+                continue;
+            }
+
+            // Annotated tags carry their own message and date
+            let (message, created) = match object.as_tag() {
+                Some(t) => (
+                    t.message().ok().flatten().unwrap_or("").trim().to_string(),
+                    t.tagger().map(|s| s.when().seconds()).unwrap_or(target.time().seconds()),
+                ),
+                None => (String::new(), target.time().seconds()),
+            };
+
+            let t = tag::Tag {
+                name,
+                commit_id: commit_id.clone(),
+                message,
+                created_at: local_time(created),
+            };
+
+            let json = serde_json::to_string_pretty(&t)
+                .map_err(|e| format!("Failed to serialize tag: {}", e))?;
+            fs::write(&path, json).map_err(|e| format!("Failed to write tag: {}", e))?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+}
+
+// Every file in a commit's tree, stored in the object store
+fn snapshot(repo: &Repository, blobs: &mut HashMap<Oid, String>, tree: &git2::Tree) -> Result<HashMap<String, String>, GitError> {
+    // Submodules and symlinks have no content of their own
+    let mut files: Vec<(String, Oid)> = Vec::new();
+    tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+        if entry.kind() == Some(ObjectType::Blob) && entry.filemode() != 0o120000 {
+            files.push((format!("./{}{}", dir, String::from_utf8_lossy(entry.name_bytes())), entry.id()));
+        }
+        TreeWalkResult::Ok
+    })?;
+
+    let mut snapshot = HashMap::new();
+    for (path, blob) in files {
+        if tracker::should_ignore(&path) {
+            continue;
+        }
+
+        let hash = match blobs.get(&blob) {
+            Some(h) => h.clone(),
+            None => {
+                let hash = object::store(repo.find_blob(blob)?.content())?;
+                blobs.insert(blob, hash.clone());
+                hash
+            }
+        };
+        snapshot.insert(path, hash);
+    }
+
+    Ok(snapshot)
+}
+
+// Unix seconds as an Ark timestamp
+fn local_time(seconds: i64) -> String {
+    DateTime::from_timestamp(seconds, 0)
+        .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
@@ -1 +1,3 @@
+pub mod export;
 pub mod git_wrapper;
+pub mod import;
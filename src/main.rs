@@ -21,25 +21,83 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Start tracking a project
-    Start,
+    Start {
+        /// When importing git history, follow only each branch's first parent
+        #[arg(long)]
+        first_parent: bool,
+    },
     /// Save your changes
     Save {
         message: Option<String>,
+        /// Save even if the secret scanner finds something
+        #[arg(long)]
+        allow_secrets: bool,
+        /// Run an AI review first and refuse to save on findings at or above this severity
+        #[arg(long)]
+        fail_on: Option<String>,
     },
     /// Check current status
     Check,
     /// View history
     History,
     /// Sync with remote (pull + push)
-    Sync,
+    Sync {
+        /// Push even if the secret scanner finds something
+        #[arg(long)]
+        allow_secrets: bool,
+        /// Remote to use instead of the branch's upstream or the default remote
+        #[arg(long)]
+        remote: Option<String>,
+    },
     /// Push changes to GitHub
-    Push,
+    Push {
+        /// Push even if the secret scanner finds something
+        #[arg(long)]
+        allow_secrets: bool,
+        /// Remote to use instead of the branch's upstream or the default remote
+        #[arg(long)]
+        remote: Option<String>,
+        /// Replace the remote branch even if it has saves this one doesn't
+        #[arg(long)]
+        force: bool,
+    },
     /// Pull changes from GitHub
-    Pull,
+    Pull {
+        /// Remote to use instead of the branch's upstream or the default remote
+        #[arg(long)]
+        remote: Option<String>,
+        /// With an Ark remote, replace diverged local saves with the remote's
+        #[arg(long)]
+        force: bool,
+    },
+    /// Fetch saves, branches and tags from a remote without changing yours
+    Fetch {
+        /// Remote to use instead of the branch's upstream or the default remote
+        #[arg(long)]
+        remote: Option<String>,
+    },
     /// Undo last save
     Undo,
+    /// Import the git history of this directory (resumes if interrupted)
+    Import {
+        /// Follow only each branch's first parent
+        #[arg(long)]
+        first_parent: bool,
+    },
+    /// Check every saved message against the rules in .ark/lint.json
+    LintHistory,
     /// Scan for secrets
-    Scan,
+    Scan {
+        /// Scan every saved version instead of the working tree
+        #[arg(long)]
+        history: bool,
+        /// Output format: text, json or sarif
+        #[arg(long, default_value = "text")]
+        format: String,
+        /// Show matched secrets instead of masking them
+        #[arg(long)]
+        reveal: bool,
+    },
     /// Show project info
     Info,
     /// Manage branches
@@ -47,15 +105,64 @@ enum Commands {
         action: String,
         name: Option<String>,
         new_name: Option<String>,
+        /// For upstream: stop tracking a remote branch
+        #[arg(long)]
+        unset: bool,
     },
     /// AI powered features
     Ai {
         action: String,
+        /// Arguments for the action, e.g. the question for `ark ai ask`
+        args: Vec<String>,
+        /// Save and push even if the secret scanner finds something
+        #[arg(long)]
+        allow_secrets: bool,
+        /// Print the exact prompt that would be sent, without sending it
+        #[arg(long)]
+        show_prompt: bool,
+        /// Review output format: text, json or sarif
+        #[arg(long, default_value = "text")]
+        format: String,
+        /// Only show review findings at or above this severity (low, medium, high, critical)
+        #[arg(long)]
+        min_severity: Option<String>,
+        /// Exit with an error if the review finds issues at or above this severity
+        #[arg(long)]
+        fail_on: Option<String>,
+        /// AI provider for setup: groq, openai, ollama or anthropic
+        #[arg(long)]
+        provider: Option<String>,
+        /// Model name for setup (defaults per provider)
+        #[arg(long)]
+        model: Option<String>,
+        /// Custom API endpoint for setup, e.g. an OpenAI-compatible server
+        #[arg(long)]
+        base_url: Option<String>,
+        /// For fix: apply the proposed patches after per-hunk confirmation
+        #[arg(long)]
+        apply: bool,
+        /// For fix: undo the last applied fix
+        #[arg(long)]
+        revert: bool,
+        /// For fix --apply: accept every hunk without asking
+        #[arg(long, short = 'y')]
+        yes: bool,
+        /// For prompts: write the default templates to .ark/prompts/
+        #[arg(long)]
+        init: bool,
+        /// Always ask the provider, ignoring cached answers
+        #[arg(long)]
+        no_cache: bool,
     },
-    /// Manage remote repository
+    /// Manage remotes: add, list, remove, rename, set-url, default
     Remote {
         action: String,
-        url: Option<String>,
+        name: Option<String>,
+        /// URL for add and set-url, new name for rename
+        value: Option<String>,
+        /// For default: go back to origin
+        #[arg(long)]
+        unset: bool,
     },
     /// Show changes/diff
     Diff {
@@ -63,12 +170,24 @@ enum Commands {
     },
     /// Merge a branch into current branch
     Merge {
-        branch: String,
+        branch: Option<String>,
+        /// Message for the merge save
+        #[arg(short, long)]
+        message: Option<String>,
+        /// Finish a merge after resolving its conflicts
+        #[arg(long = "continue")]
+        cont: bool,
+        /// Stop a merge with conflicts and restore the files it changed
+        #[arg(long)]
+        abort: bool,
     },
     /// Clone a remote repository
     Clone {
         url: String,
         dir: Option<String>,
+        /// Import only each branch's first-parent history
+        #[arg(long)]
+        first_parent: bool,
     },
     /// Manage version tags
     Tag {
@@ -92,15 +211,20 @@ fn main() {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Start => cli::start::run(),
-        Commands::Save { message } => cli::save::run(message),
+        Commands::Start { first_parent } => cli::start::run(first_parent),
+        Commands::Save { message, allow_secrets, fail_on } => {
+            cli::save::run(message, allow_secrets, fail_on.as_deref());
+        }
         Commands::Check => cli::check::run(),
         Commands::History => cli::history::run(),
-        Commands::Sync => cli::sync::run(),
-        Commands::Push => cli::push::run(),
-        Commands::Pull => cli::pull::run(),
+        Commands::Sync { allow_secrets, remote } => cli::sync::run(allow_secrets, remote.as_deref()),
+        Commands::Push { allow_secrets, remote, force } => cli::push::run(allow_secrets, remote.as_deref(), force),
+        Commands::Pull { remote, force } => cli::pull::run(remote.as_deref(), force),
+        Commands::Fetch { remote } => cli::fetch::run(remote.as_deref()),
         Commands::Undo => cli::undo::run(),
-        Commands::Scan => cli::scan::run(),
+        Commands::Scan { history, format, reveal } => cli::scan::run(history, &format, reveal),
+        Commands::LintHistory => cli::lint::run(),
+        Commands::Import { first_parent } => cli::import::run(first_parent),
         Commands::Info => {
             match repo::load_config() {
                 Ok(config) => {
@@ -111,23 +235,42 @@ fn main() {
                 Err(e) => eprintln!("{}", e),
             }
         }
-        Commands::Branch { action, name, new_name } => {
-            cli::branch::run(&action, name.as_deref(), new_name.as_deref());
+        Commands::Branch { action, name, new_name, unset } => {
+            cli::branch::run(&action, name.as_deref(), new_name.as_deref(), unset);
         }
-        Commands::Ai { action } => {
-            cli::ai::run(&action);
+        Commands::Ai {
+            action, args, allow_secrets, show_prompt, format, min_severity, fail_on, provider, model, base_url,
+            apply, revert, yes, init, no_cache,
+        } => {
+            let options = cli::ai::AiOptions {
+                args,
+                allow_secrets,
+                show_prompt,
+                format,
+                min_severity,
+                fail_on,
+                provider,
+                model,
+                base_url,
+                apply,
+                revert,
+                yes,
+                init,
+                no_cache,
+            };
+            cli::ai::run(&action, &options);
         }
-        Commands::Remote { action, url } => {
-            cli::remote::run(&action, url.as_deref());
+        Commands::Remote { action, name, value, unset } => {
+            cli::remote::run(&action, name.as_deref(), value.as_deref(), unset);
         }
         Commands::Diff { commit_id } => {
             cli::diff::run(commit_id.as_deref());
         }
-        Commands::Merge { branch } => {
-            cli::merge::run(&branch);
+        Commands::Merge { branch, message, cont, abort } => {
+            cli::merge::run(branch.as_deref(), message.as_deref(), cont, abort);
         }
-        Commands::Clone { url, dir } => {
-            cli::clone::run(&url, dir.as_deref());
+        Commands::Clone { url, dir, first_parent } => {
+            cli::clone::run(&url, dir.as_deref(), first_parent);
         }
         Commands::Tag { action, name, message } => {
             cli::tag::run(&action, name.as_deref(), message.as_deref());
@@ -1 +1,2 @@
 pub mod scanner;
+pub mod report;
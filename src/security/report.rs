@@ -0,0 +1,115 @@
+use serde_json::{json, Value};
+use crate::core::tracker;
+use crate::security::scanner::{self, ScanResult, PATTERNS};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFORMATION_URI: &str = "https://github.com/sumitt-wayne/Ark";
+
+// Stable identity of a finding: survives the line moving within the file.
+// Built from the redacted line so it is the same with or without --reveal.
+pub fn fingerprint(result: &ScanResult) -> String {
+    let line = scanner::redact_line(&result.line);
+    let key = format!("{}|{}|{}", result.rule_id, normalize_path(&result.file), line);
+    tracker::hash_content(key.as_bytes())
+}
+
+pub fn to_json(results: &[ScanResult]) -> String {
+    let findings: Vec<Value> = results.iter().map(|r| {
+        json!({
+            "rule_id": r.rule_id,
+            "issue": r.issue,
+            "file": normalize_path(&r.file),
+            "line": r.line_number,
+            "column": r.column,
+            "code": r.line,
+            "commit": r.commit,
+            "fingerprint": fingerprint(r),
+        })
+    }).collect();
+
+    let doc = json!({
+        "tool": "ark",
+        "version": env!("CARGO_PKG_VERSION"),
+        "results": findings,
+    });
+
+    serde_json::to_string_pretty(&doc).unwrap_or_default()
+}
+
+pub fn to_sarif(results: &[ScanResult]) -> String {
+    let rule_ids = unique_rule_ids();
+
+    let rules: Vec<Value> = rule_ids.iter().map(|id| {
+        let (_, pattern, issue) = PATTERNS.iter()
+            .find(|(rule_id, _, _)| rule_id == id)
+            .copied()
+            .unwrap_or_default();
+
+        json!({
+            "id": id,
+            "name": id,
+            "shortDescription": { "text": issue },
+            "fullDescription": { "text": format!("Line contains '{}', which often indicates a hardcoded credential.", pattern) },
+            "defaultConfiguration": { "level": "error" },
+            "properties": { "tags": ["security", "secrets"] },
+        })
+    }).collect();
+
+    let sarif_results: Vec<Value> = results.iter().map(|r| {
+        let mut result = json!({
+            "ruleId": r.rule_id,
+            "ruleIndex": rule_ids.iter().position(|id| *id == r.rule_id).unwrap_or(0),
+            "level": "error",
+            "message": { "text": r.issue },
+            "locations": [{
+                "physicalLocation": {
+                    "artifactLocation": { "uri": normalize_path(&r.file) },
+                    "region": {
+                        "startLine": r.line_number,
+                        "startColumn": r.column,
+                    },
+                },
+            }],
+            "partialFingerprints": { "arkFindingHash/v1": fingerprint(r) },
+        });
+
+        if let Some(commit) = &r.commit {
+            result["properties"] = json!({ "commit": commit });
+        }
+
+        result
+    }).collect();
+
+    let doc = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "ark",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": INFORMATION_URI,
+                    "rules": rules,
+                },
+            },
+            "results": sarif_results,
+        }],
+    });
+
+    serde_json::to_string_pretty(&doc).unwrap_or_default()
+}
+
+fn unique_rule_ids() -> Vec<&'static str> {
+    let mut ids: Vec<&'static str> = Vec::new();
+    for (id, _, _) in PATTERNS {
+        if !ids.contains(id) {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+// SARIF artifact URIs are relative to the project root
+fn normalize_path(path: &str) -> String {
+    path.trim_start_matches("./").replace('\\', "/")
+}
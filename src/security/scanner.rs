@@ -1,29 +1,44 @@
 use std::fs;
 use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use crate::core::object;
 
 pub struct ScanResult {
     pub file: String,
     pub line_number: usize,
     pub line: String,
     pub issue: String,
+    pub rule_id: String,
+    // 1-based column of the matched pattern
+    pub column: usize,
+    // Commit (or stash) that first introduced the finding, for history scans
+    pub commit: Option<String>,
 }
 
-const PATTERNS: &[(&str, &str)] = &[
-    ("password", "Possible password found"),
-    ("passwd", "Possible password found"),
-    ("secret", "Possible secret found"),
-    ("api_key", "Possible API key found"),
-    ("apikey", "Possible API key found"),
-    ("access_token", "Possible access token found"),
-    ("auth_token", "Possible auth token found"),
-    ("private_key", "Possible private key found"),
-    ("aws_access_key_id", "AWS Access Key found"),
-    ("aws_secret_access_key", "AWS Secret Key found"),
-    ("database_url", "Database URL found"),
-    ("db_password", "Database password found"),
-    ("bearer", "Bearer token found"),
-    ("BEGIN RSA PRIVATE KEY", "RSA Private Key found"),
-    ("BEGIN OPENSSH PRIVATE KEY", "SSH Private Key found"),
+pub struct HistoryScan {
+    pub results: Vec<ScanResult>,
+    pub blobs_scanned: usize,
+    // Files whose content was saved before Ark retained file contents
+    pub blobs_missing: usize,
+}
+
+// (rule id, pattern, issue)
+pub const PATTERNS: &[(&str, &str, &str)] = &[
+    ("password", "password", "Possible password found"),
+    ("password", "passwd", "Possible password found"),
+    ("generic-secret", "secret", "Possible secret found"),
+    ("api-key", "api_key", "Possible API key found"),
+    ("api-key", "apikey", "Possible API key found"),
+    ("access-token", "access_token", "Possible access token found"),
+    ("auth-token", "auth_token", "Possible auth token found"),
+    ("private-key", "private_key", "Possible private key found"),
+    ("aws-access-key-id", "aws_access_key_id", "AWS Access Key found"),
+    ("aws-secret-access-key", "aws_secret_access_key", "AWS Secret Key found"),
+    ("database-url", "database_url", "Database URL found"),
+    ("database-password", "db_password", "Database password found"),
+    ("bearer-token", "bearer", "Bearer token found"),
+    ("rsa-private-key", "BEGIN RSA PRIVATE KEY", "RSA Private Key found"),
+    ("ssh-private-key", "BEGIN OPENSSH PRIVATE KEY", "SSH Private Key found"),
 ];
 
 // Only scan files that commonly contain secrets
@@ -39,6 +54,21 @@ pub fn scan_files() -> Vec<ScanResult> {
     results
 }
 
+// Scan only the given files, e.g. the ones about to be saved or pushed
+pub fn scan_paths(paths: &[String]) -> Vec<ScanResult> {
+    let mut results = Vec::new();
+
+    for p in paths {
+        let path = Path::new(p);
+        if should_ignore(p) || !path.is_file() || !is_scannable(path) {
+            continue;
+        }
+        scan_file(path, &mut results);
+    }
+
+    results
+}
+
 fn scan_dir(dir: &Path, results: &mut Vec<ScanResult>) {
     let entries = match fs::read_dir(dir) {
         Ok(e) => e,
@@ -62,14 +92,51 @@ fn scan_dir(dir: &Path, results: &mut Vec<ScanResult>) {
 }
 
 fn is_scannable(path: &Path) -> bool {
+    // .env, .env.local, .env.production, ... are always scanned
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+    if name.starts_with(".env") {
+        return true;
+    }
+
     match path.extension() {
         Some(ext) => SCANNABLE_EXTENSIONS.contains(&ext.to_string_lossy().as_ref()),
-        // Also scan files with no extension like .env, Makefile
-        None => {
-            let name = path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
-            name.starts_with(".env") || name == "makefile"
+        // Also scan files with no extension like Makefile
+        None => name == "makefile",
+    }
+}
+
+// Scan every saved version of every file. Sources are (label, snapshot)
+// pairs in chronological order; each distinct content is scanned once and
+// attributed to the first source that contains it.
+pub fn scan_snapshots(sources: &[(String, HashMap<String, String>)]) -> HistoryScan {
+    let mut scan = HistoryScan { results: Vec::new(), blobs_scanned: 0, blobs_missing: 0 };
+    let mut seen: HashSet<String> = HashSet::new();
+
+    for (label, snapshot) in sources {
+        let mut paths: Vec<&String> = snapshot.keys().collect();
+        paths.sort();
+
+        for path in paths {
+            let hash = &snapshot[path];
+            if should_ignore(path) || !is_scannable(Path::new(path)) || !seen.insert(hash.clone()) {
+                continue;
+            }
+
+            match object::load(hash) {
+                Some(bytes) => {
+                    scan.blobs_scanned += 1;
+                    let before = scan.results.len();
+                    scan_content(path, &String::from_utf8_lossy(&bytes), &mut scan.results);
+                    for r in &mut scan.results[before..] {
+                        r.commit = Some(label.clone());
+                    }
+                }
+                None => scan.blobs_missing += 1,
+            }
         }
     }
+
+    scan
 }
 
 fn scan_file(path: &Path, results: &mut Vec<ScanResult>) {
@@ -78,16 +145,23 @@ fn scan_file(path: &Path, results: &mut Vec<ScanResult>) {
         Err(_) => return,
     };
 
+    scan_content(&path.to_string_lossy(), &content, results);
+}
+
+fn scan_content(file: &str, content: &str, results: &mut Vec<ScanResult>) {
     for (line_number, line) in content.lines().enumerate() {
         let line_lower = line.to_lowercase();
 
-        for (pattern, issue) in PATTERNS {
-            if line_lower.contains(&pattern.to_lowercase()) {
+        for (rule_id, pattern, issue) in PATTERNS {
+            if let Some(index) = line_lower.find(&pattern.to_lowercase()) {
                 results.push(ScanResult {
-                    file: path.to_string_lossy().to_string(),
+                    file: file.to_string(),
                     line_number: line_number + 1,
                     line: line.trim().to_string(),
                     issue: issue.to_string(),
+                    rule_id: rule_id.to_string(),
+                    column: line_lower[..index].chars().count() + 1,
+                    commit: None,
                 });
                 break;
             }
@@ -95,6 +169,130 @@ fn scan_file(path: &Path, results: &mut Vec<ScanResult>) {
     }
 }
 
+// Mask the value following every rule match, keeping the first 4 chars,
+// e.g. `API_KEY=sk-1234567890` becomes `API_KEY=sk-1****`.
+// Lines without a match are returned unchanged.
+pub fn redact_line(line: &str) -> String {
+    replace_secrets(line, |value, _| mask(value))
+}
+
+// Replace every secret value in free text with a placeholder naming the
+// rule, e.g. `[REDACTED:api-key]`. Returns the text and the number of
+// values replaced.
+pub fn scrub_text(text: &str) -> (String, usize) {
+    let mut count = 0;
+    let scrubbed: Vec<String> = text.split('\n').map(|line| {
+        replace_secrets(line, |_, rule_id| {
+            count += 1;
+            format!("[REDACTED:{}]", rule_id)
+        })
+    }).collect();
+
+    (scrubbed.join("\n"), count)
+}
+
+// Files whose content should never leave the machine, whatever is in them
+pub fn is_sensitive_file(path: &str) -> bool {
+    let name = Path::new(path).file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+
+    name.starts_with(".env")
+        || name.starts_with("id_rsa")
+        || name.starts_with("id_ed25519")
+        || name.starts_with("credentials")
+        || name.starts_with("secrets.")
+        || [".npmrc", ".pypirc", ".netrc", ".htpasswd"].contains(&name.as_str())
+        || [".pem", ".key", ".p12", ".pfx", ".keystore", ".jks"].iter().any(|ext| name.ends_with(ext))
+}
+
+fn replace_secrets<F>(line: &str, mut replacement: F) -> String
+where
+    F: FnMut(&str, &str) -> String,
+{
+    let mut spans = secret_spans(line);
+    if spans.is_empty() {
+        return line.to_string();
+    }
+
+    spans.sort();
+    let mut replaced = String::new();
+    let mut last = 0;
+
+    for (start, end, rule_id) in spans {
+        if start < last {
+            continue;
+        }
+        replaced.push_str(&line[last..start]);
+        replaced.push_str(&replacement(&line[start..end], rule_id));
+        last = end;
+    }
+
+    replaced.push_str(&line[last..]);
+    replaced
+}
+
+// Byte ranges of the values following each rule match on a line
+fn secret_spans(line: &str) -> Vec<(usize, usize, &'static str)> {
+    let lower = line.to_ascii_lowercase();
+    let bytes = line.as_bytes();
+    let mut spans = Vec::new();
+
+    for (rule_id, pattern, _) in PATTERNS {
+        let pattern = pattern.to_ascii_lowercase();
+        let mut from = 0;
+
+        while let Some(index) = lower[from..].find(&pattern) {
+            let mut pos = from + index + pattern.len();
+
+            // Rest of the key name, e.g. API_KEY_PROD
+            while pos < bytes.len() && (bytes[pos].is_ascii_alphanumeric() || matches!(bytes[pos], b'_' | b'-')) {
+                pos += 1;
+            }
+            // Separators between key and value
+            while pos < bytes.len() && matches!(bytes[pos], b' ' | b'\t' | b'=' | b':' | b'"' | b'\'' | b'`') {
+                pos += 1;
+            }
+
+            // A quoted value runs to the closing quote, spaces and all
+            let start = pos;
+            let quote = start.checked_sub(1)
+                .map(|i| bytes[i])
+                .filter(|b| matches!(b, b'"' | b'\'' | b'`'));
+            match quote {
+                Some(quote) => {
+                    while pos < bytes.len() && bytes[pos] != quote {
+                        pos += 1;
+                    }
+                }
+                _ => {
+                    while pos < bytes.len() && !matches!(bytes[pos], b' ' | b'\t' | b'"' | b'\'' | b'`' | b',' | b';') {
+                        pos += 1;
+                    }
+                }
+            }
+
+            if pos > start {
+                spans.push((start, pos, *rule_id));
+            }
+            from = pos.max(from + index + pattern.len());
+        }
+    }
+
+    spans
+}
+
+pub fn redact(results: &mut [ScanResult]) {
+    for r in results.iter_mut() {
+        r.line = redact_line(&r.line);
+    }
+}
+
+fn mask(value: &str) -> String {
+    if value.chars().count() <= 4 {
+        return "****".to_string();
+    }
+    format!("{}****", value.chars().take(4).collect::<String>())
+}
+
 fn should_ignore(path: &str) -> bool {
     let ignore_list = [".ark", "target", ".git"];
     ignore_list.iter().any(|i| path.contains(i))
@@ -1,6 +1,9 @@
 use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpListener;
 use std::process::Command;
 use std::path::Path;
+use std::thread;
 
 fn ark_cmd(dir: &str, args: &[&str]) -> std::process::Output {
     // Use the compiled binary directly
@@ -10,19 +13,99 @@ fn ark_cmd(dir: &str, args: &[&str]) -> std::process::Output {
     Command::new(binary)
         .args(args)
         .current_dir(dir)
+        .env("ARK_CONFIG_DIR", user_config_dir(dir))
+        .env_remove("ARK_AI_API_KEY")
+        .env_remove("ARK_AI_PASSPHRASE")
         .output()
         .expect("Failed to run ark binary. Run 'cargo build' first.")
 }
 
+// Run ark with the given text on stdin
+fn ark_cmd_input(dir: &str, args: &[&str], input: &str) -> std::process::Output {
+    let binary = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("target/debug/ark");
+
+    let mut child = Command::new(binary)
+        .args(args)
+        .current_dir(dir)
+        .env("ARK_CONFIG_DIR", user_config_dir(dir))
+        .env_remove("ARK_AI_API_KEY")
+        .env_remove("ARK_AI_PASSPHRASE")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to run ark binary. Run 'cargo build' first.");
+
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+// Per-test stand-in for the per-user config directory
+fn user_config_dir(dir: &str) -> String {
+    format!("{}-user", dir)
+}
+
 fn setup(test_name: &str) -> String {
     let dir = format!("/tmp/ark_test_{}", test_name);
     let _ = fs::remove_dir_all(&dir);
+    let _ = fs::remove_dir_all(user_config_dir(&dir));
     fs::create_dir_all(&dir).unwrap();
     dir
 }
 
 fn cleanup(dir: &str) {
     let _ = fs::remove_dir_all(dir);
+    let _ = fs::remove_dir_all(user_config_dir(dir));
+}
+
+// (status, extra headers, body)
+type MockResponse = (u16, Vec<(&'static str, String)>, String);
+
+// Minimal HTTP server answering each connection with the next canned
+// (status, headers, body) response. Returns the base URL and a handle
+// yielding the raw requests received.
+fn mock_server(responses: Vec<MockResponse>) -> (String, thread::JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let mut requests = Vec::new();
+
+        for (status, headers, body) in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut raw = Vec::new();
+            let mut buf = [0u8; 4096];
+
+            // Read headers, then the body by Content-Length
+            loop {
+                let n = stream.read(&mut buf).unwrap();
+                if n == 0 { break; }
+                raw.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&raw).to_string();
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text.lines()
+                        .find(|l| l.to_lowercase().starts_with("content-length:"))
+                        .and_then(|l| l[15..].trim().parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if raw.len() >= end + 4 + length { break; }
+                }
+            }
+            requests.push(String::from_utf8_lossy(&raw).to_string());
+
+            let mut response = format!("HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n", status, body.len());
+            for (name, value) in headers {
+                response.push_str(&format!("{}: {}\r\n", name, value));
+            }
+            response.push_str("\r\n");
+            response.push_str(&body);
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+
+        requests
+    });
+
+    (url, handle)
 }
 
 #[test]
@@ -489,3 +572,1681 @@ fn test_pull_without_remote() {
 
     cleanup(&dir);
 }
+
+// Run git in `dir`, returning trimmed stdout
+fn git(dir: &str, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("Failed to run git");
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+#[test]
+fn test_push_keeps_file_modes() {
+    let dir = setup("push_file_modes");
+    let remote = format!("{}-remote.git", dir);
+    let _ = fs::remove_dir_all(&remote);
+    git("/tmp", &["init", "--bare", "-q", &remote]);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["remote", "add", &remote]);
+    git(&dir, &["config", "user.name", "Ark Test"]);
+    git(&dir, &["config", "user.email", "ark@example.com"]);
+
+    fs::write(format!("{}/a.txt", dir), "a").unwrap();
+    fs::write(format!("{}/run.sh", dir), "#!/bin/sh\necho hi\n").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(format!("{}/run.sh", dir), fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    ark_cmd(&dir, &["save", "feat: add run script"]);
+    ark_cmd(&dir, &["push"]);
+
+    let tree = git(&dir, &["ls-tree", "origin/main"]);
+    assert!(tree.contains("100644 blob") && tree.contains("a.txt"));
+    #[cfg(unix)]
+    assert!(tree.lines().any(|l| l.starts_with("100755") && l.ends_with("run.sh")), "{}", tree);
+
+    // Later versions keep the mode git already has
+    fs::write(format!("{}/run.sh", dir), "#!/bin/sh\necho bye\n").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(format!("{}/run.sh", dir), fs::Permissions::from_mode(0o644)).unwrap();
+    }
+    ark_cmd(&dir, &["save", "fix: say bye"]);
+    ark_cmd(&dir, &["push"]);
+    #[cfg(unix)]
+    assert!(git(&dir, &["ls-tree", "origin/main"]).lines().any(|l| l.starts_with("100755") && l.ends_with("run.sh")));
+
+    cleanup(&dir);
+    let _ = fs::remove_dir_all(&remote);
+}
+
+#[test]
+fn test_push_retry_after_failed_export() {
+    let dir = setup("push_retry_export");
+    let remote = format!("{}-remote.git", dir);
+    let _ = fs::remove_dir_all(&remote);
+    git("/tmp", &["init", "--bare", "-q", &remote]);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["remote", "add", &remote]);
+    git(&dir, &["config", "user.name", "Ark Test"]);
+    git(&dir, &["config", "user.email", "ark@example.com"]);
+
+    fs::write(format!("{}/a.txt", dir), "v0").unwrap();
+    ark_cmd(&dir, &["save", "feat: start"]);
+    ark_cmd(&dir, &["push"]);
+
+    for (i, msg) in ["feat: first", "feat: second", "feat: third"].iter().enumerate() {
+        fs::write(format!("{}/a.txt", dir), format!("v{}", i + 1)).unwrap();
+        ark_cmd(&dir, &["save", msg]);
+    }
+
+    // Break the last save so the export stops part way through
+    let broken = fs::read_dir(format!("{}/.ark/commits", dir)).unwrap()
+        .map(|e| e.unwrap().path())
+        .find(|p| fs::read_to_string(p).unwrap().contains("feat: third"))
+        .unwrap();
+    let saved = fs::read_to_string(&broken).unwrap();
+    fs::write(&broken, "{").unwrap();
+    let output = ark_cmd(&dir, &["push"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Error exporting"), "{}", stderr);
+
+    fs::write(&broken, saved).unwrap();
+    ark_cmd(&dir, &["push"]);
+
+    let log = git(&dir, &["log", "--format=%s", "origin/main"]);
+    assert_eq!(log.lines().collect::<Vec<_>>(), vec!["feat: third", "feat: second", "feat: first", "feat: start"], "{}", log);
+
+    cleanup(&dir);
+    let _ = fs::remove_dir_all(&remote);
+}
+
+#[test]
+fn test_push_ignores_outer_git_checkout() {
+    let outer = setup("push_outer_checkout");
+    let dir = format!("{}/project", outer);
+    let remote = format!("{}-remote.git", outer);
+    let _ = fs::remove_dir_all(&remote);
+    git("/tmp", &["init", "--bare", "-q", &remote]);
+
+    git(&outer, &["init", "-q", "-b", "main"]);
+    git(&outer, &["config", "user.name", "Ark Test"]);
+    git(&outer, &["config", "user.email", "ark@example.com"]);
+    fs::write(format!("{}/outer.txt", outer), "outer").unwrap();
+    git(&outer, &["add", "outer.txt"]);
+    git(&outer, &["commit", "-q", "-m", "outer commit"]);
+
+    fs::create_dir_all(&dir).unwrap();
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["remote", "add", &remote]);
+    fs::write(format!("{}/a.txt", dir), "a").unwrap();
+    ark_cmd(&dir, &["save", "feat: inner"]);
+    ark_cmd(&dir, &["push"]);
+
+    // The project gets a repository of its own; the outer one is untouched
+    assert!(Path::new(&format!("{}/.git", dir)).exists());
+    assert_eq!(git(&outer, &["log", "--format=%s"]).trim(), "outer commit");
+    assert_eq!(git(&dir, &["log", "--format=%s", "origin/main"]).trim(), "feat: inner");
+
+    cleanup(&outer);
+    let _ = fs::remove_dir_all(&remote);
+}
+
+#[test]
+fn test_push_follows_current_branch_and_upstream() {
+    let dir = setup("push_branches");
+    let remote = format!("{}-remote.git", dir);
+    let _ = fs::remove_dir_all(&remote);
+    git("/tmp", &["init", "--bare", "-q", &remote]);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["remote", "add", &remote]);
+    git(&dir, &["config", "user.name", "Ark Test"]);
+    git(&dir, &["config", "user.email", "ark@example.com"]);
+
+    fs::write(format!("{}/a.txt", dir), "a").unwrap();
+    ark_cmd(&dir, &["save", "feat: add a"]);
+    let output = ark_cmd(&dir, &["push"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Pushed successfully"));
+
+    // A feature branch goes to the same-named git branch and remembers it
+    ark_cmd(&dir, &["branch", "new", "feature"]);
+    ark_cmd(&dir, &["branch", "go", "feature"]);
+    fs::write(format!("{}/b.txt", dir), "b").unwrap();
+    ark_cmd(&dir, &["save", "feat: add b"]);
+    ark_cmd(&dir, &["push"]);
+    assert_eq!(git(&dir, &["branch", "--show-current"]), "feature");
+    let output = ark_cmd(&dir, &["branch", "upstream"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("origin/feature"));
+
+    // A configured upstream branch overrides the name
+    ark_cmd(&dir, &["branch", "upstream", "origin", "release"]);
+    fs::write(format!("{}/c.txt", dir), "c").unwrap();
+    ark_cmd(&dir, &["save", "feat: add c"]);
+    ark_cmd(&dir, &["push"]);
+
+    let refs = git(&dir, &["ls-remote", "--heads", &remote]);
+    assert!(refs.contains("refs/heads/main"));
+    assert!(refs.contains("refs/heads/feature"));
+    assert!(refs.contains("refs/heads/release"));
+    // Ark's own data stays out of git
+    assert_eq!(git(&dir, &["ls-tree", "-r", "--name-only", "origin/main"]), "a.txt");
+
+    let output = ark_cmd(&dir, &["pull", "--remote", "backup"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Remote 'backup' not found"));
+
+    cleanup(&dir);
+    let _ = fs::remove_dir_all(&remote);
+}
+
+#[test]
+fn test_sync_replays_saves_as_git_commits() {
+    let dir = setup("sync_export");
+    let remote = format!("{}-remote.git", dir);
+    let _ = fs::remove_dir_all(&remote);
+    git("/tmp", &["init", "--bare", "-q", &remote]);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["remote", "add", &remote]);
+    git(&dir, &["config", "user.name", "Ark Test"]);
+    git(&dir, &["config", "user.email", "ark@example.com"]);
+
+    fs::write(format!("{}/a.txt", dir), "a\n").unwrap();
+    ark_cmd(&dir, &["save", "feat: add a"]);
+    ark_cmd(&dir, &["branch", "new", "feature"]);
+    ark_cmd(&dir, &["branch", "go", "feature"]);
+    fs::write(format!("{}/b.txt", dir), "b\n").unwrap();
+    ark_cmd(&dir, &["save", "feat: add b"]);
+    ark_cmd(&dir, &["branch", "go", "main"]);
+    fs::write(format!("{}/a.txt", dir), "a\nmore\n").unwrap();
+    ark_cmd(&dir, &["save", "fix: extend a"]);
+    ark_cmd(&dir, &["merge", "feature"]);
+
+    let output = ark_cmd(&dir, &["sync"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Pushed successfully"));
+
+    // Every save keeps its message and author, and the merge has two parents
+    let log = git(&dir, &["log", "--format=%s|%an|%p", "origin/main"]);
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("merge: feature into main|Ark Test|"));
+    assert_eq!(lines[0].rsplit('|').next().unwrap().split(' ').count(), 2);
+    assert!(log.contains("fix: extend a|Ark Test"));
+    assert!(log.contains("feat: add b|Ark Test"));
+    assert!(log.contains("feat: add a|Ark Test"));
+    assert_eq!(git(&dir, &["show", "origin/main:a.txt"]), "a\nmore");
+
+    let first_sha = git(&dir, &["rev-list", "--max-parents=0", "origin/main"]);
+    let map = fs::read_to_string(format!("{}/.ark/git-map", dir)).unwrap();
+    assert_eq!(map.lines().count(), 4);
+    assert!(map.contains(&first_sha));
+
+    // Later syncs only replay new saves
+    fs::write(format!("{}/c.txt", dir), "c\n").unwrap();
+    ark_cmd(&dir, &["save", "docs: add c"]);
+    let output = ark_cmd(&dir, &["push"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Exported 1 commit(s)"));
+    assert_eq!(git(&dir, &["log", "-1", "--format=%s", "origin/main"]), "docs: add c");
+    assert_eq!(git(&dir, &["status", "--porcelain"]), "");
+
+    cleanup(&dir);
+    let _ = fs::remove_dir_all(&remote);
+}
+
+#[test]
+fn test_import_git_history_on_start_and_clone() {
+    let dir = setup("import_git");
+    let commit = |msg: &str| git(&dir, &["-c", "user.name=Git Author", "-c", "user.email=git@example.com", "commit", "-qam", msg]);
+
+    git(&dir, &["init", "-q", "-b", "main"]);
+    fs::write(format!("{}/a.txt", dir), "a\n").unwrap();
+    git(&dir, &["add", "."]);
+    commit("first");
+    git(&dir, &["tag", "v1"]);
+    git(&dir, &["checkout", "-qb", "side"]);
+    fs::write(format!("{}/b.txt", dir), "b\n").unwrap();
+    git(&dir, &["add", "."]);
+    commit("side work");
+    git(&dir, &["checkout", "-q", "main"]);
+    fs::write(format!("{}/a.txt", dir), "a\nmore\n").unwrap();
+    commit("edit a");
+    git(&dir, &["-c", "user.name=Git Author", "-c", "user.email=git@example.com", "merge", "-q", "--no-ff", "side", "-m", "Merge side"]);
+    git(&dir, &["branch", "-qD", "side"]);
+
+    let output = ark_cmd(&dir, &["start"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Imported 4 commit(s)"), "{}", stdout);
+
+    let history = String::from_utf8_lossy(&ark_cmd(&dir, &["history"]).stdout).to_string();
+    for message in ["first", "edit a", "Merge side"] {
+        assert!(history.contains(message));
+    }
+    // The deleted branch's commits come in through the merge
+    let branches = String::from_utf8_lossy(&ark_cmd(&dir, &["branch", "list"]).stdout).to_string();
+    assert!(branches.contains("merged-"));
+    assert!(String::from_utf8_lossy(&ark_cmd(&dir, &["tag", "list"]).stdout).contains("v1"));
+    assert!(String::from_utf8_lossy(&ark_cmd(&dir, &["check"]).stdout).contains("Nothing to report"));
+
+    // Re-running skips what is already imported
+    let output = ark_cmd(&dir, &["import"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("already imported"));
+
+    // Cloning with --first-parent leaves the merged line out
+    let clone = format!("{}-clone", dir);
+    let _ = fs::remove_dir_all(&clone);
+    ark_cmd("/tmp", &["clone", &dir, &clone, "--first-parent"]);
+    let branches = String::from_utf8_lossy(&ark_cmd(&clone, &["branch", "list"]).stdout).to_string();
+    assert!(!branches.contains("merged-"));
+    let history = String::from_utf8_lossy(&ark_cmd(&clone, &["history"]).stdout).to_string();
+    assert!(history.contains("3 total saves"), "{}", history);
+
+    cleanup(&dir);
+    let _ = fs::remove_dir_all(&clone);
+}
+
+#[test]
+fn test_start_imports_only_own_checkout() {
+    let outer = setup("import_own_checkout");
+    git(&outer, &["init", "-q", "-b", "main"]);
+    fs::write(format!("{}/outer.txt", outer), "outer\n").unwrap();
+    git(&outer, &["add", "."]);
+    git(&outer, &["-c", "user.name=Git Author", "-c", "user.email=git@example.com", "commit", "-qm", "outer commit"]);
+
+    // A project inside someone else's checkout
+    let inner = format!("{}/project", outer);
+    fs::create_dir_all(&inner).unwrap();
+    let stdout = String::from_utf8_lossy(&ark_cmd(&inner, &["start"]).stdout).to_string();
+    assert!(!stdout.contains("Import"), "{}", stdout);
+    assert!(String::from_utf8_lossy(&ark_cmd(&inner, &["history"]).stdout).contains("No saves found"));
+
+    // A bare repository has no working tree to track
+    let bare = format!("{}-bare.git", outer);
+    let _ = fs::remove_dir_all(&bare);
+    git("/tmp", &["clone", "-q", "--bare", &outer, &bare]);
+    let stdout = String::from_utf8_lossy(&ark_cmd(&bare, &["start"]).stdout).to_string();
+    assert!(!stdout.contains("Import"), "{}", stdout);
+
+    cleanup(&outer);
+    let _ = fs::remove_dir_all(&bare);
+}
+
+#[test]
+fn test_save_blocked_by_secrets() {
+    let dir = setup("save_blocked_secrets");
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/config.yaml", dir), "api_key: sk-1234567890abcdef").unwrap();
+
+    let output = ark_cmd(&dir, &["save", "add config"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Blocked"));
+    assert!(stderr.contains("--allow-secrets"));
+
+    let output = ark_cmd(&dir, &["ai", "commit"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Blocked"));
+
+    let history_output = ark_cmd(&dir, &["history"]);
+    let history_stdout = String::from_utf8_lossy(&history_output.stdout);
+    assert!(history_stdout.contains("No saves found"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_save_allow_secrets_records_override() {
+    let dir = setup("save_allow_secrets");
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/config.yaml", dir), "api_key: sk-1234567890abcdef").unwrap();
+
+    let output = ark_cmd(&dir, &["save", "add config", "--allow-secrets"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("saved successfully"));
+
+    let commit_file = fs::read_dir(format!("{}/.ark/commits", dir)).unwrap()
+        .flatten()
+        .next()
+        .unwrap()
+        .path();
+    let commit_json = fs::read_to_string(commit_file).unwrap();
+    assert!(commit_json.contains("allowed_secrets"));
+    assert!(commit_json.contains("config.yaml"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_scan_history_finds_deleted_secret() {
+    let dir = setup("scan_history");
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/settings.json", dir), "{\"api_key\": \"sk-1234567890abcdef\"}").unwrap();
+    ark_cmd(&dir, &["save", "add settings", "--allow-secrets"]);
+
+    fs::remove_file(format!("{}/settings.json", dir)).unwrap();
+    fs::write(format!("{}/readme.txt", dir), "nothing here").unwrap();
+    ark_cmd(&dir, &["save", "remove settings"]);
+
+    let output = ark_cmd(&dir, &["scan"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("No secrets"));
+
+    let output = ark_cmd(&dir, &["scan", "--history"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("settings.json"));
+    assert!(stdout.contains("introduced"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_scan_history_credits_the_save_that_added_a_secret() {
+    let dir = setup("scan_history_credit");
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/settings.json", dir), "{\"api_key\": \"sk-1234567890abcdef\"}").unwrap();
+    ark_cmd(&dir, &["save", "add settings", "--allow-secrets"]);
+
+    // A branch sorting before main carries the secret along
+    ark_cmd(&dir, &["branch", "new", "alpha"]);
+    ark_cmd(&dir, &["branch", "go", "alpha"]);
+    fs::write(format!("{}/readme.txt", dir), "nothing here").unwrap();
+    ark_cmd(&dir, &["save", "add readme"]);
+
+    // Timestamps can be skewed, e.g. by an import
+    let commits: Vec<(String, String)> = fs::read_dir(format!("{}/.ark/commits", dir)).unwrap()
+        .map(|e| e.unwrap().path())
+        .map(|p| (p.file_stem().unwrap().to_string_lossy().to_string(), fs::read_to_string(&p).unwrap()))
+        .collect();
+    let (added, json) = commits.iter().find(|(_, json)| json.contains("add settings")).unwrap();
+    let mut c: serde_json::Value = serde_json::from_str(json).unwrap();
+    c["timestamp"] = "2099-01-01 00:00:00".into();
+    fs::write(format!("{}/.ark/commits/{}.json", dir, added), c.to_string()).unwrap();
+
+    let output = ark_cmd(&dir, &["scan", "--history", "--format", "json"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["results"][0]["commit"], added.as_str(), "{}", report);
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_scan_sarif_output() {
+    let dir = setup("scan_sarif");
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/secret.env", dir), "API_KEY=sk-1234567890abcdef").unwrap();
+
+    let output = ark_cmd(&dir, &["scan", "--format", "sarif"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let sarif: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+
+    assert_eq!(sarif["version"], "2.1.0");
+    let result = &sarif["runs"][0]["results"][0];
+    assert_eq!(result["ruleId"], "api-key");
+    assert_eq!(result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "secret.env");
+    assert_eq!(result["locations"][0]["physicalLocation"]["region"]["startLine"], 1);
+    assert!(result["partialFingerprints"].is_object());
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_scan_json_output() {
+    let dir = setup("scan_json");
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/secret.env", dir), "\nDB_PASSWORD=hunter2").unwrap();
+
+    let output = ark_cmd(&dir, &["scan", "--format", "json"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let report: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+
+    assert_eq!(report["results"][0]["file"], "secret.env");
+    assert_eq!(report["results"][0]["line"], 2);
+    assert_eq!(report["results"][0]["column"], 4);
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_scan_masks_secrets_by_default() {
+    let dir = setup("scan_masks");
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/secret.env", dir), "API_KEY=sk-1234567890abcdef").unwrap();
+
+    let output = ark_cmd(&dir, &["scan"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("API_KEY=sk-1****"));
+    assert!(!stdout.contains("1234567890abcdef"));
+
+    let output = ark_cmd(&dir, &["scan", "--reveal"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("sk-1234567890abcdef"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_diff_masks_secrets() {
+    let dir = setup("diff_masks");
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/settings.py", dir), "DEBUG = True\npassword = \"hunter2hunter2\"\n").unwrap();
+
+    let output = ark_cmd(&dir, &["diff"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("DEBUG = True"));
+    assert!(stdout.contains("hunt****"));
+    assert!(!stdout.contains("hunter2hunter2"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_quoted_secret_with_spaces_is_masked_whole() {
+    let dir = setup("quoted_secret");
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/db.yaml", dir), "db_password: \"correct horse battery staple\"\n").unwrap();
+
+    let output = ark_cmd(&dir, &["scan"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("db_password: \"corr****\""), "{}", stdout);
+    assert!(!stdout.contains("horse"));
+
+    let output = ark_cmd(&dir, &["ai", "diff", "--show-prompt"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[REDACTED:database-password]"));
+    assert!(!stdout.contains("battery staple"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_show_prompt_scrubs_secrets() {
+    let dir = setup("ai_show_prompt");
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/app.toml", dir), "name = \"demo\"\napi_key = \"sk-1234567890abcdef\"\n").unwrap();
+    fs::write(format!("{}/server.pem", dir), "-----BEGIN CERTIFICATE-----\nMIIBszCCAVmgAwIBAgIU\n").unwrap();
+
+    let output = ark_cmd(&dir, &["ai", "diff", "--show-prompt"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Prompt (not sent)"));
+    assert!(stdout.contains("name = \"demo\""));
+    assert!(stdout.contains("[REDACTED:api-key]"));
+    assert!(!stdout.contains("sk-1234567890abcdef"));
+    assert!(stdout.contains("server.pem"));
+    assert!(!stdout.contains("MIIBszCCAVmgAwIBAgIU"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_setup_unknown_provider() {
+    let dir = setup("ai_setup_unknown_provider");
+
+    ark_cmd(&dir, &["start"]);
+
+    let output = ark_cmd(&dir, &["ai", "setup", "--provider", "bogus"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Unknown provider"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_ollama_provider() {
+    let dir = setup("ai_ollama_provider");
+    let (url, server) = mock_server(vec![(
+        200,
+        vec![("Content-Type", "application/json".to_string())],
+        r#"{"message": {"role": "assistant", "content": "This project says hello."}, "done": true}"#.to_string(),
+    )]);
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/test.txt", dir), "hello").unwrap();
+    ark_cmd(&dir, &["save", "first save"]);
+
+    let output = ark_cmd(&dir, &["ai", "setup", "--provider", "ollama", "--model", "llama3.1", "--base-url", &url]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("AI configured successfully"));
+
+    let output = ark_cmd(&dir, &["ai", "explain"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("This project says hello."));
+
+    let requests = server.join().unwrap();
+    assert!(requests[0].starts_with("POST /api/chat"));
+    assert!(requests[0].contains("\"model\":\"llama3.1\""));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_setup_encrypts_key_outside_project() {
+    let dir = setup("ai_setup_encrypts");
+
+    ark_cmd(&dir, &["start"]);
+
+    let output = ark_cmd_input(&dir, &["ai", "setup"], "gsk_test_key_1234567890\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("AI configured successfully"));
+
+    let project_config = fs::read_to_string(format!("{}/.ark/ai_config.json", dir)).unwrap();
+    assert!(!project_config.contains("api_key"));
+
+    let credentials = fs::read_to_string(format!("{}/credentials.json", user_config_dir(&dir))).unwrap();
+    assert!(credentials.contains("ciphertext"));
+    assert!(!credentials.contains("gsk_test_key_1234567890"));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = |p: String| fs::metadata(p).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(user_config_dir(&dir)), 0o700);
+        assert_eq!(mode(format!("{}/credentials.json", user_config_dir(&dir))), 0o600);
+    }
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_legacy_key_migration() {
+    let dir = setup("ai_legacy_migration");
+    let (url, server) = mock_server(vec![(
+        200,
+        vec![("Content-Type", "application/json".to_string())],
+        r#"{"choices": [{"message": {"role": "assistant", "content": "Migrated fine."}}]}"#.to_string(),
+    )]);
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/test.txt", dir), "hello").unwrap();
+    ark_cmd(&dir, &["save", "first save"]);
+
+    // "secret" XOR 0x5A, as written by older versions
+    let legacy: String = "secret".bytes().map(|b| format!("{:02x}", b ^ 0x5A)).collect();
+    fs::write(
+        format!("{}/.ark/ai_config.json", dir),
+        format!(r#"{{"api_key": "{}", "model": "m", "base_url": "{}"}}"#, legacy, url),
+    ).unwrap();
+
+    let output = ark_cmd(&dir, &["ai", "explain"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Migrated fine."));
+
+    let requests = server.join().unwrap();
+    assert!(requests[0].to_lowercase().contains("authorization: bearer secret"));
+
+    let project_config = fs::read_to_string(format!("{}/.ark/ai_config.json", dir)).unwrap();
+    assert!(!project_config.contains("api_key"));
+    assert!(project_config.contains("groq"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_prompt_uses_line_diff() {
+    let dir = setup("ai_prompt_line_diff");
+
+    ark_cmd(&dir, &["start"]);
+    let original: Vec<String> = (1..=500).map(|i| format!("line {}", i)).collect();
+    fs::write(format!("{}/big.txt", dir), original.join("\n")).unwrap();
+    ark_cmd(&dir, &["save", "first save"]);
+
+    let mut changed = original.clone();
+    changed[399] = "changed deep inside".to_string();
+    fs::write(format!("{}/big.txt", dir), changed.join("\n")).unwrap();
+
+    let output = ark_cmd(&dir, &["ai", "diff", "--show-prompt"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("-line 400"));
+    assert!(stdout.contains("+changed deep inside"));
+    assert!(!stdout.contains("line 1\n"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_prompt_chunks_large_changes() {
+    let dir = setup("ai_prompt_chunks");
+
+    ark_cmd(&dir, &["start"]);
+    for f in 0..6 {
+        let content: Vec<String> = (0..400).map(|i| format!("file {} line {}", f, i)).collect();
+        fs::write(format!("{}/part{}.txt", dir, f), content.join("\n")).unwrap();
+    }
+
+    let output = ark_cmd(&dir, &["ai", "diff", "--show-prompt"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("summarizing in 4 parts"));
+    assert!(stdout.contains("<summary of part 4>"));
+    assert!(stdout.contains("omitted"));
+
+    cleanup(&dir);
+}
+
+// Ollama-style chat response carrying `content`
+fn ollama_reply(content: &str) -> MockResponse {
+    let body = serde_json::json!({ "message": { "role": "assistant", "content": content }, "done": true });
+    (200, vec![("Content-Type", "application/json".to_string())], body.to_string())
+}
+
+fn review_reply() -> MockResponse {
+    let review = serde_json::json!({ "findings": [
+        { "file": "app.py", "line_start": 2, "line_end": 3, "severity": "high", "category": "bug",
+          "suggestion": "Handle the empty list case." },
+        { "file": "app.py", "line_start": 1, "severity": "low", "category": "style",
+          "suggestion": "Add a docstring." },
+        { "file": "not_changed.py", "line_start": 1, "severity": "high", "suggestion": "Invented file." }
+    ]});
+    ollama_reply(&format!("```json\n{}\n```", review))
+}
+
+#[test]
+fn test_ai_review_structured() {
+    let dir = setup("ai_review_structured");
+    let (url, server) = mock_server(vec![review_reply(), review_reply(), review_reply()]);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["ai", "setup", "--provider", "ollama", "--base-url", &url]);
+    fs::write(format!("{}/app.py", dir), "def first(items):\n    return items[0]\n\n").unwrap();
+
+    let output = ark_cmd(&dir, &["ai", "review", "--min-severity", "medium"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("app.py"));
+    assert!(stdout.contains("HIGH"));
+    assert!(stdout.contains("Handle the empty list case."));
+    assert!(!stdout.contains("Add a docstring."));
+    assert!(stdout.contains("1 malformed finding(s) ignored"));
+
+    let output = ark_cmd(&dir, &["ai", "review", "--format", "sarif", "--no-cache"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let sarif: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(sarif["runs"][0]["results"].as_array().unwrap().len(), 2);
+    assert_eq!(sarif["runs"][0]["results"][0]["level"], "error");
+
+    let output = ark_cmd(&dir, &["ai", "review", "--fail-on", "high", "--no-cache"]);
+    assert!(!output.status.success());
+
+    server.join().unwrap();
+    cleanup(&dir);
+}
+
+#[test]
+fn test_save_fail_on_review() {
+    let dir = setup("save_fail_on_review");
+    let (url, server) = mock_server(vec![review_reply()]);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["ai", "setup", "--provider", "ollama", "--base-url", &url]);
+    fs::write(format!("{}/app.py", dir), "def first(items):\n    return items[0]\n\n").unwrap();
+
+    let output = ark_cmd(&dir, &["save", "add app", "--fail-on", "high"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Blocked"));
+
+    let history_output = ark_cmd(&dir, &["history"]);
+    let history_stdout = String::from_utf8_lossy(&history_output.stdout);
+    assert!(history_stdout.contains("No saves found"));
+
+    server.join().unwrap();
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_fix_apply_and_revert() {
+    let dir = setup("ai_fix_apply");
+    let original = "def first(items):\n    return items[0]\n\ndef last(items):\n    return items[-1]\n";
+    let patch = "Here you go:\n```diff\n--- a/app.py\n+++ b/app.py\n\
+        @@ -1,2 +1,4 @@\n def first(items):\n+    if not items:\n+        return None\n     return items[0]\n\
+        @@ -4,2 +6,2 @@\n def last(items):\n-    return items[-1]\n+    return items[-1] if items else None\n\
+        @@ -9,1 +11,1 @@\n-    missing_line()\n+    fixed()\n```\n";
+    let (url, server) = mock_server(vec![ollama_reply(patch)]);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["ai", "setup", "--provider", "ollama", "--base-url", &url]);
+    fs::write(format!("{}/app.py", dir), original).unwrap();
+
+    let output = ark_cmd_input(&dir, &["ai", "fix", "--apply"], "y\nn\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("hunk 3/3 does not match the current file"));
+    assert!(stdout.contains("Applied"));
+
+    let fixed = fs::read_to_string(format!("{}/app.py", dir)).unwrap();
+    assert!(fixed.contains("if not items:"));
+    assert!(fixed.contains("    return items[-1]\n"));
+
+    let output = ark_cmd(&dir, &["ai", "fix", "--revert"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Reverted"));
+    assert_eq!(fs::read_to_string(format!("{}/app.py", dir)).unwrap(), original);
+
+    let output = ark_cmd(&dir, &["ai", "fix", "--revert"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("No applied fixes to revert"));
+
+    server.join().unwrap();
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_fix_patch_removes_sql_comment_line() {
+    let dir = setup("ai_fix_sql_comment");
+    let original = "-- users table\nCREATE TABLE users (id INT);\n";
+    // The removed line reads "--- users table", like a file header
+    let patch = "```diff\n--- a/schema.sql\n+++ b/schema.sql\n\
+        @@ -1,2 +1,2 @@\n--- users table\n+-- accounts table\n CREATE TABLE users (id INT);\n```\n";
+    let (url, server) = mock_server(vec![ollama_reply(patch)]);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["ai", "setup", "--provider", "ollama", "--base-url", &url]);
+    fs::write(format!("{}/schema.sql", dir), original).unwrap();
+
+    let output = ark_cmd_input(&dir, &["ai", "fix", "--apply"], "y\n");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Applied"));
+    assert_eq!(
+        fs::read_to_string(format!("{}/schema.sql", dir)).unwrap(),
+        "-- accounts table\nCREATE TABLE users (id INT);\n"
+    );
+
+    server.join().unwrap();
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_fix_keeps_crlf_line_endings() {
+    let dir = setup("ai_fix_crlf");
+    let original = "[server]\r\nport = 80\r\nhost = localhost\r\n";
+    let patch = "```diff\n--- a/app.ini\n+++ b/app.ini\n\
+        @@ -1,3 +1,3 @@\n [server]\n-port = 80\n+port = 8080\n host = localhost\n```\n";
+    let (url, server) = mock_server(vec![ollama_reply(patch)]);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["ai", "setup", "--provider", "ollama", "--base-url", &url]);
+    fs::write(format!("{}/app.ini", dir), original).unwrap();
+
+    let output = ark_cmd_input(&dir, &["ai", "fix", "--apply"], "y\n");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Applied"));
+    assert_eq!(
+        fs::read_to_string(format!("{}/app.ini", dir)).unwrap(),
+        "[server]\r\nport = 8080\r\nhost = localhost\r\n"
+    );
+
+    server.join().unwrap();
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_prompt_templates() {
+    let dir = setup("ai_prompt_templates");
+
+    ark_cmd(&dir, &["start"]);
+    let output = ark_cmd(&dir, &["ai", "prompts", "--init"]);
+    assert!(output.status.success());
+    assert!(Path::new(&format!("{}/.ark/prompts/review.md", dir)).exists());
+
+    fs::write(
+        format!("{}/.ark/prompts/commit.md", dir),
+        "<!-- ark-prompt v1 -->\nTEAM STYLE on {{branch}}. Changed: {{files}}\nRecent: {{history}}\n{{diff}}\n",
+    ).unwrap();
+    fs::write(format!("{}/notes.md", dir), "notes\n").unwrap();
+    ark_cmd(&dir, &["save", "docs: explain the {{diff}} placeholder"]);
+    fs::write(format!("{}/app.py", dir), "print('hi {{branch}}')\n").unwrap();
+
+    let output = ark_cmd(&dir, &["ai", "commit", "--show-prompt"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("TEAM STYLE on main. Changed: new: ./app.py"));
+    // Placeholders inside values are text, not template
+    assert!(stdout.contains("Recent: - docs: explain the {{diff}} placeholder"));
+    assert!(stdout.contains("+print('hi {{branch}}')"));
+    assert!(!stdout.contains("ark-prompt"));
+
+    // The review template cannot drop the JSON contract
+    let output = ark_cmd(&dir, &["ai", "review", "--show-prompt"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\"findings\""));
+
+    let output = ark_cmd(&dir, &["ai", "prompts"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(".ark/prompts/commit.md"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_action_overrides() {
+    let dir = setup("ai_action_overrides");
+    let (url, server) = mock_server(vec![ollama_reply("docs: add readme")]);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["ai", "setup", "--provider", "ollama", "--base-url", &url]);
+
+    let config_path = format!("{}/.ark/ai_config.json", dir);
+    let mut config: serde_json::Value = serde_json::from_str(&fs::read_to_string(&config_path).unwrap()).unwrap();
+    config["actions"] = serde_json::json!({ "diff": { "model": "tiny-model", "max_tokens": 42, "temperature": 0.9 } });
+    fs::write(&config_path, config.to_string()).unwrap();
+
+    fs::write(format!("{}/README.md", dir), "# Demo\n").unwrap();
+    ark_cmd(&dir, &["ai", "diff"]);
+
+    let requests = server.join().unwrap();
+    assert!(requests[0].contains("\"model\":\"tiny-model\""));
+    assert!(requests[0].contains("\"num_predict\":42"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_retries_and_streams() {
+    let dir = setup("ai_retries_stream");
+    let sse = "data: {\"choices\":[{\"delta\":{\"content\":\"Built a \"}}]}\n\n\
+        data: {\"choices\":[{\"delta\":{\"content\":\"greeting.\"}}]}\n\n\
+        data: [DONE]\n\n";
+    let (url, server) = mock_server(vec![
+        (429, vec![("Retry-After", "0".to_string())], "slow down".to_string()),
+        (503, vec![], r#"{"error": {"message": "overloaded"}}"#.to_string()),
+        (200, vec![("Content-Type", "text/event-stream".to_string())], sse.to_string()),
+    ]);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd_input(&dir, &["ai", "setup", "--provider", "openai", "--base-url", &url], "sk-test\n");
+    fs::write(format!("{}/test.txt", dir), "hello").unwrap();
+    ark_cmd(&dir, &["save", "add greeting"]);
+
+    let output = ark_cmd(&dir, &["ai", "explain"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stdout.contains("Built a greeting."));
+    assert!(stderr.contains("retrying"));
+
+    let requests = server.join().unwrap();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[2].contains("\"stream\":true"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_error_body_and_timeout() {
+    let dir = setup("ai_error_timeout");
+    let (url, server) = mock_server(vec![
+        (401, vec![], r#"{"error": {"message": "Invalid API Key"}}"#.to_string()),
+    ]);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd_input(&dir, &["ai", "setup", "--provider", "openai", "--base-url", &url], "sk-test\n");
+    fs::write(format!("{}/test.txt", dir), "hello").unwrap();
+
+    let output = ark_cmd(&dir, &["ai", "diff"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("401"));
+    assert!(stderr.contains("Invalid API Key"));
+    assert_eq!(server.join().unwrap().len(), 1);
+
+    // A server that accepts the connection but never answers
+    let silent = TcpListener::bind("127.0.0.1:0").unwrap();
+    let config_path = format!("{}/.ark/ai_config.json", dir);
+    let mut config: serde_json::Value = serde_json::from_str(&fs::read_to_string(&config_path).unwrap()).unwrap();
+    config["base_url"] = serde_json::json!(format!("http://{}", silent.local_addr().unwrap()));
+    config["timeout_secs"] = serde_json::json!(1);
+    config["max_retries"] = serde_json::json!(0);
+    fs::write(&config_path, config.to_string()).unwrap();
+
+    let output = ark_cmd(&dir, &["ai", "diff"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("did not respond within 1s"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_cache_and_usage() {
+    let dir = setup("ai_cache_usage");
+    let body = serde_json::json!({
+        "message": { "role": "assistant", "content": "Adds a readme." },
+        "done": true, "prompt_eval_count": 120, "eval_count": 30
+    });
+    let (url, server) = mock_server(vec![(200, vec![], body.to_string())]);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["ai", "setup", "--provider", "ollama", "--base-url", &url]);
+    fs::write(format!("{}/README.md", dir), "# Demo\n").unwrap();
+
+    ark_cmd(&dir, &["ai", "diff"]);
+    // Same tree, same prompt: answered from .ark/ai_cache without a request
+    let output = ark_cmd(&dir, &["ai", "diff"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Adds a readme."));
+    assert_eq!(server.join().unwrap().len(), 1);
+
+    let output = ark_cmd(&dir, &["ai", "usage"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let row = stdout.lines().find(|l| l.contains("diff")).unwrap();
+    let columns: Vec<&str> = row.split_whitespace().collect();
+    assert_eq!(&columns[2..], &["2", "1", "120", "30"]);
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_objects_are_keyed_by_sha256() {
+    let dir = setup("objects_sha256");
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/a.txt", dir), "hello").unwrap();
+    ark_cmd(&dir, &["save", "feat: add a"]);
+    let sha256 = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+    assert_eq!(fs::read_to_string(format!("{}/.ark/objects/{}", dir, sha256)).unwrap(), "hello");
+
+    // Snapshots from older versions use 64-bit hashes and still match
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    b"hello".as_slice().hash(&mut hasher);
+    fs::write(
+        format!("{}/.ark/snapshots/main.json", dir),
+        format!(r#"{{"./a.txt": "{}"}}"#, hasher.finish()),
+    ).unwrap();
+    let output = ark_cmd(&dir, &["save", "nothing new"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Nothing to save"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_commit_records_its_parent() {
+    let dir = setup("commit_parent");
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/auth.py", dir), "SESSION_TIMEOUT = 30\n").unwrap();
+    ark_cmd(&dir, &["save", "feat: add sessions"]);
+    ark_cmd(&dir, &["branch", "new", "tune"]);
+
+    // A later save on main must not be taken for tune's parent
+    fs::write(format!("{}/auth.py", dir), "SESSION_TIMEOUT = 60\n").unwrap();
+    ark_cmd(&dir, &["save", "fix: longer sessions"]);
+    thread::sleep(std::time::Duration::from_millis(1100));
+
+    ark_cmd(&dir, &["branch", "go", "tune"]);
+    fs::write(format!("{}/auth.py", dir), "SESSION_TIMEOUT = 120\n").unwrap();
+    ark_cmd(&dir, &["save", "fix: much longer sessions"]);
+
+    let read = |name: &str| -> serde_json::Value {
+        serde_json::from_str(&fs::read_to_string(format!("{}/.ark/{}.json", dir, name)).unwrap()).unwrap()
+    };
+    let main = read("branches/main");
+    let tune = read("branches/tune");
+    let first = main["commit_ids"][0].as_str().unwrap();
+    let id = tune["commit_ids"][0].as_str().unwrap();
+    assert_eq!(read(&format!("commits/{}", id))["parent"], first);
+    assert_eq!(read(&format!("commits/{}", main["commit_ids"][1].as_str().unwrap()))["parent"], first);
+
+    // Branches from older versions don't record where they started
+    let mut legacy = tune.clone();
+    legacy.as_object_mut().unwrap().remove("base");
+    fs::write(format!("{}/.ark/branches/tune.json", dir), legacy.to_string()).unwrap();
+
+    let output = ark_cmd(&dir, &["ai", "ask", "when", "did", "the", "timeout", "change?", "--show-prompt"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("auth.py: -SESSION_TIMEOUT = 30"), "{}", stdout);
+    assert!(!stdout.contains("-SESSION_TIMEOUT = 60"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_ask_cites_relevant_commits() {
+    let dir = setup("ai_ask");
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/readme.md", dir), "# Demo\n").unwrap();
+    ark_cmd(&dir, &["save", "docs: add readme"]);
+    fs::write(format!("{}/auth.py", dir), "SESSION_TIMEOUT = 30\n").unwrap();
+    ark_cmd(&dir, &["save", "feat: add login sessions"]);
+    ark_cmd(&dir, &["branch", "new", "tune"]);
+    ark_cmd(&dir, &["branch", "go", "tune"]);
+    fs::write(format!("{}/auth.py", dir), "SESSION_TIMEOUT = 120\n").unwrap();
+    ark_cmd(&dir, &["save", "fix: users were logged out too often"]);
+
+    let branch: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(format!("{}/.ark/branches/tune.json", dir)).unwrap()).unwrap();
+    let id = branch["commit_ids"][0].as_str().unwrap().to_string();
+
+    let output = ark_cmd(&dir, &["ai", "ask", "when", "did", "the", "session", "timeout", "change?", "--show-prompt"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("[{}] fix: users were logged out too often", id)));
+    assert!(stdout.contains("auth.py: +SESSION_TIMEOUT = 120"));
+    assert!(stdout.contains("branch: tune"));
+    assert!(!stdout.contains("docs: add readme"));
+
+    let (url, server) = mock_server(vec![ollama_reply(&format!("It was raised to 120 in [{}].", id))]);
+    ark_cmd(&dir, &["ai", "setup", "--provider", "ollama", "--base-url", &url]);
+
+    let output = ark_cmd(&dir, &["ai", "ask", "when did the session timeout change?"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("It was raised to 120"));
+    assert!(stdout.contains("Sources:"));
+    assert!(Path::new(&format!("{}/.ark/ai_index.json", dir)).exists());
+
+    server.join().unwrap();
+    cleanup(&dir);
+}
+
+#[test]
+fn test_merge_conflict_continue_and_abort() {
+    let dir = setup("merge_conflict");
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/app.txt", dir), "one\ntwo\nthree\nfour\nfive\n").unwrap();
+    ark_cmd(&dir, &["save", "base"]);
+
+    ark_cmd(&dir, &["branch", "new", "feature"]);
+    ark_cmd(&dir, &["branch", "go", "feature"]);
+    fs::write(format!("{}/app.txt", dir), "one\ntwo\nthree\nfour\nFIVE\n").unwrap();
+    ark_cmd(&dir, &["save", "feature change"]);
+
+    ark_cmd(&dir, &["branch", "go", "main"]);
+    fs::write(format!("{}/app.txt", dir), "ONE\ntwo\nthree\nfour\n5\n").unwrap();
+    ark_cmd(&dir, &["save", "main change"]);
+
+    let output = ark_cmd(&dir, &["merge", "feature"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("conflicts in 1 file"));
+
+    // The change to the first line merges cleanly, the last line conflicts
+    let merged = fs::read_to_string(format!("{}/app.txt", dir)).unwrap();
+    assert!(merged.starts_with("ONE\n"));
+    assert!(merged.contains("<<<<<<< ours (main)\n5\n||||||| base\nfive\n=======\nFIVE\n>>>>>>> theirs (feature)\n"));
+
+    let output = ark_cmd(&dir, &["save", "too early"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("merge is in progress"));
+
+    let output = ark_cmd(&dir, &["merge", "--continue"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("still have conflict markers"));
+
+    let output = ark_cmd(&dir, &["merge", "--abort"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Merge aborted"));
+    assert_eq!(fs::read_to_string(format!("{}/app.txt", dir)).unwrap(), "ONE\ntwo\nthree\nfour\n5\n");
+
+    ark_cmd(&dir, &["merge", "feature"]);
+    fs::write(format!("{}/app.txt", dir), "ONE\ntwo\nthree\nfour\nFIVE\n").unwrap();
+    let output = ark_cmd(&dir, &["merge", "--continue"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Merge successful"));
+    assert!(!Path::new(&format!("{}/.ark/merge.json", dir)).exists());
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_resolve_conflicts() {
+    let dir = setup("ai_resolve");
+    let answer = serde_json::json!({
+        "resolution": "let timeout = 30;\nlet retries = 5;",
+        "explanation": "Kept the longer timeout from main and the retry count from feature."
+    }).to_string();
+    let (url, server) = mock_server(vec![ollama_reply(&answer)]);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["ai", "setup", "--provider", "ollama", "--base-url", &url]);
+    fs::write(format!("{}/config.rs", dir), "let timeout = 10;\nlet retries = 3;\n").unwrap();
+    fs::write(format!("{}/credentials.ini", dir), "host = db1.corp\n").unwrap();
+    ark_cmd(&dir, &["save", "add config"]);
+
+    ark_cmd(&dir, &["branch", "new", "feature"]);
+    ark_cmd(&dir, &["branch", "go", "feature"]);
+    fs::write(format!("{}/config.rs", dir), "let timeout = 10;\nlet retries = 5;\n").unwrap();
+    fs::write(format!("{}/credentials.ini", dir), "host = db2.corp\n").unwrap();
+    ark_cmd(&dir, &["save", "retry more often"]);
+
+    ark_cmd(&dir, &["branch", "go", "main"]);
+    fs::write(format!("{}/config.rs", dir), "let timeout = 30;\nlet retries = 3;\n").unwrap();
+    fs::write(format!("{}/credentials.ini", dir), "host = db3.corp\n").unwrap();
+    ark_cmd(&dir, &["save", "raise timeout"]);
+    ark_cmd(&dir, &["merge", "feature"]);
+
+    let output = ark_cmd(&dir, &["ai", "resolve", "config.rs", "--show-prompt"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("- [feature] retry more often"));
+    assert!(stdout.contains("Theirs (feature):\nlet timeout = 10;\nlet retries = 5;"));
+    assert!(stdout.contains("\"resolution\""));
+
+    // Sensitive files are left to the user
+    let output = ark_cmd(&dir, &["ai", "resolve", "--show-prompt"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("is a sensitive file, resolve manually"));
+    assert!(!stdout.contains("db2.corp"));
+    fs::write(format!("{}/credentials.ini", dir), "host = db3.corp\n").unwrap();
+
+    let output = ark_cmd_input(&dir, &["ai", "resolve"], "a\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Kept the longer timeout"));
+    assert!(stdout.contains("All conflicts resolved"));
+    assert_eq!(
+        fs::read_to_string(format!("{}/config.rs", dir)).unwrap(),
+        "let timeout = 30;\nlet retries = 5;\n"
+    );
+
+    let output = ark_cmd(&dir, &["merge", "--continue"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Merge successful"));
+
+    server.join().unwrap();
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_changelog_since_last_tag() {
+    let dir = setup("ai_changelog");
+    let (url, server) = mock_server(vec![ollama_reply("feat: add a password reset form")]);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["ai", "setup", "--provider", "ollama", "--base-url", &url]);
+    fs::write(format!("{}/login.html", dir), "<form>login</form>\n").unwrap();
+    ark_cmd(&dir, &["save", "feat: add login page"]);
+    ark_cmd(&dir, &["tag", "new", "v1.0"]);
+
+    fs::write(format!("{}/auth.py", dir), "def check(token):\n    return token.valid\n").unwrap();
+    ark_cmd(&dir, &["save", "fix(auth): handle expired tokens"]);
+    fs::write(format!("{}/reset.html", dir), "<form>reset password</form>\n").unwrap();
+    ark_cmd(&dir, &["save", "update"]);
+    fs::write(format!("{}/deps.txt", dir), "requests==2\n").unwrap();
+    ark_cmd(&dir, &["save", "chore: bump deps"]);
+
+    let output = ark_cmd(&dir, &["ai", "changelog"]);
+    assert!(output.status.success());
+    server.join().unwrap();
+
+    let changelog = fs::read_to_string(format!("{}/CHANGELOG.md", dir)).unwrap();
+    assert!(changelog.starts_with("# Changelog\n"));
+    assert!(changelog.contains("## [Unreleased]\n\n### Added\n\n- Add a password reset form\n\n### Fixed\n\n- **auth:** Handle expired tokens\n"));
+    assert!(!changelog.contains("login page"));
+    assert!(!changelog.contains("bump deps"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_changelog_withholds_sensitive_files() {
+    let dir = setup("ai_changelog_sensitive");
+    let (url, server) = mock_server(vec![ollama_reply("feat: add a password reset form")]);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["ai", "setup", "--provider", "ollama", "--base-url", &url]);
+    fs::write(format!("{}/login.html", dir), "<form>login</form>\n").unwrap();
+    ark_cmd(&dir, &["save", "feat: add login page"]);
+    ark_cmd(&dir, &["tag", "new", "v1.0"]);
+
+    // .env files are never tracked; credentials files are, but stay local
+    fs::write(format!("{}/reset.html", dir), "<form>reset password</form>\n").unwrap();
+    fs::write(format!("{}/credentials.ini", dir), "internal_host = db7.corp.example\n").unwrap();
+    ark_cmd(&dir, &["save", "update"]);
+
+    let output = ark_cmd(&dir, &["ai", "changelog"]);
+    assert!(output.status.success());
+    let requests = server.join().unwrap();
+    assert!(requests[0].contains("reset password"));
+    assert!(requests[0].contains("File: ./credentials.ini (content withheld: sensitive file)"));
+    assert!(!requests[0].contains("db7.corp.example"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_changelog_fallback_without_ai() {
+    let dir = setup("changelog_fallback");
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/a.txt", dir), "a").unwrap();
+    ark_cmd(&dir, &["save", "feat: first feature"]);
+    ark_cmd(&dir, &["tag", "new", "v1.0"]);
+    fs::write(format!("{}/b.txt", dir), "b").unwrap();
+    ark_cmd(&dir, &["save", "feat!: replace the config format"]);
+    fs::write(format!("{}/c.txt", dir), "c").unwrap();
+    ark_cmd(&dir, &["save", "Speed up startup"]);
+    ark_cmd(&dir, &["tag", "new", "v1.1"]);
+    fs::write(
+        format!("{}/CHANGELOG.md", dir),
+        "# Changelog\n\n## [Unreleased]\n\n### Added\n\n- Replace the config format\n\n## [1.0.0] - 2020-01-01\n\n### Added\n\n- Old entry\n",
+    ).unwrap();
+
+    let output = ark_cmd(&dir, &["ai", "changelog", "v1.0", "v1.1"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("grouping by message prefix only"));
+
+    let changelog = fs::read_to_string(format!("{}/CHANGELOG.md", dir)).unwrap();
+    assert!(changelog.contains("## [Unreleased]\n\n## [v1.1] - "));
+    assert!(changelog.contains("### Added\n\n- **Breaking:** Replace the config format\n\n### Changed\n\n- Speed up startup\n\n## [1.0.0] - 2020-01-01"));
+    assert!(changelog.contains("- Old entry\n"));
+    assert!(!changelog.contains("First feature"));
+
+    // Running it again replaces the release instead of adding a second one
+    ark_cmd(&dir, &["ai", "changelog", "v1.0", "v1.1"]);
+    let again = fs::read_to_string(format!("{}/CHANGELOG.md", dir)).unwrap();
+    assert_eq!(again, changelog);
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_split_creates_focused_saves() {
+    let dir = setup("ai_split");
+    let plan = serde_json::json!({ "groups": [
+        { "message": "feat(auth): check token expiry", "files": ["auth.py", "./tests/test_auth.py", "invented.py"] },
+        { "message": "docs: describe tokens", "files": ["README.md", "auth.py"] }
+    ]}).to_string();
+    let (url, server) = mock_server(vec![ollama_reply(&plan)]);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["ai", "setup", "--provider", "ollama", "--base-url", &url]);
+    fs::write(format!("{}/README.md", dir), "# App\n").unwrap();
+    ark_cmd(&dir, &["save", "first save"]);
+
+    fs::create_dir_all(format!("{}/tests", dir)).unwrap();
+    fs::write(format!("{}/auth.py", dir), "def expired(token):\n    return token.exp < now()\n").unwrap();
+    fs::write(format!("{}/tests/test_auth.py", dir), "def test_expired():\n    pass\n").unwrap();
+    fs::write(format!("{}/README.md", dir), "# App\n\nTokens expire after an hour.\n").unwrap();
+    fs::write(format!("{}/notes.txt", dir), "todo\n").unwrap();
+
+    let output = ark_cmd_input(&dir, &["ai", "split"], "y\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    server.join().unwrap();
+    assert!(stdout.contains("chore: save remaining changes"));
+    assert!(stdout.contains("Created 3 save(s)"));
+
+    let commits: Vec<serde_json::Value> = fs::read_dir(format!("{}/.ark/commits", dir)).unwrap()
+        .map(|e| serde_json::from_str(&fs::read_to_string(e.unwrap().path()).unwrap()).unwrap())
+        .collect();
+    let files_of = |message: &str| -> Vec<String> {
+        let c = commits.iter().find(|c| c["message"] == message).unwrap();
+        let mut files: Vec<String> = c["files_snapshot"].as_object().unwrap().keys().cloned().collect();
+        files.sort();
+        files
+    };
+
+    assert_eq!(files_of("feat(auth): check token expiry"), ["./README.md", "./auth.py", "./tests/test_auth.py"]);
+    let readme = commits.iter().find(|c| c["message"] == "feat(auth): check token expiry").unwrap()["files_snapshot"]["./README.md"].clone();
+    let first = commits.iter().find(|c| c["message"] == "first save").unwrap()["files_snapshot"]["./README.md"].clone();
+    assert_eq!(readme, first);
+    assert_eq!(files_of("chore: save remaining changes").len(), 4);
+
+    let output = ark_cmd(&dir, &["check"]);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("notes.txt"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_message_lint_on_save_merge_and_history() {
+    let dir = setup("message_lint");
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/a.txt", dir), "a").unwrap();
+    ark_cmd(&dir, &["save", "WIP first try"]);
+    fs::write(
+        format!("{}/.ark/lint.json", dir),
+        r#"{"types": ["feat", "fix", "chore"], "scopes": ["core", "cli"], "max_subject_length": 40, "no_wip_on": ["main"]}"#,
+    ).unwrap();
+
+    fs::write(format!("{}/b.txt", dir), "b").unwrap();
+    let output = ark_cmd(&dir, &["save", "added the b file"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Message rejected"));
+    assert!(stderr.contains("[types]"));
+
+    let output = ark_cmd(&dir, &["save", "feat(ui): add the b file to the project root"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("[max_subject_length] Subject is 44 characters; the limit is 40."));
+    assert!(stderr.contains("[scopes] Scope 'ui' is not allowed. Use one of: core, cli."));
+
+    let output = ark_cmd(&dir, &["save", "feat(core): add b"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("saved successfully"));
+
+    // A -m message is checked; the default merge message is not held to
+    // the type and scope rules
+    ark_cmd(&dir, &["branch", "new", "feature"]);
+    ark_cmd(&dir, &["branch", "go", "feature"]);
+    fs::write(format!("{}/c.txt", dir), "c").unwrap();
+    ark_cmd(&dir, &["save", "fix(cli): add c"]);
+    ark_cmd(&dir, &["branch", "go", "main"]);
+    let output = ark_cmd(&dir, &["merge", "feature", "-m", "merged feature"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("[types]"));
+    let output = ark_cmd(&dir, &["merge", "feature", "-m", "chore(core): merge feature"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Merge successful"));
+
+    ark_cmd(&dir, &["branch", "go", "feature"]);
+    fs::write(format!("{}/d.txt", dir), "d").unwrap();
+    ark_cmd(&dir, &["save", "fix(cli): add d"]);
+    ark_cmd(&dir, &["branch", "go", "main"]);
+    let output = ark_cmd(&dir, &["merge", "feature"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Merge successful"));
+
+    let output = ark_cmd(&dir, &["lint-history"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success());
+    assert!(stdout.contains("WIP first try"));
+    assert!(stdout.contains("[no_wip_on]"));
+    assert!(!stdout.contains("merge: feature into main"));
+    assert!(stdout.contains("1 of 6 message(s) break the rules"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_ai_commit_message_follows_lint_rules() {
+    let dir = setup("ai_commit_lint");
+    let (url, server) = mock_server(vec![
+        ollama_reply("updated the parser"),
+        ollama_reply("feat(core): parse nested lists"),
+    ]);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["ai", "setup", "--provider", "ollama", "--base-url", &url]);
+    fs::write(format!("{}/.ark/lint.json", dir), r#"{"types": ["feat", "fix"], "scopes": ["core"]}"#).unwrap();
+    fs::write(format!("{}/parser.py", dir), "def parse(items):\n    return items\n").unwrap();
+
+    let output = ark_cmd(&dir, &["ai", "commit"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let requests = server.join().unwrap();
+
+    assert!(requests[1].contains("updated the parser"));
+    assert!(requests[1].contains("(types)"));
+    assert!(stdout.contains("feat(core): parse nested lists"));
+    assert!(stdout.contains("Changes saved"));
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_offline_commit_messages() {
+    let dir = setup("offline_messages");
+
+    ark_cmd(&dir, &["start"]);
+    fs::create_dir_all(format!("{}/src/core", dir)).unwrap();
+    fs::write(format!("{}/src/core/branch.rs", dir), "pub fn create_branch(name: &str) {\n}\n").unwrap();
+    ark_cmd(&dir, &["save", "feat(core): add branches"]);
+
+    fs::write(
+        format!("{}/src/core/branch.rs", dir),
+        "pub fn create_branch(name: &str) {\n}\n\npub fn rename_branch(old: &str, new: &str) {\n}\n",
+    ).unwrap();
+    fs::create_dir_all(format!("{}/tests", dir)).unwrap();
+    fs::write(format!("{}/tests/branch_test.rs", dir), "fn renames() {}\n").unwrap();
+    let output = ark_cmd(&dir, &["save"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("message: feat(core): add rename branch"));
+
+    // Without AI, `ark ai commit` falls back to the local summary
+    fs::write(format!("{}/README.md", dir), "# Branches\n").unwrap();
+    let output = ark_cmd(&dir, &["ai", "commit"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("AI not configured"));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("docs: update README.md"));
+    assert!(stdout.contains("Changes saved"));
+
+    // Generated messages follow the length and scope rules
+    fs::write(
+        format!("{}/.ark/lint.json", dir),
+        r#"{"types": ["feat", "fix", "chore", "docs", "refactor"], "scopes": ["api", "branch"], "max_subject_length": 30}"#,
+    ).unwrap();
+    fs::write(
+        format!("{}/src/core/branch.rs", dir),
+        "pub fn create_branch(name: &str) {\n}\n\npub fn rename_branch(old: &str, new: &str) {\n}\n\npub fn delete_branch(name: &str) {\n}\n",
+    ).unwrap();
+    let output = ark_cmd(&dir, &["save"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("message: feat(branch): add delete\n"), "{}", stdout);
+
+    // A rule it can't meet is reported with a message to use instead
+    let config = fs::read_to_string(format!("{}/.ark/lint.json", dir)).unwrap();
+    fs::write(format!("{}/.ark/lint.json", dir), config.replace("}", r#", "require_issue": true}"#)).unwrap();
+    fs::write(format!("{}/README.md", dir), "# Branches\n\nRename them.\n").unwrap();
+    let output = ark_cmd(&dir, &["save"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("[require_issue]"));
+    assert!(stderr.contains("Message rejected: docs(api): update README.md"));
+    assert!(stderr.contains("Suggested: ark save \"docs(api): update (#<issue>)\""), "{}", stderr);
+
+    cleanup(&dir);
+}
+
+#[test]
+fn test_pull_fast_forwards_merges_and_stops_on_conflicts() {
+    let dir = setup("pull_outcomes");
+    let other = setup("pull_outcomes_other");
+    let remote = format!("{}-remote.git", dir);
+    let _ = fs::remove_dir_all(&remote);
+    git("/tmp", &["init", "--bare", "-q", &remote]);
+
+    for d in [&dir, &other] {
+        ark_cmd(d, &["start"]);
+        ark_cmd(d, &["remote", "add", &remote]);
+        git(d, &["config", "user.name", "Ark Test"]);
+        git(d, &["config", "user.email", "ark@example.com"]);
+    }
+
+    fs::write(format!("{}/a.txt", dir), "a\n").unwrap();
+    ark_cmd(&dir, &["save", "feat: add a"]);
+    ark_cmd(&dir, &["push"]);
+
+    let output = ark_cmd(&other, &["pull"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("fast-forward"));
+    assert_eq!(fs::read_to_string(format!("{}/a.txt", other)).unwrap(), "a\n");
+    let output = ark_cmd(&other, &["pull"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Already up to date"));
+
+    // Diverged histories are merged
+    fs::write(format!("{}/a.txt", dir), "a\nfrom dir\n").unwrap();
+    ark_cmd(&dir, &["save", "feat: extend a"]);
+    ark_cmd(&dir, &["push"]);
+    fs::write(format!("{}/b.txt", other), "b\n").unwrap();
+    git(&other, &["add", "b.txt"]);
+    git(&other, &["commit", "-qm", "add b"]);
+    let output = ark_cmd(&other, &["pull"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("merged"));
+    assert_eq!(git(&other, &["log", "-1", "--format=%p"]).split(' ').count(), 2);
+    assert_eq!(fs::read_to_string(format!("{}/a.txt", other)).unwrap(), "a\nfrom dir\n");
+
+    // Conflicting edits leave everything as it was
+    fs::write(format!("{}/a.txt", dir), "one\n").unwrap();
+    ark_cmd(&dir, &["save", "fix: rewrite a"]);
+    ark_cmd(&dir, &["push"]);
+    fs::write(format!("{}/a.txt", other), "two\n").unwrap();
+    git(&other, &["commit", "-qam", "rewrite a"]);
+    let head = git(&other, &["rev-parse", "HEAD"]);
+    let output = ark_cmd(&other, &["pull"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("conflict") && stderr.contains("a.txt"), "{}", stderr);
+    assert_eq!(git(&other, &["rev-parse", "HEAD"]), head);
+    assert_eq!(fs::read_to_string(format!("{}/a.txt", other)).unwrap(), "two\n");
+
+    cleanup(&dir);
+    cleanup(&other);
+    let _ = fs::remove_dir_all(&remote);
+}
+
+#[test]
+fn test_named_remotes() {
+    let dir = setup("named_remotes");
+    let origin = format!("{}-origin.git", dir);
+    let backup = format!("{}-backup.git", dir);
+    for r in [&origin, &backup] {
+        let _ = fs::remove_dir_all(r);
+        git("/tmp", &["init", "--bare", "-q", r]);
+    }
+
+    ark_cmd(&dir, &["start"]);
+    git(&dir, &["init", "-q", "-b", "main"]);
+    git(&dir, &["config", "user.name", "Ark Test"]);
+    git(&dir, &["config", "user.email", "ark@example.com"]);
+    fs::write(format!("{}/a.txt", dir), "a\n").unwrap();
+    ark_cmd(&dir, &["save", "feat: add a"]);
+
+    // A lone remote is the default whatever its name
+    ark_cmd(&dir, &["remote", "add", "backup", &backup]);
+    let output = ark_cmd(&dir, &["push"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Pushed successfully"));
+    assert!(git(&dir, &["ls-remote", "--heads", &backup]).contains("refs/heads/main"));
+
+    ark_cmd(&dir, &["remote", "add", &origin]);
+    let output = ark_cmd(&dir, &["remote", "add", "origin", &backup]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("ark remote set-url origin"));
+    let list = String::from_utf8_lossy(&ark_cmd(&dir, &["remote", "list"]).stdout).to_string();
+    assert!(list.contains("backup") && list.contains(&origin));
+    assert!(list.lines().any(|l| l.contains("origin") && l.contains("(default)")));
+
+    ark_cmd(&dir, &["remote", "default", "backup"]);
+    let output = ark_cmd(&dir, &["remote", "default"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("backup"));
+    let output = ark_cmd(&dir, &["remote", "default", "nowhere"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Remote 'nowhere' not found"));
+
+    // Renaming carries the default and upstreams along
+    ark_cmd(&dir, &["remote", "rename", "backup", "mirror"]);
+    let output = ark_cmd(&dir, &["branch", "upstream"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("mirror/main"));
+    let output = ark_cmd(&dir, &["remote", "default"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("mirror"));
+
+    ark_cmd(&dir, &["remote", "set-url", "mirror", &origin]);
+    assert_eq!(git(&dir, &["remote", "get-url", "mirror"]), origin);
+
+    // Removing clears them, and pushes go to origin again
+    let output = ark_cmd(&dir, &["remote", "remove", "mirror"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Cleared the upstream of 1 branch(es)"));
+    let output = ark_cmd(&dir, &["push"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("remote: origin"));
+    assert!(git(&dir, &["ls-remote", "--heads", &origin]).contains("refs/heads/main"));
+
+    cleanup(&dir);
+    for r in [&origin, &backup] {
+        let _ = fs::remove_dir_all(r);
+    }
+}
+
+#[test]
+fn test_ark_remote_over_a_path() {
+    let dir = setup("ark_remote");
+    let other = setup("ark_remote_other");
+    let store = format!("{}-shared.ark", dir);
+    let url = format!("file://{}", store);
+    let _ = fs::remove_dir_all(&store);
+
+    ark_cmd(&dir, &["start"]);
+    fs::write(format!("{}/a.txt", dir), "a\n").unwrap();
+    ark_cmd(&dir, &["save", "feat: add a"]);
+    ark_cmd(&dir, &["tag", "new", "v1", "first release"]);
+    let output = ark_cmd(&dir, &["remote", "add", "shared", &url]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Ark remote added"));
+    let output = ark_cmd(&dir, &["push"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Pushed successfully"), "{}", stdout);
+    assert!(stdout.contains("1 commit(s), 1 tag(s) sent"));
+    // No git involved
+    assert!(!Path::new(&format!("{}/.git", dir)).exists());
+
+    ark_cmd(&other, &["start"]);
+    ark_cmd(&other, &["remote", "add", "shared", &url]);
+    let output = ark_cmd(&other, &["pull"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("fast-forward"));
+    assert_eq!(fs::read_to_string(format!("{}/a.txt", other)).unwrap(), "a\n");
+    assert!(String::from_utf8_lossy(&ark_cmd(&other, &["history"]).stdout).contains("feat: add a"));
+    assert!(String::from_utf8_lossy(&ark_cmd(&other, &["tag", "list"]).stdout).contains("first release"));
+    assert!(String::from_utf8_lossy(&ark_cmd(&other, &["check"]).stdout).contains("Nothing to report"));
+
+    fs::write(format!("{}/a.txt", other), "a\nfrom other\n").unwrap();
+    ark_cmd(&other, &["save", "feat: extend a"]);
+    ark_cmd(&other, &["push"]);
+
+    // Saves the remote doesn't have are not overwritten silently
+    fs::write(format!("{}/b.txt", dir), "b\n").unwrap();
+    ark_cmd(&dir, &["save", "feat: add b"]);
+    let output = ark_cmd(&dir, &["push"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Pull first"));
+    let output = ark_cmd(&dir, &["pull"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("diverged"));
+    assert_eq!(fs::read_to_string(format!("{}/a.txt", dir)).unwrap(), "a\n");
+
+    let output = ark_cmd(&dir, &["push", "--force"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("forced"));
+    let output = ark_cmd(&other, &["pull", "--force"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("replaced local saves"));
+    assert_eq!(fs::read_to_string(format!("{}/a.txt", other)).unwrap(), "a\n");
+    assert_eq!(fs::read_to_string(format!("{}/b.txt", other)).unwrap(), "b\n");
+
+    let output = ark_cmd(&other, &["fetch"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("0 commit(s)"));
+
+    cleanup(&dir);
+    cleanup(&other);
+    let _ = fs::remove_dir_all(&store);
+}
+
+#[test]
+fn test_ark_push_locks_and_refuses_unreadable_branch() {
+    let dir = setup("ark_push_lock");
+    let store = format!("{}-shared.ark", dir);
+    let _ = fs::remove_dir_all(&store);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["remote", "add", "shared", &store]);
+    fs::write(format!("{}/a.txt", dir), "a\n").unwrap();
+    ark_cmd(&dir, &["save", "feat: add a"]);
+    ark_cmd(&dir, &["push"]);
+
+    fs::write(format!("{}/b.txt", dir), "b\n").unwrap();
+    ark_cmd(&dir, &["save", "feat: add b"]);
+
+    // A push already running holds the lock
+    fs::write(format!("{}/push.lock", store), "").unwrap();
+    let output = ark_cmd(&dir, &["push"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Another push"));
+    fs::remove_file(format!("{}/push.lock", store)).unwrap();
+
+    // A branch file that can't be parsed is not treated as missing
+    let remote_branch = format!("{}/branches/main.json", store);
+    fs::write(&remote_branch, "{ broken").unwrap();
+    let output = ark_cmd(&dir, &["push"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Failed to parse remote branch 'main'"));
+    assert_eq!(fs::read_to_string(&remote_branch).unwrap(), "{ broken");
+    assert!(!Path::new(&format!("{}/push.lock", store)).exists());
+
+    fs::remove_file(&remote_branch).unwrap();
+    let output = ark_cmd(&dir, &["push"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Pushed successfully"));
+    assert!(!Path::new(&format!("{}/branches/main.json.tmp", store)).exists());
+
+    cleanup(&dir);
+    let _ = fs::remove_dir_all(&store);
+}
+
+#[test]
+fn test_push_scans_saved_versions_not_working_tree() {
+    let dir = setup("push_scans_saves");
+    let store = format!("{}-shared.ark", dir);
+    let _ = fs::remove_dir_all(&store);
+
+    ark_cmd(&dir, &["start"]);
+    ark_cmd(&dir, &["remote", "add", "shared", &store]);
+    fs::write(format!("{}/cfg.yaml", dir), "region: eu\n").unwrap();
+    ark_cmd(&dir, &["save", "add config"]);
+
+    // The saved version holds a secret the working tree no longer has
+    for entry in fs::read_dir(format!("{}/.ark/objects", dir)).unwrap().flatten() {
+        fs::write(entry.path(), "aws_secret_access_key: abcd1234efgh5678\n").unwrap();
+    }
+
+    let output = ark_cmd(&dir, &["push"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Blocked") && stderr.contains("cfg.yaml"), "{}", stderr);
+    assert!(!Path::new(&format!("{}/branches/main.json", store)).exists());
+
+    cleanup(&dir);
+    let _ = fs::remove_dir_all(&store);
+}